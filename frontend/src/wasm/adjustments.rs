@@ -0,0 +1,806 @@
+use wasm_bindgen::prelude::*;
+use image::{DynamicImage, RgbaImage};
+use crate::image_processor::{ImageProcessor, decode, encode};
+
+/// Per-channel 8-bit lookup table
+pub(crate) type Lut = [u8; 256];
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Stretch each channel's levels independently, clipping `clip_percent`
+    /// of the darkest and brightest pixels. Also corrects color casts.
+    #[wasm_bindgen]
+    pub fn auto_enhance(&self, image_data: &[u8], clip_percent: f32) -> Result<Vec<u8>, JsValue> {
+        let mut rgba = decode(image_data)?.to_rgba8();
+        let total = (rgba.width() as u64 * rgba.height() as u64).max(1);
+
+        let mut luts = [identity_lut(); 3];
+        for (channel, lut) in luts.iter_mut().enumerate() {
+            let mut histogram = [0u64; 256];
+            for px in rgba.pixels() {
+                histogram[px[channel] as usize] += 1;
+            }
+            let (low, high) = clip_bounds(&histogram, total, clip_percent);
+            *lut = stretch_lut(low, high);
+        }
+
+        apply_luts(&mut rgba, &luts);
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+
+    /// Stretch levels using the luminance histogram, applying the same
+    /// mapping to all channels so color balance is preserved
+    #[wasm_bindgen]
+    pub fn auto_contrast(&self, image_data: &[u8], clip_percent: f32) -> Result<Vec<u8>, JsValue> {
+        let mut rgba = decode(image_data)?.to_rgba8();
+        let total = (rgba.width() as u64 * rgba.height() as u64).max(1);
+
+        let mut histogram = [0u64; 256];
+        for px in rgba.pixels() {
+            histogram[luma(px[0], px[1], px[2]) as usize] += 1;
+        }
+        let (low, high) = clip_bounds(&histogram, total, clip_percent);
+        let lut = stretch_lut(low, high);
+
+        apply_luts(&mut rgba, &[lut; 3]);
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+
+    /// Contrast-limited adaptive histogram equalization (CLAHE)
+    ///
+    /// Equalizes luminance per tile of a `tile_grid` x `tile_grid` grid and
+    /// blends between neighboring tiles, so shadows and highlights in
+    /// unevenly lit photos each get their own contrast. `clip_limit` caps
+    /// how far any tile is stretched (2-4 is typical; 1 leaves the image
+    /// nearly unchanged). Color is kept by shifting all channels by the same
+    /// luminance change.
+    #[wasm_bindgen]
+    pub fn apply_clahe(&self, image_data: &[u8], clip_limit: f32, tile_grid: u32) -> Result<Vec<u8>, JsValue> {
+        if clip_limit < 1.0 || !clip_limit.is_finite() {
+            return Err(JsValue::from_str("Clip limit must be at least 1"));
+        }
+        if tile_grid == 0 {
+            return Err(JsValue::from_str("Tile grid must be at least 1"));
+        }
+
+        let mut rgba = decode(image_data)?.to_rgba8();
+        let (width, height) = (rgba.width() as usize, rgba.height() as usize);
+        let grid = (tile_grid as usize).min(width).min(height).max(1);
+        let lumas: Vec<u8> = rgba.pixels().map(|px| luma(px[0], px[1], px[2])).collect();
+        let luts = tile_luts(&lumas, width, height, grid, clip_limit);
+
+        let columns = tile_weights(width, grid);
+        let rows = tile_weights(height, grid);
+        for ((i, px), &old) in rgba.pixels_mut().enumerate().zip(&lumas) {
+            let (x0, x1, wx) = columns[i % width];
+            let (y0, y1, wy) = rows[i / width];
+            let value = old as usize;
+            let top = luts[y0 * grid + x0][value] as f32 * (1.0 - wx) + luts[y0 * grid + x1][value] as f32 * wx;
+            let bottom = luts[y1 * grid + x0][value] as f32 * (1.0 - wx) + luts[y1 * grid + x1][value] as f32 * wx;
+
+            // Keeping Cb and Cr while changing Y is the same shift on R, G and B
+            let delta = top * (1.0 - wy) + bottom * wy - old as f32;
+            for channel in px.0.iter_mut().take(3) {
+                *channel = (*channel as f32 + delta).round().max(0.0).min(255.0) as u8;
+            }
+        }
+
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+
+    /// Gamma-correct midtones; values above 1 brighten and below 1 darken
+    ///
+    /// The exponent is applied to linear light rather than to the
+    /// sRGB-encoded values, so shadows don't get crushed or washed out.
+    #[wasm_bindgen]
+    pub fn adjust_gamma(&self, image_data: &[u8], gamma: f32) -> Result<Vec<u8>, JsValue> {
+        if gamma <= 0.0 || !gamma.is_finite() {
+            return Err(JsValue::from_str("Gamma must be a positive number"));
+        }
+
+        let mut rgba = decode(image_data)?.to_rgba8();
+        apply_luts(&mut rgba, &[gamma_lut(gamma); 3]);
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+
+    /// Shift color temperature and tint
+    ///
+    /// Positive `kelvin_shift` warms the image (corrects for cool light),
+    /// negative cools it. `tint` in [-1, 1] shifts toward magenta (positive)
+    /// or green (negative).
+    #[wasm_bindgen]
+    pub fn adjust_temperature(&self, image_data: &[u8], kelvin_shift: f32, tint: f32) -> Result<Vec<u8>, JsValue> {
+        let mut rgba = decode(image_data)?.to_rgba8();
+
+        let reference = kelvin_to_rgb(NEUTRAL_KELVIN);
+        let target = kelvin_to_rgb((NEUTRAL_KELVIN - kelvin_shift).max(1000.0).min(40000.0));
+        let mut gains = [
+            target[0] / reference[0],
+            target[1] / reference[1],
+            target[2] / reference[2],
+        ];
+        gains[1] *= 1.0 - tint.max(-1.0).min(1.0) * 0.5;
+
+        apply_linear_gains(&mut rgba, normalize_gains(gains));
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+
+    /// Neutralize color casts using the `gray-world` or `white-patch` assumption
+    #[wasm_bindgen]
+    pub fn auto_white_balance(&self, image_data: &[u8], method: &str) -> Result<Vec<u8>, JsValue> {
+        let mut rgba = decode(image_data)?.to_rgba8();
+        let linear = lut_linear();
+
+        let gains = match method.to_lowercase().as_str() {
+            "gray-world" | "grayworld" => {
+                // The scene should average to neutral gray
+                let mut sums = [0f64; 3];
+                for px in rgba.pixels() {
+                    for c in 0..3 {
+                        sums[c] += linear[px[c] as usize] as f64;
+                    }
+                }
+                let mean = (sums[0] + sums[1] + sums[2]) / 3.0;
+                let gain = |sum: f64| if sum > 0.0 { (mean / sum) as f32 } else { 1.0 };
+                [gain(sums[0]), gain(sums[1]), gain(sums[2])]
+            }
+            "white-patch" | "whitepatch" => {
+                // The brightest surfaces should be white; use the 99th
+                // percentile rather than the max to ignore specular noise
+                let total = (rgba.width() as u64 * rgba.height() as u64).max(1);
+                let mut gains = [1.0f32; 3];
+                for (c, gain) in gains.iter_mut().enumerate() {
+                    let mut histogram = [0u64; 256];
+                    for px in rgba.pixels() {
+                        histogram[px[c] as usize] += 1;
+                    }
+                    let (_, high) = clip_bounds(&histogram, total, 1.0);
+                    if high > 0 {
+                        *gain = 1.0 / linear[high as usize];
+                    }
+                }
+                gains
+            }
+            _ => return Err(JsValue::from_str("Method must be gray-world or white-patch")),
+        };
+
+        apply_linear_gains(&mut rgba, normalize_gains(gains));
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+
+    /// Apply a built-in filter preset: `sepia`, `vintage`, `warm`, `cool`,
+    /// `mono`, `fade` or `vivid`
+    #[wasm_bindgen]
+    pub fn apply_preset(&self, image_data: &[u8], preset_name: &str) -> Result<Vec<u8>, JsValue> {
+        let (matrix, curve) = preset(preset_name)?;
+        let mut rgba = decode(image_data)?.to_rgba8();
+
+        apply_color_matrix(&mut rgba, &matrix);
+        apply_luts(&mut rgba, &[curve; 3]);
+
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+
+    /// Mix channels with a 3x4 color matrix given as 12 row-major values
+    ///
+    /// Each row computes one output channel (R, G, B) from the red, green
+    /// and blue inputs plus a constant offset, all on a 0-1 scale.
+    #[wasm_bindgen]
+    pub fn channel_mix(&self, image_data: &[u8], matrix: &[f32]) -> Result<Vec<u8>, JsValue> {
+        if matrix.len() != 12 {
+            return Err(JsValue::from_str("Channel matrix must have 12 values (3 rows of 4)"));
+        }
+
+        let mut color_matrix: ColorMatrix = [[0.0; 4]; 3];
+        for (row, values) in color_matrix.iter_mut().zip(matrix.chunks_exact(4)) {
+            row.copy_from_slice(values);
+        }
+
+        let mut rgba = decode(image_data)?.to_rgba8();
+        apply_color_matrix(&mut rgba, &color_matrix);
+
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+
+    /// Apply tone curves per channel, as in an editor's curves panel
+    ///
+    /// Each curve is a flat list of `x, y` control points on a 0-255 scale,
+    /// e.g. `[0, 0, 64, 48, 192, 208, 255, 255]` for a gentle S-curve; a
+    /// missing or empty curve leaves that channel alone. Points are joined
+    /// by a monotone cubic spline, so the curve never overshoots between
+    /// them. The `luma` curve applies to all three channels after their own
+    /// curves, like the composite RGB curve in Photoshop.
+    #[wasm_bindgen]
+    pub fn apply_curves(
+        &self,
+        image_data: &[u8],
+        curve_points_r: Option<Vec<f32>>,
+        curve_points_g: Option<Vec<f32>>,
+        curve_points_b: Option<Vec<f32>>,
+        curve_points_luma: Option<Vec<f32>>,
+    ) -> Result<Vec<u8>, JsValue> {
+        let master = curve_lut(curve_points_luma.as_deref())?;
+        let mut luts = [
+            curve_lut(curve_points_r.as_deref())?,
+            curve_lut(curve_points_g.as_deref())?,
+            curve_lut(curve_points_b.as_deref())?,
+        ];
+        for lut in luts.iter_mut() {
+            for v in lut.iter_mut() {
+                *v = master[*v as usize];
+            }
+        }
+
+        let mut rgba = decode(image_data)?.to_rgba8();
+        apply_luts(&mut rgba, &luts);
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+
+    /// Produce a negative; alpha is inverted too unless `preserve_alpha` is set
+    #[wasm_bindgen]
+    pub fn invert(&self, image_data: &[u8], preserve_alpha: bool) -> Result<Vec<u8>, JsValue> {
+        let mut rgba = decode(image_data)?.to_rgba8();
+        invert_pixels(&mut rgba, preserve_alpha);
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+
+    /// Reduce each color channel to `levels` evenly spaced values (2-255)
+    #[wasm_bindgen]
+    pub fn posterize(&self, image_data: &[u8], levels: u8) -> Result<Vec<u8>, JsValue> {
+        if levels < 2 {
+            return Err(JsValue::from_str("Levels must be at least 2"));
+        }
+
+        let mut rgba = decode(image_data)?.to_rgba8();
+        apply_luts(&mut rgba, &[posterize_lut(levels); 3]);
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+}
+
+/// Snap each level to the nearest of `levels` evenly spaced values
+fn posterize_lut(levels: u8) -> Lut {
+    let steps = (levels - 1) as f32;
+    lut_from_fn(|v| (v * steps).round() / steps)
+}
+
+fn invert_pixels(rgba: &mut RgbaImage, preserve_alpha: bool) {
+    for px in rgba.pixels_mut() {
+        px[0] = 255 - px[0];
+        px[1] = 255 - px[1];
+        px[2] = 255 - px[2];
+        if !preserve_alpha {
+            px[3] = 255 - px[3];
+        }
+    }
+}
+
+/// Raise linear light to `1 / gamma` and re-encode as sRGB
+fn gamma_lut(gamma: f32) -> Lut {
+    let exponent = 1.0 / gamma;
+    lut_from_fn(|v| linear_to_srgb(srgb_to_linear(v).powf(exponent)))
+}
+
+/// Clipped-histogram equalization table of every tile, row by row
+fn tile_luts(lumas: &[u8], width: usize, height: usize, grid: usize, clip_limit: f32) -> Vec<Lut> {
+    let mut luts = Vec::with_capacity(grid * grid);
+    for ty in 0..grid {
+        let (y0, y1) = (ty * height / grid, (ty + 1) * height / grid);
+        for tx in 0..grid {
+            let (x0, x1) = (tx * width / grid, (tx + 1) * width / grid);
+
+            let mut histogram = [0u32; 256];
+            for row in lumas[y0 * width..y1 * width].chunks_exact(width) {
+                for &v in &row[x0..x1] {
+                    histogram[v as usize] += 1;
+                }
+            }
+
+            // Clip each bin and spread the excess evenly over all bins
+            let area = ((x1 - x0) * (y1 - y0)).max(1) as u32;
+            let limit = ((clip_limit * area as f32 / 256.0) as u32).max(1);
+            let mut excess = 0;
+            for count in histogram.iter_mut() {
+                if *count > limit {
+                    excess += *count - limit;
+                    *count = limit;
+                }
+            }
+            let (share, remainder) = (excess / 256, (excess % 256) as usize);
+            for (v, count) in histogram.iter_mut().enumerate() {
+                *count += share + (v < remainder) as u32;
+            }
+
+            let mut lut = [0u8; 256];
+            let mut cumulative = 0u32;
+            for (entry, count) in lut.iter_mut().zip(&histogram) {
+                cumulative += count;
+                *entry = ((cumulative as u64 * 255 + area as u64 / 2) / area as u64).min(255) as u8;
+            }
+            luts.push(lut);
+        }
+    }
+    luts
+}
+
+/// For each position along an axis, the two nearest tile centers and the
+/// weight of the second
+fn tile_weights(len: usize, grid: usize) -> Vec<(usize, usize, f32)> {
+    let tile = len as f32 / grid as f32;
+    (0..len)
+        .map(|i| {
+            let position = ((i as f32 + 0.5) / tile - 0.5).max(0.0);
+            let first = (position as usize).min(grid - 1);
+            let second = (first + 1).min(grid - 1);
+            (first, second, (position - first as f32).min(1.0))
+        })
+        .collect()
+}
+
+/// 3x4 color matrix over normalized RGB: each row holds the red, green and
+/// blue coefficients followed by a constant offset
+pub(crate) type ColorMatrix = [[f32; 4]; 3];
+
+/// Look up the color matrix and tone curve for a named preset
+fn preset(name: &str) -> Result<(ColorMatrix, Lut), JsValue> {
+    let preset = match name.to_lowercase().as_str() {
+        "sepia" => (
+            [
+                [0.393, 0.769, 0.189, 0.0],
+                [0.349, 0.686, 0.168, 0.0],
+                [0.272, 0.534, 0.131, 0.0],
+            ],
+            identity_lut(),
+        ),
+        "vintage" => (
+            // Desaturated base toned 35% of the way to sepia, with lifted
+            // blacks and rolled-off highlights
+            mix_matrix(&saturation_matrix(0.6), &[
+                [0.393, 0.769, 0.189, 0.0],
+                [0.349, 0.686, 0.168, 0.0],
+                [0.272, 0.534, 0.131, 0.0],
+            ], 0.35),
+            lut_from_fn(|v| 0.08 + 0.84 * s_curve(v, 0.3)),
+        ),
+        "warm" => (
+            [
+                [1.08, 0.0, 0.0, 0.02],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 0.88, 0.0],
+            ],
+            lut_from_fn(|v| s_curve(v, 0.15)),
+        ),
+        "cool" => (
+            [
+                [0.9, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.1, 0.02],
+            ],
+            lut_from_fn(|v| s_curve(v, 0.15)),
+        ),
+        "mono" => (saturation_matrix(0.0), lut_from_fn(|v| s_curve(v, 0.25))),
+        "fade" => (saturation_matrix(0.7), lut_from_fn(|v| 0.12 + 0.8 * v)),
+        "vivid" => (saturation_matrix(1.35), lut_from_fn(|v| s_curve(v, 0.35))),
+        _ => return Err(JsValue::from_str(&format!("Unknown preset: {}", name))),
+    };
+
+    Ok(preset)
+}
+
+/// Saturation matrix around Rec. 709 luminance; 0 is grayscale, 1 is unchanged
+pub(crate) fn saturation_matrix(saturation: f32) -> ColorMatrix {
+    let weights = [0.2126, 0.7152, 0.0722];
+    let mut matrix = [[0.0; 4]; 3];
+
+    for (row, out) in matrix.iter_mut().enumerate() {
+        for col in 0..3 {
+            out[col] = (1.0 - saturation) * weights[col] + if row == col { saturation } else { 0.0 };
+        }
+    }
+
+    matrix
+}
+
+/// Linear interpolation between two matrices
+fn mix_matrix(a: &ColorMatrix, b: &ColorMatrix, t: f32) -> ColorMatrix {
+    let mut out = [[0.0; 4]; 3];
+    for row in 0..3 {
+        for col in 0..4 {
+            out[row][col] = a[row][col] * (1.0 - t) + b[row][col] * t;
+        }
+    }
+    out
+}
+
+/// Monotonic contrast curve around the midpoint; `amount` in [0, 1]
+fn s_curve(v: f32, amount: f32) -> f32 {
+    v - amount * (2.0 * std::f32::consts::PI * v).sin() / (2.0 * std::f32::consts::PI)
+}
+
+/// Apply a color matrix in place, leaving alpha untouched
+pub(crate) fn apply_color_matrix(rgba: &mut RgbaImage, matrix: &ColorMatrix) {
+    for px in rgba.pixels_mut() {
+        let r = px[0] as f32 / 255.0;
+        let g = px[1] as f32 / 255.0;
+        let b = px[2] as f32 / 255.0;
+
+        for (c, row) in matrix.iter().enumerate() {
+            let value = row[0] * r + row[1] * g + row[2] * b + row[3];
+            px[c] = (value * 255.0).round().max(0.0).min(255.0) as u8;
+        }
+    }
+}
+
+/// Daylight white point used as the zero point for temperature shifts
+const NEUTRAL_KELVIN: f32 = 6500.0;
+
+/// Approximate the RGB color of a black-body radiator (Tanner Helland's fit)
+fn kelvin_to_rgb(kelvin: f32) -> [f32; 3] {
+    let t = kelvin / 100.0;
+
+    let r = if t <= 66.0 {
+        255.0
+    } else {
+        329.698727446 * (t - 60.0).powf(-0.1332047592)
+    };
+    let g = if t <= 66.0 {
+        99.4708025861 * t.ln() - 161.1195681661
+    } else {
+        288.1221695283 * (t - 60.0).powf(-0.0755148492)
+    };
+    let b = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.5177312231 * (t - 10.0).ln() - 305.0447927307
+    };
+
+    [r.max(1.0).min(255.0), g.max(1.0).min(255.0), b.max(1.0).min(255.0)]
+}
+
+/// Scale gains so overall luminance stays roughly constant
+fn normalize_gains(gains: [f32; 3]) -> [f32; 3] {
+    let luminance = 0.2126 * gains[0] + 0.7152 * gains[1] + 0.0722 * gains[2];
+    if luminance <= 0.0 {
+        return [1.0; 3];
+    }
+    [gains[0] / luminance, gains[1] / luminance, gains[2] / luminance]
+}
+
+/// Multiply each channel by a gain in linear light
+fn apply_linear_gains(rgba: &mut RgbaImage, gains: [f32; 3]) {
+    let luts = [
+        lut_from_fn(|v| linear_to_srgb((srgb_to_linear(v) * gains[0]).min(1.0))),
+        lut_from_fn(|v| linear_to_srgb((srgb_to_linear(v) * gains[1]).min(1.0))),
+        lut_from_fn(|v| linear_to_srgb((srgb_to_linear(v) * gains[2]).min(1.0))),
+    ];
+    apply_luts(rgba, &luts);
+}
+
+/// Table of linear-light values for every 8-bit sRGB level
+pub(crate) fn lut_linear() -> [f32; 256] {
+    let mut table = [0f32; 256];
+    for (i, v) in table.iter_mut().enumerate() {
+        *v = srgb_to_linear(i as f32 / 255.0);
+    }
+    table
+}
+
+/// Decode an sRGB-encoded value in [0, 1] to linear light
+pub(crate) fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encode a linear-light value in [0, 1] with the sRGB transfer curve
+pub(crate) fn linear_to_srgb(v: f32) -> f32 {
+    if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Rec. 601 luma
+pub(crate) fn luma(r: u8, g: u8, b: u8) -> u8 {
+    ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114 + 500) / 1000) as u8
+}
+
+pub(crate) fn identity_lut() -> Lut {
+    let mut lut = [0u8; 256];
+    for (i, v) in lut.iter_mut().enumerate() {
+        *v = i as u8;
+    }
+    lut
+}
+
+/// Build a LUT from a function over normalized [0, 1] values
+pub(crate) fn lut_from_fn<F: Fn(f32) -> f32>(f: F) -> Lut {
+    let mut lut = [0u8; 256];
+    for (i, v) in lut.iter_mut().enumerate() {
+        *v = (f(i as f32 / 255.0) * 255.0).round().max(0.0).min(255.0) as u8;
+    }
+    lut
+}
+
+/// Apply red, green and blue LUTs in place, leaving alpha untouched
+pub(crate) fn apply_luts(rgba: &mut RgbaImage, luts: &[Lut; 3]) {
+    for px in rgba.pixels_mut() {
+        px[0] = luts[0][px[0] as usize];
+        px[1] = luts[1][px[1] as usize];
+        px[2] = luts[2][px[2] as usize];
+    }
+}
+
+/// LUT through `x, y` control points with monotone cubic (Fritsch-Carlson)
+/// interpolation, flat beyond the first and last point
+fn curve_lut(points: Option<&[f32]>) -> Result<Lut, JsValue> {
+    let points = match points {
+        Some(points) if !points.is_empty() => points,
+        _ => return Ok(identity_lut()),
+    };
+    let invalid = || JsValue::from_str("Curve points must be x, y pairs with at least 2 distinct x values");
+    if points.len() % 2 != 0 {
+        return Err(invalid());
+    }
+
+    let mut knots: Vec<(f32, f32)> = points
+        .chunks_exact(2)
+        .map(|p| (p[0].max(0.0).min(255.0), p[1].max(0.0).min(255.0)))
+        .collect();
+    knots.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+    knots.dedup_by(|a, b| (a.0 - b.0).abs() < 1e-3);
+    if knots.len() < 2 {
+        return Err(invalid());
+    }
+
+    let slopes: Vec<f32> = knots.windows(2).map(|w| (w[1].1 - w[0].1) / (w[1].0 - w[0].0)).collect();
+    let mut tangents = vec![0f32; knots.len()];
+    tangents[0] = slopes[0];
+    tangents[knots.len() - 1] = slopes[slopes.len() - 1];
+    for (tangent, pair) in tangents[1..knots.len() - 1].iter_mut().zip(slopes.windows(2)) {
+        *tangent = if pair[0] * pair[1] <= 0.0 { 0.0 } else { (pair[0] + pair[1]) / 2.0 };
+    }
+    // Limit tangents so each segment stays monotone
+    for (i, &slope) in slopes.iter().enumerate() {
+        if slope == 0.0 {
+            tangents[i] = 0.0;
+            tangents[i + 1] = 0.0;
+            continue;
+        }
+        let (a, b) = (tangents[i] / slope, tangents[i + 1] / slope);
+        let magnitude = a * a + b * b;
+        if magnitude > 9.0 {
+            let scale = 3.0 / magnitude.sqrt();
+            tangents[i] = scale * a * slope;
+            tangents[i + 1] = scale * b * slope;
+        }
+    }
+
+    let (first, last) = (knots[0], knots[knots.len() - 1]);
+    let mut lut = [0u8; 256];
+    for (x, entry) in lut.iter_mut().enumerate() {
+        let x = x as f32;
+        let y = if x <= first.0 {
+            first.1
+        } else if x >= last.0 {
+            last.1
+        } else {
+            let i = knots.windows(2).position(|w| x < w[1].0).unwrap_or(knots.len() - 2);
+            let ((x0, y0), (x1, y1)) = (knots[i], knots[i + 1]);
+            let h = x1 - x0;
+            let t = (x - x0) / h;
+            let (t2, t3) = (t * t, t * t * t);
+            (2.0 * t3 - 3.0 * t2 + 1.0) * y0
+                + (t3 - 2.0 * t2 + t) * h * tangents[i]
+                + (-2.0 * t3 + 3.0 * t2) * y1
+                + (t3 - t2) * h * tangents[i + 1]
+        };
+        *entry = y.round().max(0.0).min(255.0) as u8;
+    }
+
+    Ok(lut)
+}
+
+/// Find the levels below/above which `clip_percent` of pixels fall
+fn clip_bounds(histogram: &[u64; 256], total: u64, clip_percent: f32) -> (u8, u8) {
+    let clip = (total as f64 * clip_percent.max(0.0).min(50.0) as f64 / 100.0) as u64;
+
+    let mut low = 0;
+    let mut count = 0;
+    for (level, &n) in histogram.iter().enumerate() {
+        count += n;
+        if count > clip {
+            low = level;
+            break;
+        }
+    }
+
+    let mut high = 255;
+    count = 0;
+    for (level, &n) in histogram.iter().enumerate().rev() {
+        count += n;
+        if count > clip {
+            high = level;
+            break;
+        }
+    }
+
+    (low as u8, high as u8)
+}
+
+/// Linearly map `[low, high]` onto the full range
+fn stretch_lut(low: u8, high: u8) -> Lut {
+    if high <= low {
+        return identity_lut();
+    }
+
+    let (low, high) = (low as f32, high as f32);
+    let mut lut = [0u8; 256];
+    for (i, v) in lut.iter_mut().enumerate() {
+        *v = ((i as f32 - low) * 255.0 / (high - low)).round().max(0.0).min(255.0) as u8;
+    }
+    lut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn clip_bounds_skip_the_clipped_tails() {
+        let mut histogram = [0u64; 256];
+        histogram[10] = 5;
+        histogram[50] = 90;
+        histogram[240] = 5;
+
+        assert_eq!(clip_bounds(&histogram, 100, 0.0), (10, 240));
+        // 5% at each end swallows the outliers
+        assert_eq!(clip_bounds(&histogram, 100, 5.0), (50, 50));
+        assert_eq!(clip_bounds(&histogram, 100, 4.0), (10, 240));
+    }
+
+    #[test]
+    fn stretch_lut_maps_bounds_to_the_full_range() {
+        let lut = stretch_lut(50, 150);
+        assert_eq!((lut[0], lut[50], lut[100], lut[150], lut[255]), (0, 0, 128, 255, 255));
+
+        // A flat channel is left alone rather than divided by zero
+        assert_eq!(stretch_lut(80, 80), identity_lut());
+    }
+
+    #[test]
+    fn gamma_keeps_endpoints_and_moves_midtones() {
+        assert_eq!(gamma_lut(1.0), identity_lut());
+
+        let brighter = gamma_lut(2.2);
+        let darker = gamma_lut(0.5);
+        assert_eq!((brighter[0], brighter[255]), (0, 255));
+        assert_eq!((darker[0], darker[255]), (0, 255));
+        assert!(brighter[128] > 128 && darker[128] < 128);
+    }
+
+    #[test]
+    fn temperature_gains_keep_luminance() {
+        let warm = kelvin_to_rgb(3000.0);
+        let cool = kelvin_to_rgb(10000.0);
+        assert!(warm[0] > warm[2] && cool[2] > cool[0]);
+
+        let gains = normalize_gains([1.4, 1.0, 0.6]);
+        let luminance = 0.2126 * gains[0] + 0.7152 * gains[1] + 0.0722 * gains[2];
+        assert!((luminance - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn unit_gains_leave_pixels_alone() {
+        let mut rgba = RgbaImage::from_fn(16, 16, |x, y| Rgba([(x * 16) as u8, (y * 16) as u8, 200, 255]));
+        let original = rgba.clone();
+
+        apply_linear_gains(&mut rgba, [1.0; 3]);
+        assert_eq!(rgba, original);
+    }
+
+    #[test]
+    fn presets_tone_as_named() {
+        for name in ["sepia", "vintage", "warm", "cool", "mono", "fade", "VIVID"] {
+            assert!(preset(name).is_ok());
+        }
+
+        let (sepia, _) = preset("sepia").ok().unwrap();
+        let mut white = RgbaImage::from_pixel(1, 1, Rgba([255, 255, 255, 255]));
+        apply_color_matrix(&mut white, &sepia);
+        assert_eq!(white.get_pixel(0, 0), &Rgba([255, 255, 239, 255]));
+
+        let (mono, _) = preset("mono").ok().unwrap();
+        let mut red = RgbaImage::from_pixel(1, 1, Rgba([200, 30, 30, 128]));
+        apply_color_matrix(&mut red, &mono);
+        let px = red.get_pixel(0, 0);
+        assert!(px[0] == px[1] && px[1] == px[2] && px[3] == 128);
+    }
+
+    #[test]
+    fn invert_optionally_keeps_alpha() {
+        let mut rgba = RgbaImage::from_pixel(1, 1, Rgba([0, 100, 255, 200]));
+        invert_pixels(&mut rgba, true);
+        assert_eq!(rgba.get_pixel(0, 0), &Rgba([255, 155, 0, 200]));
+
+        invert_pixels(&mut rgba, false);
+        assert_eq!(rgba.get_pixel(0, 0), &Rgba([0, 100, 255, 55]));
+    }
+
+    #[test]
+    fn posterize_snaps_to_even_levels() {
+        let mut two = posterize_lut(2).to_vec();
+        two.dedup();
+        assert_eq!(two, [0, 255]);
+
+        let mut four = posterize_lut(4).to_vec();
+        four.dedup();
+        assert_eq!(four, [0, 85, 170, 255]);
+        assert_eq!(posterize_lut(4)[42], 0);
+        assert_eq!(posterize_lut(4)[43], 85);
+
+        let mut most = posterize_lut(255).to_vec();
+        most.dedup();
+        assert_eq!(most.len(), 255);
+    }
+
+    #[test]
+    fn color_matrix_mixes_channels_and_offsets() {
+        let mut rgba = RgbaImage::from_pixel(1, 1, Rgba([200, 100, 50, 77]));
+        // Swap red and blue, and lift green by a fifth of the range
+        let swap = [
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0, 0.2],
+            [1.0, 0.0, 0.0, 0.0],
+        ];
+        apply_color_matrix(&mut rgba, &swap);
+        assert_eq!(rgba.get_pixel(0, 0), &Rgba([50, 151, 200, 77]));
+
+        // Custom grayscale weights put the same value in every channel
+        let weights = [[0.5, 0.5, 0.0, 0.0]; 3];
+        apply_color_matrix(&mut rgba, &weights);
+        assert_eq!(rgba.get_pixel(0, 0), &Rgba([101, 101, 101, 77]));
+    }
+
+    #[test]
+    fn clahe_clip_limit_bounds_the_stretch() {
+        // One 16x16 tile of a single level: a clip limit of 1 spreads its
+        // histogram almost flat, so the table stays close to identity
+        let flat = vec![100u8; 256];
+        let lut = tile_luts(&flat, 16, 16, 1, 1.0)[0];
+        assert!(lut.iter().enumerate().all(|(v, &out)| (out as i32 - v as i32).abs() <= 2));
+
+        // Without clipping, two close levels are pulled to full contrast
+        let two: Vec<u8> = (0..256).map(|i| if i < 128 { 100 } else { 110 }).collect();
+        let lut = tile_luts(&two, 16, 16, 1, 256.0)[0];
+        assert_eq!((lut[100], lut[110]), (128, 255));
+    }
+
+    #[test]
+    fn clahe_blends_between_tile_centers() {
+        let weights = tile_weights(8, 2);
+        // Pixels before the first tile center and after the last use one tile
+        assert_eq!(weights[0], (0, 1, 0.0));
+        assert_eq!((weights[7].0, weights[7].1), (1, 1));
+        // Tile centers sit at 2 and 6; pixel 4 (centered at 4.5) is 5/8 of
+        // the way from the first to the second
+        assert_eq!((weights[4].0, weights[4].1), (0, 1));
+        assert!((weights[4].2 - 0.625).abs() < 1e-6);
+    }
+
+    #[test]
+    fn curves_pass_through_points_and_stay_flat_outside() {
+        assert_eq!(curve_lut(None).ok().unwrap(), identity_lut());
+        assert_eq!(curve_lut(Some(&[0.0, 0.0, 255.0, 255.0])).ok().unwrap(), identity_lut());
+
+        let lut = curve_lut(Some(&[192.0, 224.0, 64.0, 32.0, 128.0, 128.0])).ok().unwrap();
+        assert_eq!((lut[0], lut[64], lut[128], lut[192], lut[255]), (32, 32, 128, 224, 224));
+        // Monotone cubic never overshoots between increasing points
+        assert!(lut.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+}
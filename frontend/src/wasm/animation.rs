@@ -0,0 +1,582 @@
+use std::io::Cursor;
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use js_sys::{Array, Object, Reflect, Uint8Array};
+use serde::Deserialize;
+use image::{AnimationDecoder, DynamicImage, Frame, Frames, GenericImageView, ImageDecoder, RgbaImage, imageops::{self, FilterType}};
+use image::codecs::gif::{GifDecoder, GifEncoder, Repeat};
+use image::codecs::webp::WebPDecoder;
+use crate::image_processor::{ImageProcessor, decode_array, encode, parse_options};
+use crate::limits::{DecodeError, FrameBudget, check_dimensions, check_output_size, decoder_limits};
+use crate::riff;
+use crate::quantize::{Palette, floyd_steinberg, map_nearest};
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Get frame count, dimensions and timing of an animated GIF
+    #[wasm_bindgen]
+    pub fn get_gif_info(&self, image_data: &[u8]) -> Result<JsValue, JsValue> {
+        let frames = decode_gif(image_data)?;
+        let delays: Vec<u32> = frames.iter().map(frame_delay_ms).collect();
+        let (width, height) = frames
+            .first()
+            .map(|f| f.buffer().dimensions())
+            .unwrap_or((0, 0));
+
+        let result = serde_json::json!({
+            "width": width,
+            "height": height,
+            "frameCount": frames.len(),
+            "delays": delays,
+            "totalDuration": delays.iter().sum::<u32>()
+        });
+
+        JsValue::from_serde(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Decode every frame of an animated GIF as `{ data, delay }` PNG frames
+    #[wasm_bindgen]
+    pub fn decode_gif_frames(&self, image_data: &[u8]) -> Result<Array, JsValue> {
+        let frames = decode_gif(image_data)?;
+        frames_to_js(frames)
+    }
+
+    /// Extract a single frame of an animated GIF as PNG
+    #[wasm_bindgen]
+    pub fn extract_gif_frame(&self, image_data: &[u8], index: u32) -> Result<Vec<u8>, JsValue> {
+        let frame = decode_gif(image_data)?
+            .into_iter()
+            .nth(index as usize)
+            .ok_or_else(|| JsValue::from_str("Frame index out of range"))?;
+
+        encode(&DynamicImage::ImageRgba8(frame.into_buffer()), image::ImageOutputFormat::Png)
+    }
+
+    /// Resize every frame of an animated GIF, keeping timing and loop count
+    ///
+    /// Pass 0 for either dimension to derive it from the aspect ratio.
+    #[wasm_bindgen]
+    pub fn resize_gif(&self, image_data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, JsValue> {
+        let frames = decode_gif(image_data)?;
+        let repeat = gif_repeat(image_data);
+
+        let (src_width, src_height) = frames
+            .first()
+            .map(|f| f.buffer().dimensions())
+            .ok_or_else(|| JsValue::from_str("GIF contains no frames"))?;
+
+        let (width, height) = match (width, height) {
+            (0, 0) => return Err(JsValue::from_str("Width or height must be non-zero")),
+            (0, h) => (((src_width as u64 * h as u64) / src_height as u64).max(1) as u32, h),
+            (w, 0) => (w, ((src_height as u64 * w as u64) / src_width as u64).max(1) as u32),
+            (w, h) => (w, h),
+        };
+
+        let resized = frames.into_iter().map(|frame| {
+            let delay = frame.delay();
+            let buffer = imageops::resize(frame.buffer(), width, height, FilterType::Lanczos3);
+            Frame::from_parts(buffer, 0, 0, delay)
+        });
+
+        encode_gif(resized, repeat, GIF_QUANTIZE_SPEED)
+    }
+
+    /// Assemble encoded frames into an animated PNG
+    ///
+    /// `delays_ms` holds one delay per frame, or a single delay for all frames.
+    /// A `loop_count` of 0 loops forever.
+    #[wasm_bindgen]
+    pub fn encode_apng(&self, frames: &Array, delays_ms: &[u32], loop_count: u32) -> Result<Vec<u8>, JsValue> {
+        let frames: Vec<RgbaImage> = decode_array(frames)?
+            .into_iter()
+            .map(|img| img.to_rgba8())
+            .collect();
+        let delays = expand_delays(delays_ms, frames.len())?;
+
+        encode_apng(&frames, &delays, loop_count)
+    }
+
+    /// Decode every frame of an animated WebP as `{ data, delay }` PNG frames
+    #[wasm_bindgen]
+    pub fn decode_webp_frames(&self, image_data: &[u8]) -> Result<Array, JsValue> {
+        let frames = decode_webp(image_data)?;
+        frames_to_js(frames)
+    }
+
+    /// Assemble encoded frames into an animated WebP
+    ///
+    /// Native builds with the `libwebp` feature encode lossy frames at
+    /// `quality` (0 to 100). The browser build can't link libwebp and writes
+    /// lossless frames instead, ignoring `quality`. A `loop_count` of 0 loops
+    /// forever.
+    #[wasm_bindgen]
+    pub fn encode_animated_webp(&self, frames: &Array, delays_ms: &[u32], quality: f32, loop_count: u32) -> Result<Vec<u8>, JsValue> {
+        let frames: Vec<RgbaImage> = decode_array(frames)?
+            .into_iter()
+            .map(|img| img.to_rgba8())
+            .collect();
+        let delays = expand_delays(delays_ms, frames.len())?;
+
+        encode_animated_webp(&frames, &delays, quality, loop_count)
+    }
+
+    /// Assemble captured frames into an animated `gif`, `webp` or `apng`
+    ///
+    /// Frames are scaled to the size of the first one. `delays_ms` holds one
+    /// delay per frame or a single delay for all. `options` accepts
+    /// `{ loopCount, maxColors, dither, quality }`: a `loopCount` of 0 (the
+    /// default) loops forever, `maxColors` (2-256) caps the GIF palette that
+    /// all frames share, and `quality` (0-100) applies to WebP.
+    #[wasm_bindgen]
+    pub fn frames_to_animation(&self, frames: &Array, delays_ms: &[u32], format: &str, options: JsValue) -> Result<Vec<u8>, JsValue> {
+        let options: AnimationOptions = parse_options(&options)?;
+        let images = decode_array(frames)?;
+        let (width, height) = images
+            .first()
+            .map(|img| img.dimensions())
+            .ok_or_else(|| JsValue::from_str("At least one frame is required"))?;
+
+        let frames: Vec<RgbaImage> = images
+            .into_iter()
+            .map(|img| {
+                if img.dimensions() == (width, height) {
+                    img.to_rgba8()
+                } else {
+                    imageops::resize(&img.to_rgba8(), width, height, FilterType::Triangle)
+                }
+            })
+            .collect();
+        let delays = expand_delays(delays_ms, frames.len())?;
+
+        match format.to_lowercase().as_str() {
+            "gif" => {
+                let max_colors = options.max_colors.unwrap_or(256).max(2).min(256) as usize;
+                encode_paletted_gif(&frames, &delays, max_colors, options.dither, options.loop_count)
+            }
+            "webp" => encode_animated_webp(&frames, &delays, options.quality.unwrap_or(75.0), options.loop_count),
+            "apng" | "png" => encode_apng(&frames, &delays, options.loop_count),
+            _ => Err(JsValue::from_str("Animation format must be gif, webp or apng")),
+        }
+    }
+}
+
+/// Settings for `frames_to_animation`
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct AnimationOptions {
+    loop_count: u32,
+    max_colors: Option<u16>,
+    dither: bool,
+    quality: Option<f32>,
+}
+
+/// NeuQuant sampling speed (1 = best palette, 30 = fastest)
+pub(crate) const GIF_QUANTIZE_SPEED: i32 = 10;
+
+/// Decode all frames of a GIF, composited onto the full logical screen
+pub(crate) fn decode_gif(image_data: &[u8]) -> Result<Vec<Frame>, DecodeError> {
+    let invalid = |e: image::ImageError| DecodeError::Invalid(format!("Failed to load GIF: {}", e));
+
+    let mut decoder = GifDecoder::new(Cursor::new(image_data)).map_err(invalid)?;
+    let (width, height) = decoder.dimensions();
+    check_dimensions(width, height)?;
+    decoder.set_limits(decoder_limits()).map_err(invalid)?;
+
+    collect_frames(decoder.into_frames(), "GIF")
+}
+
+/// Decode all frames of a (possibly animated) WebP
+pub(crate) fn decode_webp(image_data: &[u8]) -> Result<Vec<Frame>, DecodeError> {
+    let invalid = |e: image::ImageError| DecodeError::Invalid(format!("Failed to load WebP: {}", e));
+
+    let mut decoder = WebPDecoder::new(Cursor::new(image_data)).map_err(invalid)?;
+    let (width, height) = decoder.dimensions();
+    check_dimensions(width, height)?;
+    decoder.set_limits(decoder_limits()).map_err(invalid)?;
+
+    collect_frames(decoder.into_frames(), "WebP")
+}
+
+/// Decode frames one at a time, stopping as soon as they exceed the frame budget
+fn collect_frames(frames: Frames<'_>, format: &str) -> Result<Vec<Frame>, DecodeError> {
+    let mut budget = FrameBudget::new();
+
+    frames
+        .map(|frame| {
+            let frame = frame.map_err(|e| DecodeError::Invalid(format!("Failed to decode {} frames: {}", format, e)))?;
+            let (width, height) = frame.buffer().dimensions();
+            budget.spend(width, height)?;
+            Ok(frame)
+        })
+        .collect()
+}
+
+pub(crate) fn frame_delay_ms(frame: &Frame) -> u32 {
+    let (numer, denom) = frame.delay().numer_denom_ms();
+    numer.checked_div(denom).unwrap_or(0)
+}
+
+/// Convert decoded frames into a JS array of `{ data: Uint8Array, delay: number }`
+pub(crate) fn frames_to_js(frames: Vec<Frame>) -> Result<Array, JsValue> {
+    let result = Array::new();
+
+    for frame in frames {
+        let delay = frame_delay_ms(&frame);
+        let png = encode(&DynamicImage::ImageRgba8(frame.into_buffer()), image::ImageOutputFormat::Png)?;
+
+        let entry = Object::new();
+        Reflect::set(&entry, &"data".into(), &Uint8Array::from(png.as_slice()))?;
+        Reflect::set(&entry, &"delay".into(), &delay.into())?;
+        result.push(&entry);
+    }
+
+    Ok(result)
+}
+
+/// Read the NETSCAPE loop extension; it may appear after the first frame
+fn gif_repeat(image_data: &[u8]) -> Repeat {
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::Indexed);
+
+    let mut decoder = match options.read_info(image_data) {
+        Ok(decoder) => decoder,
+        Err(_) => return Repeat::Infinite,
+    };
+    while let Ok(Some(_)) = decoder.read_next_frame() {}
+
+    match decoder.repeat() {
+        gif::Repeat::Infinite => Repeat::Infinite,
+        gif::Repeat::Finite(count) => Repeat::Finite(count),
+    }
+}
+
+/// Encode frames as an animated GIF with per-frame NeuQuant palettes
+pub(crate) fn encode_gif<I: IntoIterator<Item = Frame>>(frames: I, repeat: Repeat, speed: i32) -> Result<Vec<u8>, JsValue> {
+    let mut output = Vec::new();
+    {
+        let mut encoder = GifEncoder::new_with_speed(&mut output, speed);
+        encoder
+            .set_repeat(repeat)
+            .map_err(|e| JsValue::from_str(&format!("Failed to encode GIF: {}", e)))?;
+        encoder
+            .encode_frames(frames)
+            .map_err(|e| JsValue::from_str(&format!("Failed to encode GIF: {}", e)))?;
+    }
+
+    Ok(output)
+}
+
+/// Pair every frame with a delay, broadcasting a single delay to all frames
+pub(crate) fn expand_delays(delays_ms: &[u32], frame_count: usize) -> Result<Vec<u32>, JsValue> {
+    match delays_ms.len() {
+        1 => Ok(vec![delays_ms[0]; frame_count]),
+        n if n == frame_count => Ok(delays_ms.to_vec()),
+        _ => Err(JsValue::from_str("Provide one delay per frame or a single delay for all frames")),
+    }
+}
+
+/// Encode equally sized RGBA frames as an APNG
+pub(crate) fn encode_apng(frames: &[RgbaImage], delays_ms: &[u32], loop_count: u32) -> Result<Vec<u8>, JsValue> {
+    let (width, height) = frames
+        .first()
+        .map(|f| f.dimensions())
+        .ok_or_else(|| JsValue::from_str("At least one frame is required"))?;
+
+    if frames.iter().any(|f| f.dimensions() != (width, height)) {
+        return Err(JsValue::from_str("All frames must have the same dimensions"));
+    }
+
+    let to_js = |e: png::EncodingError| JsValue::from_str(&format!("Failed to encode APNG: {}", e));
+
+    let mut output = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut output, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(frames.len() as u32, loop_count).map_err(to_js)?;
+
+        let mut writer = encoder.write_header().map_err(to_js)?;
+        for (frame, delay) in frames.iter().zip(delays_ms) {
+            writer.set_frame_delay((*delay).min(u16::MAX as u32) as u16, 1000).map_err(to_js)?;
+            writer.write_image_data(frame.as_raw()).map_err(to_js)?;
+        }
+        writer.finish().map_err(to_js)?;
+    }
+
+    Ok(output)
+}
+
+/// Encode equally sized RGBA frames as an animated WebP, see
+/// `ImageProcessor::encode_animated_webp`
+pub(crate) fn encode_animated_webp(frames: &[RgbaImage], delays_ms: &[u32], quality: f32, loop_count: u32) -> Result<Vec<u8>, JsValue> {
+    let (width, height) = frames
+        .first()
+        .map(|f| f.dimensions())
+        .ok_or_else(|| JsValue::from_str("At least one frame is required"))?;
+
+    if frames.iter().any(|f| f.dimensions() != (width, height)) {
+        return Err(JsValue::from_str("All frames must have the same dimensions"));
+    }
+
+    #[cfg(feature = "libwebp")]
+    return encode_animated_webp_lossy(frames, delays_ms, quality, loop_count);
+
+    #[cfg(not(feature = "libwebp"))]
+    {
+        let _ = quality;
+        encode_animated_webp_lossless(frames, delays_ms, loop_count)
+    }
+}
+
+/// Lossy frames through libwebp's animation encoder; native builds only
+#[cfg(feature = "libwebp")]
+fn encode_animated_webp_lossy(frames: &[RgbaImage], delays_ms: &[u32], quality: f32, loop_count: u32) -> Result<Vec<u8>, JsValue> {
+    use webp_animation::{AnimParams, Encoder, EncoderOptions, EncodingConfig};
+
+    let to_js = |e: webp_animation::Error| JsValue::from_str(&format!("Failed to encode WebP: {:?}", e));
+
+    let options = EncoderOptions {
+        anim_params: AnimParams { loop_count: i32::try_from(loop_count).unwrap_or(i32::MAX) },
+        encoding_config: Some(EncodingConfig::new_lossy(quality.max(0.0).min(100.0))),
+        ..Default::default()
+    };
+    let mut encoder = Encoder::new_with_options(frames[0].dimensions(), options).map_err(to_js)?;
+
+    // Frames are placed on a timeline of start timestamps
+    let timeline = frame_timeline(delays_ms).map_err(JsValue::from_str)?;
+    for (frame, timestamp) in frames.iter().zip(&timeline) {
+        encoder.add_frame(frame.as_raw(), *timestamp).map_err(to_js)?;
+    }
+
+    let end = timeline.last().copied().unwrap_or(0);
+    let webp = encoder.finalize(end).map_err(to_js)?;
+    Ok(webp.to_vec())
+}
+
+/// Start timestamp of every frame followed by the end of the last one, in
+/// the `i32` milliseconds libwebp uses
+#[cfg(any(feature = "libwebp", test))]
+fn frame_timeline(delays_ms: &[u32]) -> Result<Vec<i32>, &'static str> {
+    let mut timeline = Vec::with_capacity(delays_ms.len() + 1);
+    let mut timestamp: i32 = 0;
+    timeline.push(timestamp);
+
+    for &delay in delays_ms {
+        timestamp = i32::try_from(delay)
+            .ok()
+            .and_then(|delay| timestamp.checked_add(delay))
+            .ok_or("Animation is too long: total delay exceeds 2^31 - 1 ms")?;
+        timeline.push(timestamp);
+    }
+
+    Ok(timeline)
+}
+
+/// Lossless VP8L frames from the pure-Rust encoder, wrapped in the extended
+/// WebP container's `ANIM`/`ANMF` chunks
+#[cfg_attr(feature = "libwebp", allow(dead_code))]
+fn encode_animated_webp_lossless(frames: &[RgbaImage], delays_ms: &[u32], loop_count: u32) -> Result<Vec<u8>, JsValue> {
+    use image::codecs::webp::WebPEncoder;
+
+    // Canvas and frame sizes are stored minus one in 24 bits
+    const MAX_SIDE: u32 = 1 << 24;
+    const MAX_DURATION_MS: u32 = (1 << 24) - 1;
+    let u24 = |v: u32| [v as u8, (v >> 8) as u8, (v >> 16) as u8];
+
+    let (width, height) = frames[0].dimensions();
+    if width > MAX_SIDE || height > MAX_SIDE {
+        return Err(JsValue::from_str("Animated WebP frames are limited to 16777216 pixels per side"));
+    }
+
+    let mut body = b"WEBP".to_vec();
+
+    // Animation and alpha flags, then the canvas size
+    let mut vp8x = vec![0x02 | 0x10, 0, 0, 0];
+    vp8x.extend_from_slice(&u24(width - 1));
+    vp8x.extend_from_slice(&u24(height - 1));
+    riff::write_chunk(&mut body, b"VP8X", &vp8x);
+
+    // Transparent background, then the loop count
+    let mut anim = vec![0, 0, 0, 0];
+    anim.extend_from_slice(&(loop_count.min(u16::MAX as u32) as u16).to_le_bytes());
+    riff::write_chunk(&mut body, b"ANIM", &anim);
+
+    for (frame, &delay) in frames.iter().zip(delays_ms) {
+        let mut still = Vec::new();
+        WebPEncoder::new_lossless(&mut still)
+            .encode(frame.as_raw(), width, height, image::ColorType::Rgba8)
+            .map_err(|e| JsValue::from_str(&format!("Failed to encode WebP: {}", e)))?;
+        let bitstream = riff::chunks(&still)
+            .map_while(Result::ok)
+            .find(|chunk| chunk.fourcc == b"VP8L")
+            .ok_or_else(|| JsValue::from_str("Failed to encode WebP: missing VP8L chunk"))?;
+
+        // Offset (0, 0), full-canvas size, duration, then "don't blend, don't dispose"
+        let mut anmf = Vec::with_capacity(16 + bitstream.raw.len());
+        anmf.extend_from_slice(&u24(0));
+        anmf.extend_from_slice(&u24(0));
+        anmf.extend_from_slice(&u24(width - 1));
+        anmf.extend_from_slice(&u24(height - 1));
+        anmf.extend_from_slice(&u24(delay.min(MAX_DURATION_MS)));
+        anmf.push(0x02);
+        anmf.extend_from_slice(bitstream.raw);
+        riff::write_chunk(&mut body, b"ANMF", &anmf);
+    }
+
+    let size = u32::try_from(body.len()).map_err(|_| JsValue::from_str("Animated WebP exceeds 4 GiB"))?;
+    let mut output = b"RIFF".to_vec();
+    output.extend_from_slice(&size.to_le_bytes());
+    output.extend_from_slice(&body);
+    check_output_size(output.len())?;
+    Ok(output)
+}
+
+/// Encode equally sized frames as a GIF sharing one palette of at most
+/// `max_colors` entries, which keeps colors from flickering between frames
+fn encode_paletted_gif(frames: &[RgbaImage], delays_ms: &[u32], max_colors: usize, dither: bool, loop_count: u32) -> Result<Vec<u8>, JsValue> {
+    let (width, height) = frames[0].dimensions();
+    if width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(JsValue::from_str("GIF frames are limited to 65535 pixels per side"));
+    }
+
+    if frames.iter().any(|f| f.dimensions() != (width, height)) {
+        return Err(JsValue::from_str("All frames must have the same dimensions"));
+    }
+
+    // Train the palette on every frame stacked into one tall image, which
+    // copies all of them, so the total is held to the frame budget
+    let mut budget = FrameBudget::new();
+    for _ in frames {
+        budget.spend(width, height)?;
+    }
+    let stacked_height = stacked_height(height, frames.len())
+        .ok_or_else(|| JsValue::from_str("Too many frames to train a shared GIF palette"))?;
+    let stacked: Vec<u8> = frames.iter().flat_map(|f| f.as_raw().iter().copied()).collect();
+    let stacked = RgbaImage::from_raw(width, stacked_height, stacked)
+        .ok_or_else(|| JsValue::from_str("Failed to encode GIF: stacked frame buffer size mismatch"))?;
+    let palette = Palette::adaptive(&stacked, max_colors);
+
+    // GIF has 1-bit transparency: the most transparent entry, if mostly clear
+    let transparent = (0..palette.len())
+        .map(|i| i as u8)
+        .min_by_key(|&i| palette.color(i)[3])
+        .filter(|&i| palette.color(i)[3] < 128);
+
+    let to_js = |e: gif::EncodingError| JsValue::from_str(&format!("Failed to encode GIF: {}", e));
+
+    let mut output = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut output, width as u16, height as u16, &palette.rgb()).map_err(to_js)?;
+        let repeat = if loop_count == 0 { gif::Repeat::Infinite } else { gif::Repeat::Finite(loop_count.min(u16::MAX as u32) as u16) };
+        encoder.set_repeat(repeat).map_err(to_js)?;
+
+        for (frame, delay) in frames.iter().zip(delays_ms) {
+            let indices = if dither { floyd_steinberg(frame, &palette) } else { map_nearest(frame, &palette) };
+            let frame = gif::Frame {
+                width: width as u16,
+                height: height as u16,
+                // GIF delays are in centiseconds
+                delay: (delay.saturating_add(5) / 10).min(u16::MAX as u32) as u16,
+                dispose: gif::DisposalMethod::Background,
+                transparent,
+                buffer: indices.into(),
+                ..Default::default()
+            };
+            encoder.write_frame(&frame).map_err(to_js)?;
+        }
+    }
+
+    Ok(output)
+}
+
+/// Height of `frame_count` frames stacked vertically, if it fits in a u32
+fn stacked_height(height: u32, frame_count: usize) -> Option<u32> {
+    u32::try_from(frame_count).ok().and_then(|count| height.checked_mul(count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+    use crate::limits::{LimitExceeded, set_max_frame_pixels};
+
+    fn gif(width: u16, height: u16, frames: usize) -> Vec<u8> {
+        let mut data = Vec::new();
+        {
+            let mut encoder = gif::Encoder::new(&mut data, width, height, &[0, 0, 0, 255, 255, 255]).unwrap();
+            for _ in 0..frames {
+                let frame = gif::Frame { width: 1, height: 1, buffer: Cow::Owned(vec![1]), ..gif::Frame::default() };
+                encoder.write_frame(&frame).unwrap();
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn decode_gif_checks_logical_screen() {
+        let result = decode_gif(&gif(40_000, 1, 1));
+        assert!(matches!(result, Err(DecodeError::Limit(LimitExceeded { limit: "maxWidth", .. }))));
+    }
+
+    #[test]
+    fn decode_gif_stops_at_frame_budget() {
+        set_max_frame_pixels(250);
+
+        assert_eq!(decode_gif(&gif(10, 10, 2)).unwrap().len(), 2);
+        let result = decode_gif(&gif(10, 10, 3));
+        assert!(matches!(
+            result,
+            Err(DecodeError::Limit(LimitExceeded { limit: "maxFramePixels", value: 300, max: 250 }))
+        ));
+    }
+
+    #[test]
+    fn frame_timeline_accumulates_delays() {
+        assert_eq!(frame_timeline(&[100, 50]), Ok(vec![0, 100, 150]));
+    }
+
+    #[test]
+    fn frame_timeline_rejects_overflow() {
+        assert!(frame_timeline(&[u32::MAX]).is_err());
+        assert!(frame_timeline(&[i32::MAX as u32, 1]).is_err());
+        assert_eq!(frame_timeline(&[i32::MAX as u32]), Ok(vec![0, i32::MAX]));
+    }
+
+    #[test]
+    fn lossless_animated_webp_round_trips() {
+        let red = RgbaImage::from_pixel(3, 2, image::Rgba([255, 0, 0, 255]));
+        let clear = RgbaImage::from_pixel(3, 2, image::Rgba([0, 0, 255, 0]));
+
+        let webp = encode_animated_webp_lossless(&[red.clone(), clear.clone()], &[120, 40], 3).ok().unwrap();
+        let frames = decode_webp(&webp).unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].buffer(), &red);
+        assert_eq!(frames[1].buffer().get_pixel(1, 1)[3], 0);
+        assert_eq!(frames.iter().map(frame_delay_ms).collect::<Vec<_>>(), vec![120, 40]);
+    }
+
+    #[test]
+    fn stacked_height_rejects_overflow() {
+        assert_eq!(stacked_height(100, 3), Some(300));
+        assert_eq!(stacked_height(65_535, 70_000), None);
+        assert_eq!(stacked_height(1, usize::MAX), None);
+    }
+
+    #[test]
+    fn paletted_gif_round_trips() {
+        let red = RgbaImage::from_pixel(32, 32, image::Rgba([255, 0, 0, 255]));
+        let blue = RgbaImage::from_pixel(32, 32, image::Rgba([0, 0, 255, 255]));
+
+        let gif = encode_paletted_gif(&[red, blue], &[100, 200], 16, false, 0).ok().unwrap();
+        let frames = decode_gif(&gif).unwrap();
+
+        // NeuQuant learns the palette, so colors are close rather than exact
+        let close = |a: &image::Rgba<u8>, b: [u8; 3]| (0..3).all(|c| (a[c] as i32 - b[c] as i32).abs() < 32);
+        assert_eq!(frames.len(), 2);
+        assert!(close(frames[0].buffer().get_pixel(0, 0), [255, 0, 0]));
+        assert!(close(frames[1].buffer().get_pixel(31, 31), [0, 0, 255]));
+        assert_eq!(frames.iter().map(frame_delay_ms).collect::<Vec<_>>(), vec![100, 200]);
+    }
+}
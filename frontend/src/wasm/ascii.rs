@@ -0,0 +1,63 @@
+use std::fmt::Write;
+use wasm_bindgen::prelude::*;
+use image::imageops::FilterType;
+use crate::adjustments::luma;
+use crate::image_processor::{ImageProcessor, decode};
+use crate::limits::check_dimensions;
+
+/// Light ramp for light text on a dark background, darkest first
+const DEFAULT_CHARSET: &str = " .:-=+*#%@";
+
+/// Terminal cells are about twice as tall as they are wide
+const CELL_ASPECT: f32 = 0.5;
+
+/// Widest output we generate, in characters
+const MAX_COLUMNS: u32 = 1000;
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Render an image as text, one character per cell
+    ///
+    /// `charset` runs from the character for the darkest pixels to the one
+    /// for the brightest (default `" .:-=+*#%@"`, suited to light text on
+    /// a dark background; reverse it for dark on light). Rows are halved to
+    /// compensate for tall terminal cells. With `colored`, each character
+    /// gets a 24-bit ANSI foreground color and every line ends with a reset.
+    /// Transparent pixels become spaces.
+    #[wasm_bindgen]
+    pub fn to_ascii(&self, image_data: &[u8], columns: u32, charset: Option<String>, colored: bool) -> Result<String, JsValue> {
+        if columns == 0 || columns > MAX_COLUMNS {
+            return Err(JsValue::from_str(&format!("Columns must be between 1 and {}", MAX_COLUMNS)));
+        }
+        let charset: Vec<char> = charset.as_deref().filter(|c| !c.is_empty()).unwrap_or(DEFAULT_CHARSET).chars().collect();
+
+        let img = decode(image_data)?;
+        let rows = ((img.height() as f32 / img.width() as f32 * columns as f32 * CELL_ASPECT).round() as u32).max(1);
+        check_dimensions(columns, rows)?;
+        let cells = img.resize_exact(columns, rows, FilterType::Triangle).to_rgba8();
+
+        let mut text = String::with_capacity((columns as usize + 1) * rows as usize * if colored { 20 } else { 1 });
+        for row in cells.rows() {
+            for px in row {
+                if px[3] < 128 {
+                    text.push(' ');
+                    continue;
+                }
+
+                let level = luma(px[0], px[1], px[2]) as usize;
+                let ch = charset[level * (charset.len() - 1) / 255];
+                if colored {
+                    let _ = write!(text, "\x1b[38;2;{};{};{}m{}", px[0], px[1], px[2], ch);
+                } else {
+                    text.push(ch);
+                }
+            }
+            if colored {
+                text.push_str("\x1b[0m");
+            }
+            text.push('\n');
+        }
+
+        Ok(text)
+    }
+}
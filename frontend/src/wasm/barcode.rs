@@ -0,0 +1,210 @@
+use wasm_bindgen::prelude::*;
+use image::{DynamicImage, GrayImage, Luma};
+use crate::image_processor::{ImageProcessor, encode};
+
+/// Code 128 bar/space widths for symbol values 0-105 (103-105 are the start codes)
+const CODE128_PATTERNS: [&str; 106] = [
+    "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312",
+    "132212", "221213", "221312", "231212", "112232", "122132", "122231", "113222",
+    "123122", "123221", "223211", "221132", "221231", "213212", "223112", "312131",
+    "311222", "321122", "321221", "312212", "322112", "322211", "212123", "212321",
+    "232121", "111323", "131123", "131321", "112313", "132113", "132311", "211313",
+    "231113", "231311", "112133", "112331", "132131", "113123", "113321", "133121",
+    "313121", "211331", "231131", "213113", "213311", "213131", "311123", "311321",
+    "331121", "312113", "312311", "332111", "314111", "221411", "431111", "111224",
+    "111422", "121124", "121421", "141122", "141221", "112214", "112412", "122114",
+    "122411", "142112", "142211", "241211", "221114", "413111", "241112", "134111",
+    "111242", "121142", "121241", "114212", "124112", "124211", "411212", "421112",
+    "421211", "212141", "214121", "412121", "111143", "111341", "131141", "114113",
+    "114311", "411113", "411311", "113141", "114131", "311141", "411131", "211412",
+    "211214", "211232",
+];
+const CODE128_STOP: &str = "2331112";
+const CODE128_START_B: u32 = 104;
+const CODE128_START_C: u32 = 105;
+const CODE128_SWITCH_TO_B: u32 = 100;
+
+/// EAN-13 left-hand "L" (odd parity) digit encodings; R is the complement
+/// and G the reversed complement
+const EAN_L_CODES: [u8; 10] = [
+    0b0001101, 0b0011001, 0b0010011, 0b0111101, 0b0100011,
+    0b0110001, 0b0101111, 0b0111011, 0b0110111, 0b0001011,
+];
+
+/// Parity of digits 2-7 selected by the first digit, bit set = G code
+const EAN_PARITY: [u8; 10] = [
+    0b000000, 0b001011, 0b001101, 0b001110, 0b010011,
+    0b011001, 0b011100, 0b010101, 0b010110, 0b011010,
+];
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Render a 1D barcode as a black-on-white PNG
+    ///
+    /// `symbology` is `code128` or `ean13` (12 digits, or 13 with a valid
+    /// check digit). `module_width` is the narrowest bar in pixels and
+    /// `quiet_zone` the blank margin on each side in modules (10 is the
+    /// usual minimum).
+    #[wasm_bindgen]
+    pub fn generate_barcode(&self, data: &str, symbology: &str, module_width: u32, height: u32, quiet_zone: u32) -> Result<Vec<u8>, JsValue> {
+        let modules = barcode_modules(data, symbology)?;
+        let module_width = module_width.max(1);
+        let width = (modules.len() as u32 + 2 * quiet_zone) * module_width;
+
+        let mut canvas = GrayImage::from_pixel(width, height.max(1), Luma([255]));
+        for (i, _) in modules.iter().enumerate().filter(|(_, &dark)| dark) {
+            let x0 = (quiet_zone + i as u32) * module_width;
+            for x in x0..x0 + module_width {
+                for y in 0..canvas.height() {
+                    canvas.put_pixel(x, y, Luma([0]));
+                }
+            }
+        }
+
+        encode(&DynamicImage::ImageLuma8(canvas), image::ImageOutputFormat::Png)
+    }
+
+    /// Render a 1D barcode as SVG, with the same parameters as `generate_barcode`
+    #[wasm_bindgen]
+    pub fn generate_barcode_svg(&self, data: &str, symbology: &str, module_width: u32, height: u32, quiet_zone: u32) -> Result<String, JsValue> {
+        let modules = barcode_modules(data, symbology)?;
+        let module_width = module_width.max(1);
+        let width = (modules.len() as u32 + 2 * quiet_zone) * module_width;
+        let height = height.max(1);
+
+        // One rectangle per run of adjacent bars
+        let mut path = String::new();
+        let mut i = 0;
+        while i < modules.len() {
+            if !modules[i] {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < modules.len() && modules[i] {
+                i += 1;
+            }
+            let bar = (i - start) as u32 * module_width;
+            path.push_str(&format!(
+                "M{} 0h{}v{}h-{}z",
+                (quiet_zone + start as u32) * module_width,
+                bar,
+                height,
+                bar
+            ));
+        }
+
+        Ok(format!(
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" shape-rendering="crispEdges">"#,
+                r##"<rect width="{w}" height="{h}" fill="#ffffff"/><path d="{path}" fill="#000000"/></svg>"##
+            ),
+            w = width,
+            h = height,
+            path = path
+        ))
+    }
+}
+
+/// Encode `data` as a row of modules, `true` for a bar
+fn barcode_modules(data: &str, symbology: &str) -> Result<Vec<bool>, JsValue> {
+    match symbology.to_lowercase().replace('-', "").as_str() {
+        "code128" => code128(data),
+        "ean13" => ean13(data),
+        _ => Err(JsValue::from_str("Unsupported barcode symbology")),
+    }
+}
+
+/// Code 128 using code set C for all-digit data and code set B otherwise
+fn code128(data: &str) -> Result<Vec<bool>, JsValue> {
+    if data.is_empty() {
+        return Err(JsValue::from_str("Barcode data must not be empty"));
+    }
+
+    let bytes = data.as_bytes();
+    let mut values = Vec::new();
+    if bytes.len() >= 4 && bytes.iter().all(u8::is_ascii_digit) {
+        // Two digits per symbol, finishing an odd count in code set B
+        values.push(CODE128_START_C);
+        for pair in bytes.chunks_exact(2) {
+            values.push(((pair[0] - b'0') * 10 + (pair[1] - b'0')) as u32);
+        }
+        if bytes.len() % 2 == 1 {
+            values.push(CODE128_SWITCH_TO_B);
+            values.push((bytes[bytes.len() - 1] - b' ') as u32);
+        }
+    } else {
+        values.push(CODE128_START_B);
+        for &b in bytes {
+            if !(b' '..=127).contains(&b) {
+                return Err(JsValue::from_str("Code 128 supports printable ASCII only"));
+            }
+            values.push((b - b' ') as u32);
+        }
+    }
+
+    // The start symbol and the first data symbol both have weight 1
+    let checksum = values
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| v * (i as u32).max(1))
+        .sum::<u32>()
+        % 103;
+    values.push(checksum);
+
+    let mut modules = Vec::new();
+    for v in values {
+        push_widths(&mut modules, CODE128_PATTERNS[v as usize]);
+    }
+    push_widths(&mut modules, CODE128_STOP);
+
+    Ok(modules)
+}
+
+/// Append alternating bars and spaces, starting with a bar
+fn push_widths(modules: &mut Vec<bool>, widths: &str) {
+    for (i, w) in widths.bytes().enumerate() {
+        for _ in 0..w - b'0' {
+            modules.push(i % 2 == 0);
+        }
+    }
+}
+
+fn ean13(data: &str) -> Result<Vec<bool>, JsValue> {
+    let digits: Vec<u8> = data.bytes().map(|b| b.wrapping_sub(b'0')).collect();
+    if !(digits.len() == 12 || digits.len() == 13) || digits.iter().any(|&d| d > 9) {
+        return Err(JsValue::from_str("EAN-13 needs 12 or 13 digits"));
+    }
+
+    let sum: u32 = digits[..12]
+        .iter()
+        .enumerate()
+        .map(|(i, &d)| d as u32 * if i % 2 == 0 { 1 } else { 3 })
+        .sum();
+    let check = ((10 - sum % 10) % 10) as u8;
+    if digits.len() == 13 && digits[12] != check {
+        return Err(JsValue::from_str("Invalid EAN-13 check digit"));
+    }
+
+    let mut modules = Vec::with_capacity(95);
+    let mut push_bits = |bits: u8, count: u32| {
+        for i in (0..count).rev() {
+            modules.push(bits >> i & 1 == 1);
+        }
+    };
+
+    push_bits(0b101, 3);
+    let parity = EAN_PARITY[digits[0] as usize];
+    for (i, &d) in digits[1..7].iter().enumerate() {
+        let l = EAN_L_CODES[d as usize];
+        let g = (!l & 0x7f).reverse_bits() >> 1;
+        push_bits(if parity >> (5 - i) & 1 == 1 { g } else { l }, 7);
+    }
+    push_bits(0b01010, 5);
+    for &d in digits[7..12].iter().chain(std::iter::once(&check)) {
+        push_bits(!EAN_L_CODES[d as usize] & 0x7f, 7);
+    }
+    push_bits(0b101, 3);
+
+    Ok(modules)
+}
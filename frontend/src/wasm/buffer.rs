@@ -0,0 +1,67 @@
+use wasm_bindgen::prelude::*;
+use js_sys::Uint8Array;
+
+/// A result kept in WASM memory, for handing multi-megabyte outputs to JS
+/// without copying them across the boundary
+///
+/// `view()` exposes the bytes in place; call `free()` once done with them.
+#[wasm_bindgen]
+pub struct OutputBuffer {
+    data: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl OutputBuffer {
+    /// Byte length of the result
+    #[wasm_bindgen(getter)]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    #[wasm_bindgen]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Address of the first byte in WASM memory, for use with `len` on
+    /// `memory.buffer`
+    #[wasm_bindgen(getter)]
+    pub fn ptr(&self) -> usize {
+        self.data.as_ptr() as usize
+    }
+
+    /// A `Uint8Array` over the result without copying it
+    ///
+    /// The view becomes invalid when this buffer is freed and detaches if
+    /// WASM memory grows, so use it right away (e.g. `new ImageData(...)`,
+    /// `blob` construction or `postMessage` after `slice()`) before calling
+    /// into the module again.
+    #[wasm_bindgen]
+    pub fn view(&self) -> Uint8Array {
+        // Safety: the view borrows `data`, which lives until `free()`; the
+        // caller is told not to keep it across other calls into the module
+        unsafe { Uint8Array::view(&self.data) }
+    }
+
+    /// Copy the result into a caller-provided array, returning the number of
+    /// bytes written; fails if `target` is too small
+    #[wasm_bindgen]
+    pub fn copy_to(&self, target: &Uint8Array) -> Result<usize, JsValue> {
+        if (target.length() as usize) < self.data.len() {
+            return Err(JsValue::from_str(&format!(
+                "Target holds {} bytes but the result needs {}",
+                target.length(),
+                self.data.len()
+            )));
+        }
+
+        target.subarray(0, self.data.len() as u32).copy_from(&self.data);
+        Ok(self.data.len())
+    }
+}
+
+impl From<Vec<u8>> for OutputBuffer {
+    fn from(data: Vec<u8>) -> Self {
+        OutputBuffer { data }
+    }
+}
@@ -33,42 +33,275 @@ fn main() {
     let ts_content = r#"/* tslint:disable */
 /* eslint-disable */
 export function init(module?: WebAssembly.Module): Promise<void>;
+export function init_threads(num_threads: number): Promise<void>;
+export function threads_enabled(): boolean;
+
+export interface EncodeOptions {
+  quality?: number;
+  progressive?: boolean;
+  subsampling?: "4:4:4" | "4:2:2" | "4:2:0";
+  compression?: "fast" | "default" | "best";
+  filter?: "none" | "sub" | "up" | "average" | "paeth" | "adaptive";
+  palette?: boolean;
+  maxColors?: number;
+  lossless?: boolean;
+  embedSrgb?: boolean;
+}
+
+export interface ResourceLimits {
+  maxWidth?: number;
+  maxHeight?: number;
+  maxPixels?: number;
+  maxFramePixels?: number;
+  maxOutputBytes?: number;
+}
+
+export class CancellationToken {
+  free(): void;
+  constructor();
+  static from_shared(flag: Int32Array): CancellationToken;
+  cancel(): void;
+  reset(): void;
+  is_cancelled(): boolean;
+}
 
 export class CryptoModule {
   free(): void;
   constructor();
-  generate_aes_key(): string;
+  static generate_aes_key(): string;
   encrypt_aes(plaintext: string, key_base64: string): string;
   decrypt_aes(ciphertext_base64: string, key_base64: string): string;
+  encrypt_bytes(plaintext: Uint8Array, key_base64: string, algorithm?: "aes-256-gcm" | "chacha20-poly1305"): Uint8Array;
+  decrypt_bytes(combined: Uint8Array, key_base64: string): Uint8Array;
+  decrypt_bytes_legacy(combined: Uint8Array, key_base64: string): Uint8Array;
   hash_sha256(data: string): string;
   hash_sha512(data: string): string;
-  generate_keypair(): any;
+  hash_sha3_256(data: Uint8Array): string;
+  hash_sha3_512(data: Uint8Array): string;
+  hash_keccak256(data: Uint8Array): string;
+  static hash_blob(source: Blob | ReadableStream<Uint8Array>, algorithm?: "sha256" | "sha512" | "sha3-256" | "sha3-512" | "keccak256" | "blake3", on_progress?: (processed: number, total: number | undefined) => void): Promise<string>;
+  blake3_hash(data: Uint8Array): string;
+  blake3_keyed_hash(key_base64: string, data: Uint8Array): string;
+  blake3_derive_key(context: string, key_material: Uint8Array): string;
+  hmac_sha256(key: string, data: string): string;
+  hmac_sha512(key: string, data: string): string;
+  hmac_verify(key: string, data: string, mac: string, algorithm?: "sha256" | "sha512"): boolean;
+  static generate_keypair(): any;
   sign_ed25519(message: string, secret_key_base64: string): string;
   verify_ed25519(message: string, signature_base64: string, public_key_base64: string): boolean;
+  static generate_p256_keypair(): { publicKey: string; secretKey: string };
+  sign_p256(message: string, secret_key_base64: string, format?: "p1363" | "der"): string;
+  verify_p256(message: string, signature_base64: string, public_key_base64: string): boolean;
+  static generate_rsa_keypair(bits: 2048 | 3072 | 4096): { publicKey: string; secretKey: string };
+  sign_rsa(message: string, secret_key_base64: string, scheme?: "pss" | "pkcs1v15"): string;
+  verify_rsa(message: string, signature_base64: string, public_key_base64: string, scheme?: "pss" | "pkcs1v15"): boolean;
+  encrypt_rsa_oaep(plaintext: Uint8Array, public_key_base64: string): Uint8Array;
+  decrypt_rsa_oaep(ciphertext: Uint8Array, secret_key_base64: string): Uint8Array;
+  static generate_secp256k1_keypair(): { publicKey: string; xOnlyPublicKey: string; secretKey: string };
+  sign_secp256k1(message_hash: Uint8Array, secret_key_base64: string): string;
+  verify_secp256k1(message_hash: Uint8Array, signature_base64: string, public_key_base64: string): boolean;
+  recover_secp256k1(message_hash: Uint8Array, signature_base64: string): string;
+  sign_schnorr(message: Uint8Array, secret_key_base64: string): string;
+  verify_schnorr(message: Uint8Array, signature_base64: string, public_key_base64: string): boolean;
+  ethereum_address(public_key_base64: string): string;
+  personal_sign(message: string, secret_key_base64: string): string;
+  recover_personal_sign(message: string, signature_hex: string): string;
+  verify_personal_sign(message: string, signature_hex: string, address: string): boolean;
+  key_to_jwk(key_type: "ed25519" | "p256" | "oct", key_base64: string, is_secret: boolean): JsonWebKey;
+  jwk_to_key(jwk: JsonWebKey | string): { keyType: "ed25519" | "p256" | "oct"; publicKey: string | null; secretKey: string | null };
+  jwk_thumbprint(jwk: JsonWebKey | string): string;
+  static generate_x25519_keypair(): { publicKey: string; secretKey: string };
+  derive_shared_secret(my_secret_base64: string, their_public_base64: string, info?: string): string;
   random_bytes(length: number): string;
+  static generate_mnemonic(strength?: 128 | 160 | 192 | 224 | 256): string;
+  validate_mnemonic(phrase: string): boolean;
+  mnemonic_to_seed(phrase: string, passphrase?: string): string;
+  derive_path(seed_base64: string, path: string, curve?: "secp256k1" | "ed25519"): { secretKey: string; publicKey: string; chainCode: string };
   derive_key_pbkdf2(password: string, salt: string, iterations: number): string;
+  hkdf_sha256(ikm_base64: string, salt_base64: string, info: string, length: number): string;
+  derive_key_argon2id(password: string, salt: string, mem_kib: number, iterations: number, parallelism: number): string;
+  hash_password(password: string, mem_kib?: number, iterations?: number, parallelism?: number): string;
+  verify_password(password: string, phc_hash: string): boolean;
+  recommend_argon2id_params(target_ms?: number): { memKib: number; iterations: number; parallelism: number; estimatedMs: number };
+}
+
+export class EncryptStream {
+  free(): void;
+  constructor(key_base64: string, algorithm?: "aes-256-gcm" | "chacha20-poly1305");
+  push(data: Uint8Array): Uint8Array;
+  finish(): Uint8Array;
+}
+
+export class DecryptStream {
+  free(): void;
+  constructor(key_base64: string);
+  push(data: Uint8Array): Uint8Array;
+  finish(): Uint8Array;
 }
 
 export class ImageProcessor {
   free(): void;
   constructor();
+  set_linear_light(enabled: boolean): void;
+  set_cancellation_token(token: CancellationToken): void;
+  clear_cancellation_token(): void;
+  set_limits(options?: ResourceLimits): void;
+  get_limits(): ResourceLimits;
+  set_cache_budget(bytes: number): void;
+  clear_cache(): void;
+  cache_stats(): { entries: number; bytes: number; budget: number; hits: number; misses: number };
   resize_image(image_data: Uint8Array, width: number, height: number, maintain_aspect: boolean): Uint8Array;
-  convert_format(image_data: Uint8Array, format: string): Uint8Array;
+  convert_format(image_data: Uint8Array, format: string, options?: EncodeOptions): Uint8Array;
   apply_blur(image_data: Uint8Array, sigma: number): Uint8Array;
-  apply_grayscale(image_data: Uint8Array): Uint8Array;
+  apply_grayscale(image_data: Uint8Array, weights?: Float32Array): Uint8Array;
   adjust_brightness(image_data: Uint8Array, value: number): Uint8Array;
   adjust_contrast(image_data: Uint8Array, contrast: number): Uint8Array;
   rotate(image_data: Uint8Array, degrees: number): Uint8Array;
   flip(image_data: Uint8Array, horizontal: boolean): Uint8Array;
   crop(image_data: Uint8Array, x: number, y: number, width: number, height: number): Uint8Array;
-  compress(image_data: Uint8Array, quality: number): Uint8Array;
+  compress(image_data: Uint8Array, quality: number, options?: EncodeOptions): Uint8Array;
+  encode_avif(image_data: Uint8Array, quality: number, speed: number): Uint8Array;
   generate_thumbnail(image_data: Uint8Array, max_width: number, max_height: number): Uint8Array;
+  thumbnail_large(image_data: Uint8Array, max_width: number, max_height: number, format: string, quality: number): Uint8Array;
+  generate_responsive_set(image_data: Uint8Array, widths: Uint32Array, format: string, quality: number): Uint8Array[];
   get_dimensions(image_data: Uint8Array): any;
-  apply_convolution(image_data: Uint8Array, kernel: Float32Array): Uint8Array;
+  inspect(image_data: Uint8Array): { format: string, width: number, height: number, colorType: string, bitDepth: number, channels: number, hasAlpha: boolean, frameCount: number, decodedBytes: number, withinLimits: boolean, iccProfile: { description: string | null, colorSpace: string, deviceClass: string, version: string, size: number } | null };
+  read_metadata(image_data: Uint8Array): any;
+  strip_metadata(image_data: Uint8Array): Uint8Array;
+  apply_watermark(image_data: Uint8Array, watermark_data: Uint8Array, position: string, opacity: number, scale: number): Uint8Array;
+  composite(base_data: Uint8Array, overlay_data: Uint8Array, x: number, y: number, blend_mode: string, opacity: number): Uint8Array;
+  create_collage(images: Uint8Array[], layout: string, spacing: number, background: string): Uint8Array;
+  contact_sheet(images: Uint8Array[], columns: number, cell_size: number, labels?: string[], font_data?: Uint8Array): Uint8Array;
+  draw_text(image_data: Uint8Array, text: string, font_data: Uint8Array, x: number, y: number, size: number, color: string, align: string): Uint8Array;
+  measure_text(text: string, font_data: Uint8Array, size: number): any;
+  generate_placeholder(width: number, height: number, style: string, options?: { colors?: string[]; angle?: number; seed?: string; text?: string; textColor?: string; textSize?: number }, font_data?: Uint8Array): Uint8Array;
+  apply_convolution(image_data: Uint8Array, kernel: Float32Array, divisor?: number, offset?: number, edge_mode?: string): Uint8Array;
+  load(image_data: Uint8Array): LoadedImage;
+  process_batch(images: Uint8Array[], operations_json: string): Array<{ data?: Uint8Array; error?: string }>;
+  decode_to_image_data(image_data: Uint8Array): ImageData;
+  encode_image_data(image_data: ImageData, format: string, quality: number): Uint8Array;
+  encode_rgba(rgba: Uint8Array, width: number, height: number, format: string, quality: number): Uint8Array;
+  get_gif_info(image_data: Uint8Array): any;
+  decode_gif_frames(image_data: Uint8Array): Array<{ data: Uint8Array, delay: number }>;
+  extract_gif_frame(image_data: Uint8Array, index: number): Uint8Array;
+  resize_gif(image_data: Uint8Array, width: number, height: number): Uint8Array;
+  encode_apng(frames: Uint8Array[], delays_ms: Uint32Array, loop_count: number): Uint8Array;
+  decode_webp_frames(image_data: Uint8Array): Array<{ data: Uint8Array, delay: number }>;
+  encode_animated_webp(frames: Uint8Array[], delays_ms: Uint32Array, quality: number, loop_count: number): Uint8Array;
+  frames_to_animation(frames: Uint8Array[], delays_ms: Uint32Array, format: string, options?: { loopCount?: number; maxColors?: number; dither?: boolean; quality?: number }): Uint8Array;
+  get_tiff_page_count(image_data: Uint8Array): number;
+  extract_tiff_page(image_data: Uint8Array, page: number, format: string): Uint8Array;
+  convert_tiff_pages(image_data: Uint8Array, format: string): Uint8Array[];
+  extract_raw_preview(image_data: Uint8Array): Uint8Array;
+  get_raw_info(image_data: Uint8Array): any;
+  generate_favicon(image_data: Uint8Array): Record<string, Uint8Array>;
+  generate_identicon(seed: string, size: number, style?: "github" | "geometric"): Uint8Array;
+  auto_enhance(image_data: Uint8Array, clip_percent: number): Uint8Array;
+  auto_contrast(image_data: Uint8Array, clip_percent: number): Uint8Array;
+  apply_clahe(image_data: Uint8Array, clip_limit: number, tile_grid: number): Uint8Array;
+  adjust_gamma(image_data: Uint8Array, gamma: number): Uint8Array;
+  adjust_temperature(image_data: Uint8Array, kelvin_shift: number, tint: number): Uint8Array;
+  auto_white_balance(image_data: Uint8Array, method: string): Uint8Array;
+  apply_preset(image_data: Uint8Array, preset_name: string): Uint8Array;
+  apply_lut(image_data: Uint8Array, cube_file_bytes: Uint8Array): Uint8Array;
+  channel_mix(image_data: Uint8Array, matrix: Float32Array): Uint8Array;
+  invert(image_data: Uint8Array, preserve_alpha: boolean): Uint8Array;
+  posterize(image_data: Uint8Array, levels: number): Uint8Array;
+  apply_curves(image_data: Uint8Array, curve_points_r?: Float32Array, curve_points_g?: Float32Array, curve_points_b?: Float32Array, curve_points_luma?: Float32Array): Uint8Array;
+  threshold(image_data: Uint8Array, value: number): Uint8Array;
+  threshold_otsu(image_data: Uint8Array): Uint8Array;
+  otsu_threshold(image_data: Uint8Array): number;
+  dither(image_data: Uint8Array, max_colors: number, method: string, grayscale: boolean): Uint8Array;
+  quantize(image_data: Uint8Array, max_colors: number, dither: boolean): Uint8Array;
+  dominant_colors(image_data: Uint8Array, count: number): Array<{ color: string, percentage: number }>;
+  pixelate(image_data: Uint8Array, x: number, y: number, width: number, height: number, block_size: number): Uint8Array;
+  pixelate_all(image_data: Uint8Array, block_size: number): Uint8Array;
+  remove_red_eye(image_data: Uint8Array, regions?: { x: number; y: number; width: number; height: number }[]): Uint8Array;
+  add_noise(image_data: Uint8Array, amount: number, monochrome: boolean, grain_size?: number, seed?: number): Uint8Array;
+  duotone(image_data: Uint8Array, shadow_color: string, highlight_color: string): Uint8Array;
+  gradient_map(image_data: Uint8Array, colors: string[]): Uint8Array;
+  to_ascii(image_data: Uint8Array, columns: number, charset: string | undefined, colored: boolean): string;
+  sharpen(image_data: Uint8Array, amount: number, radius: number, threshold: number): Uint8Array;
+  denoise(image_data: Uint8Array, radius: number): Uint8Array;
+  denoise_bilateral(image_data: Uint8Array, radius: number, sigma_color: number, sigma_space: number): Uint8Array;
+  detect_edges(image_data: Uint8Array, method: string, threshold: number): Uint8Array;
+  blur_region(image_data: Uint8Array, x: number, y: number, width: number, height: number, sigma: number): Uint8Array;
+  detect_faces(image_data: Uint8Array, cascade: Uint8Array, options?: { minSize?: number; maxSize?: number; threshold?: number }): { x: number, y: number, width: number, height: number, score: number }[];
+  blur_faces(image_data: Uint8Array, cascade: Uint8Array, options?: { minSize?: number; maxSize?: number; threshold?: number; padding?: number; style?: "blur" | "pixelate" }): Uint8Array;
+  blur_masked(image_data: Uint8Array, mask_data: Uint8Array, sigma: number): Uint8Array;
+  rotate_arbitrary(image_data: Uint8Array, degrees: number, background_color: string): Uint8Array;
+  skew(image_data: Uint8Array, shear_x: number, shear_y: number, background: string): Uint8Array;
+  correct_distortion(image_data: Uint8Array, k1: number, k2: number): Uint8Array;
+  stitch(images: Uint8Array[], options?: { maxSize?: number }): Uint8Array;
+  smart_crop(image_data: Uint8Array, target_width: number, target_height: number): Uint8Array;
+  smart_crop_box(image_data: Uint8Array, target_width: number, target_height: number): any;
+  auto_trim(image_data: Uint8Array, tolerance: number): { image: Uint8Array, x: number, y: number, width: number, height: number };
+  extend_canvas(image_data: Uint8Array, top: number, right: number, bottom: number, left: number, color: string): Uint8Array;
+  add_border(image_data: Uint8Array, width: number, color: string, radius: number): Uint8Array;
+  round_corners(image_data: Uint8Array, radius: number): Uint8Array;
+  circle_crop(image_data: Uint8Array): Uint8Array;
+  upscale(image_data: Uint8Array, factor: number, algorithm: string): Uint8Array;
+  convert_colors(values: Float32Array, from: string, to: string): Float32Array;
+  extract_icc_profile(image_data: Uint8Array): Uint8Array | undefined;
+  get_icc_info(image_data: Uint8Array): { description: string | null, colorSpace: string, deviceClass: string, version: string, size: number } | null;
+  generate_tile_pyramid(image_data: Uint8Array, tile_size: number, overlap: number, format: string, quality: number, on_tile: (level: number, column: number, row: number, data: Uint8Array) => void): string;
+  thumbhash_encode(image_data: Uint8Array): Uint8Array;
+  thumbhash_decode(hash: Uint8Array): Uint8Array;
+  perceptual_hash(image_data: Uint8Array, method: string): string;
+  hash_distance(hash_a: string, hash_b: string): number;
+  find_duplicates(images: Uint8Array[], threshold: number): { clusters: number[][], hashes: (string | null)[] };
+  compare(image_a: Uint8Array, image_b: Uint8Array): { ssim: number, psnr: number | null };
+  diff_images(image_a: Uint8Array, image_b: Uint8Array, threshold: number): { image: Uint8Array, changedPixels: number, totalPixels: number };
+  generate_qr(text: string, size: number, error_correction: string, fg_color: string, bg_color: string): Uint8Array;
+  generate_qr_svg(text: string, error_correction: string, fg_color: string, bg_color: string): string;
+  decode_qr(image_data: Uint8Array): Array<{ text: string, corners: Array<{ x: number, y: number }> }>;
+  generate_barcode(data: string, symbology: string, module_width: number, height: number, quiet_zone: number): Uint8Array;
+  generate_barcode_svg(data: string, symbology: string, module_width: number, height: number, quiet_zone: number): string;
+  split_channels(image_data: Uint8Array): Uint8Array[];
+  merge_channels(r: Uint8Array, g: Uint8Array, b: Uint8Array, a?: Uint8Array): Uint8Array;
+  apply_mask(image_data: Uint8Array, mask_data: Uint8Array): Uint8Array;
+  extract_alpha(image_data: Uint8Array): Uint8Array;
+  chroma_key(image_data: Uint8Array, key_color: string, tolerance: number, feather: number): Uint8Array;
+  remove_background(image_data: Uint8Array, mask_data: Uint8Array): Uint8Array;
   to_base64(image_data: Uint8Array): string;
   from_base64(base64_str: string): Uint8Array;
 }
 
+export class LoadedImage {
+  free(): void;
+  constructor(image_data: Uint8Array);
+  static from_image_data(image_data: ImageData): LoadedImage;
+  static from_rgba(rgba: Uint8Array, width: number, height: number): LoadedImage;
+  readonly width: number;
+  readonly height: number;
+  resize(width: number, height: number, maintain_aspect: boolean): void;
+  thumbnail(max_width: number, max_height: number): LoadedImage;
+  crop(x: number, y: number, width: number, height: number): LoadedImage;
+  blur(sigma: number): LoadedImage;
+  grayscale(): LoadedImage;
+  brighten(value: number): LoadedImage;
+  contrast(contrast: number): LoadedImage;
+  rotate(degrees: number): void;
+  flip(horizontal: boolean): LoadedImage;
+  clone(): LoadedImage;
+  to_image_data(): ImageData;
+  to_rgba(): Uint8Array;
+  to_rgba_buffer(): OutputBuffer;
+  encode_avif(quality: number, speed: number): Uint8Array;
+  encode(format: string, quality: number): Uint8Array;
+  encode_with_options(format: string, options?: EncodeOptions): Uint8Array;
+  encode_to_buffer(format: string, options?: EncodeOptions): OutputBuffer;
+}
+
+export class OutputBuffer {
+  free(): void;
+  readonly len: number;
+  readonly ptr: number;
+  is_empty(): boolean;
+  view(): Uint8Array;
+  copy_to(target: Uint8Array): number;
+}
+
 export default init;
 "#;
     
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use crate::image_processor::ImageProcessor;
+
+/// Default byte budget for cached results
+pub(crate) const DEFAULT_CACHE_BUDGET: usize = 64 * 1024 * 1024;
+
+/// BLAKE3 digest of the input bytes, and the operation with its parameters
+///
+/// The digest is collision resistant, so a crafted input cannot be served
+/// another image's cached result.
+type CacheKey = ([u8; 32], String);
+
+/// Least-recently-used cache of encoded results, keyed by `CacheKey`
+pub(crate) struct ImageCache {
+    entries: HashMap<CacheKey, Entry>,
+    budget: usize,
+    bytes: usize,
+    /// Incremented on every access; entries remember when they were last used
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+struct Entry {
+    data: Vec<u8>,
+    last_used: u64,
+}
+
+impl ImageCache {
+    pub(crate) fn new(budget: usize) -> Self {
+        ImageCache { entries: HashMap::new(), budget, bytes: 0, clock: 0, hits: 0, misses: 0 }
+    }
+
+    fn get(&mut self, key: &CacheKey) -> Option<Vec<u8>> {
+        self.clock += 1;
+        match self.entries.get_mut(key) {
+            Some(entry) => {
+                entry.last_used = self.clock;
+                self.hits += 1;
+                Some(entry.data.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, key: CacheKey, data: Vec<u8>) {
+        // A result larger than the whole budget would just flush everything else
+        if data.len() > self.budget {
+            return;
+        }
+
+        self.clock += 1;
+        self.bytes += data.len();
+        if let Some(old) = self.entries.insert(key, Entry { data, last_used: self.clock }) {
+            self.bytes -= old.data.len();
+        }
+        self.evict();
+    }
+
+    /// Drop least recently used entries until the cache fits its budget
+    fn evict(&mut self) {
+        while self.bytes > self.budget {
+            let oldest = match self.entries.iter().min_by_key(|(_, entry)| entry.last_used) {
+                Some((key, _)) => key.clone(),
+                None => break,
+            };
+            if let Some(entry) = self.entries.remove(&oldest) {
+                self.bytes -= entry.data.len();
+            }
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Set the memory budget for cached results in bytes; 0 disables caching
+    #[wasm_bindgen]
+    pub fn set_cache_budget(&self, bytes: usize) {
+        let mut cache = self.cache.borrow_mut();
+        cache.budget = bytes;
+        cache.evict();
+    }
+
+    /// Drop all cached results
+    #[wasm_bindgen]
+    pub fn clear_cache(&self) {
+        let mut cache = self.cache.borrow_mut();
+        cache.entries.clear();
+        cache.bytes = 0;
+    }
+
+    /// Cache usage as `{ entries, bytes, budget, hits, misses }`
+    #[wasm_bindgen]
+    pub fn cache_stats(&self) -> Result<JsValue, JsValue> {
+        let cache = self.cache.borrow();
+        let result = serde_json::json!({
+            "entries": cache.entries.len(),
+            "bytes": cache.bytes,
+            "budget": cache.budget,
+            "hits": cache.hits,
+            "misses": cache.misses
+        });
+
+        JsValue::from_serde(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+}
+
+impl ImageProcessor {
+    /// Return the cached result of `operation` on `image_data`, computing and
+    /// storing it on a miss. `operation` must encode every parameter that
+    /// affects the output. Errors are never cached.
+    pub(crate) fn cached<F: FnOnce() -> Result<Vec<u8>, JsValue>>(&self, image_data: &[u8], operation: String, compute: F) -> Result<Vec<u8>, JsValue> {
+        if self.cache.borrow().budget == 0 {
+            return compute();
+        }
+
+        let key = (*blake3::hash(image_data).as_bytes(), operation);
+
+        if let Some(data) = self.cache.borrow_mut().get(&key) {
+            return Ok(data);
+        }
+
+        let data = compute()?;
+        self.cache.borrow_mut().insert(key, data.clone());
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(data: &[u8]) -> CacheKey {
+        (*blake3::hash(data).as_bytes(), "resize:10x10".to_string())
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = ImageCache::new(8);
+        cache.insert(key(b"a"), vec![1; 4]);
+        cache.insert(key(b"b"), vec![2; 4]);
+        assert_eq!(cache.get(&key(b"a")), Some(vec![1; 4]));
+
+        cache.insert(key(b"c"), vec![3; 4]);
+        assert_eq!(cache.get(&key(b"b")), None);
+        assert_eq!(cache.get(&key(b"a")), Some(vec![1; 4]));
+        assert_eq!(cache.bytes, 8);
+    }
+
+    #[test]
+    fn other_input_misses() {
+        let mut cache = ImageCache::new(8);
+        cache.insert(key(b"a"), vec![1; 4]);
+
+        assert_eq!(cache.get(&key(b"ab")), None);
+        assert_eq!((cache.hits, cache.misses), (0, 1));
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use crate::image_processor::ImageProcessor;
+
+const TAG_NEW_SUBFILE_TYPE: u16 = 0x00FE;
+const TAG_COMPRESSION: u16 = 0x0103;
+const TAG_STRIP_OFFSETS: u16 = 0x0111;
+const TAG_STRIP_BYTE_COUNTS: u16 = 0x0117;
+const TAG_SUB_IFDS: u16 = 0x014A;
+const TAG_JPEG_OFFSET: u16 = 0x0201;
+const TAG_JPEG_LENGTH: u16 = 0x0202;
+
+/// Upper bound on IFDs visited, guarding against malformed offset loops
+const MAX_IFDS: usize = 64;
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Extract the largest embedded JPEG preview from a TIFF-based RAW file (DNG, CR2, NEF, ARW)
+    #[wasm_bindgen]
+    pub fn extract_raw_preview(&self, image_data: &[u8]) -> Result<Vec<u8>, JsValue> {
+        find_raw_preview(image_data)
+            .map(|preview| preview.to_vec())
+            .ok_or_else(|| JsValue::from_str("No embedded preview found"))
+    }
+
+    /// Get camera and preview details for a RAW file
+    #[wasm_bindgen]
+    pub fn get_raw_info(&self, image_data: &[u8]) -> Result<JsValue, JsValue> {
+        let preview = find_raw_preview(image_data)
+            .ok_or_else(|| JsValue::from_str("No embedded preview found"))?;
+        // Only the header is needed, so the preview is never decoded
+        let (width, height) = image::io::Reader::with_format(std::io::Cursor::new(preview), image::ImageFormat::Jpeg)
+            .into_dimensions()
+            .map_err(|e| JsValue::from_str(&format!("Failed to load preview: {}", e)))?;
+
+        let metadata = self.read_metadata(image_data)?;
+
+        let result = serde_json::json!({
+            "previewWidth": width,
+            "previewHeight": height,
+            "previewSize": preview.len(),
+        });
+        let info = JsValue::from_serde(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))?;
+
+        js_sys::Reflect::set(&info, &"metadata".into(), &metadata)?;
+        Ok(info)
+    }
+}
+
+struct IfdEntry {
+    tag: u16,
+    field_type: u16,
+    count: u32,
+    value_offset: usize,
+}
+
+/// Minimal reader for walking TIFF IFDs in either byte order
+struct TiffReader<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> TiffReader<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let little_endian = match data.get(..4)? {
+            [0x49, 0x49, 0x2A, 0x00] => true,
+            [0x4D, 0x4D, 0x00, 0x2A] => false,
+            _ => return None,
+        };
+
+        Some(TiffReader { data, little_endian })
+    }
+
+    fn u16_at(&self, offset: usize) -> Option<u16> {
+        let bytes = [*self.data.get(offset)?, *self.data.get(offset.checked_add(1)?)?];
+        Some(if self.little_endian { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+    }
+
+    fn u32_at(&self, offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(offset..offset.checked_add(4)?)?.try_into().ok()?;
+        Some(if self.little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    }
+
+    fn read_ifd(&self, offset: usize) -> Option<(Vec<IfdEntry>, u32)> {
+        let count = self.u16_at(offset)? as usize;
+        let mut entries = Vec::with_capacity(count);
+
+        for i in 0..count {
+            let base = offset + 2 + i * 12;
+            entries.push(IfdEntry {
+                tag: self.u16_at(base)?,
+                field_type: self.u16_at(base + 2)?,
+                count: self.u32_at(base + 4)?,
+                value_offset: base + 8,
+            });
+        }
+
+        let next = self.u32_at(offset + 2 + count * 12).unwrap_or(0);
+        Some((entries, next))
+    }
+
+    /// Read SHORT, LONG or IFD values, following the offset when they don't fit inline
+    fn values(&self, entry: &IfdEntry) -> Vec<u32> {
+        let size = match entry.field_type {
+            3 => 2,
+            4 | 13 => 4,
+            _ => return Vec::new(),
+        };
+
+        let count = entry.count.min(1024) as usize;
+        let start = if size * count <= 4 {
+            entry.value_offset
+        } else {
+            match self.u32_at(entry.value_offset) {
+                Some(offset) => offset as usize,
+                None => return Vec::new(),
+            }
+        };
+
+        (0..count)
+            .filter_map(|i| match size {
+                2 => self.u16_at(start + i * 2).map(u32::from),
+                _ => self.u32_at(start + i * 4),
+            })
+            .collect()
+    }
+}
+
+/// Locate the largest baseline/progressive JPEG referenced by any IFD
+fn find_raw_preview(data: &[u8]) -> Option<&[u8]> {
+    let reader = TiffReader::new(data)?;
+    let mut pending = vec![reader.u32_at(4)? as usize];
+    let mut visited = HashSet::new();
+    let mut best: Option<&[u8]> = None;
+
+    while let Some(offset) = pending.pop() {
+        if offset == 0 || visited.len() >= MAX_IFDS || !visited.insert(offset) {
+            continue;
+        }
+
+        let (entries, next) = match reader.read_ifd(offset) {
+            Some(ifd) => ifd,
+            None => continue,
+        };
+        pending.push(next as usize);
+
+        let value = |tag: u16| entries.iter().find(|e| e.tag == tag).map(|e| reader.values(e));
+        let first = |tag: u16| value(tag).and_then(|v| v.first().copied());
+
+        if let Some(sub_ifds) = value(TAG_SUB_IFDS) {
+            pending.extend(sub_ifds.into_iter().map(|o| o as usize));
+        }
+
+        let mut candidates = Vec::new();
+        if let (Some(start), Some(length)) = (first(TAG_JPEG_OFFSET), first(TAG_JPEG_LENGTH)) {
+            candidates.push((start, length));
+        }
+
+        // Old-style JPEG (6) or JPEG (7) compression stored as a single strip.
+        // Full-resolution DNG raw data (subfile type 0) is lossless JPEG and is skipped below.
+        let compression = first(TAG_COMPRESSION);
+        let is_preview = first(TAG_NEW_SUBFILE_TYPE).is_none_or(|t| t & 1 == 1) || compression == Some(6);
+        if matches!(compression, Some(6) | Some(7)) && is_preview {
+            if let (Some(offsets), Some(counts)) = (value(TAG_STRIP_OFFSETS), value(TAG_STRIP_BYTE_COUNTS)) {
+                if offsets.len() == 1 && counts.len() == 1 {
+                    candidates.push((offsets[0], counts[0]));
+                }
+            }
+        }
+
+        for (start, length) in candidates {
+            // Offsets come from the file; on wasm32 the sum can wrap
+            let Some(end) = (start as usize).checked_add(length as usize) else {
+                continue;
+            };
+            if let Some(jpeg) = data.get(start as usize..end) {
+                if is_displayable_jpeg(jpeg) && best.is_none_or(|b| jpeg.len() > b.len()) {
+                    best = Some(jpeg);
+                }
+            }
+        }
+    }
+
+    best
+}
+
+/// Check the JPEG starts with SOI and uses a baseline or progressive frame (not lossless)
+fn is_displayable_jpeg(data: &[u8]) -> bool {
+    if data.len() < 4 || data[0] != 0xFF || data[1] != 0xD8 {
+        return false;
+    }
+
+    let mut pos = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            return false;
+        }
+
+        match data[pos + 1] {
+            0xC0..=0xC2 => return true,
+            0xC3 | 0xDA => return false,
+            0xFF => pos += 1,
+            _ => {
+                let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+                pos += 2 + length;
+            }
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Little-endian TIFF whose only IFD points at a JPEG with the given length
+    fn tiff_with_jpeg(length: u32) -> Vec<u8> {
+        let mut data = b"II*\0".to_vec();
+        data.extend_from_slice(&8u32.to_le_bytes());
+        data.extend_from_slice(&2u16.to_le_bytes());
+        for (tag, value) in [(TAG_JPEG_OFFSET, 38u32), (TAG_JPEG_LENGTH, length)] {
+            data.extend_from_slice(&tag.to_le_bytes());
+            data.extend_from_slice(&4u16.to_le_bytes());
+            data.extend_from_slice(&1u32.to_le_bytes());
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&0u32.to_le_bytes());
+        // SOI then a baseline SOF0 marker
+        data.extend_from_slice(&[0xFF, 0xD8, 0xFF, 0xC0, 0x00, 0x04, 0x00, 0x00]);
+        data
+    }
+
+    #[test]
+    fn finds_jpeg_preview() {
+        let data = tiff_with_jpeg(8);
+        assert_eq!(find_raw_preview(&data), Some(&data[38..]));
+    }
+
+    #[test]
+    fn oversized_preview_length_is_skipped() {
+        assert_eq!(find_raw_preview(&tiff_with_jpeg(u32::MAX)), None);
+    }
+}
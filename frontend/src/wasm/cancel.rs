@@ -0,0 +1,93 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use js_sys::{Atomics, Int32Array};
+
+thread_local! {
+    /// Token of the `ImageProcessor` call in progress, polled by row loops
+    static ACTIVE: RefCell<Option<CancellationToken>> = const { RefCell::new(None) };
+}
+
+/// Flag that long-running image operations poll so the UI can abort them
+///
+/// While an operation runs, the thread that started it is busy, so a
+/// processor running in a worker should use `from_shared`: the UI thread
+/// then cancels with `Atomics.store(flag, 0, 1)` on the same
+/// `SharedArrayBuffer`. `cancel()` covers tokens shared between queued
+/// operations on one thread.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct CancellationToken {
+    cancelled: Rc<Cell<bool>>,
+    shared: Option<Int32Array>,
+}
+
+#[wasm_bindgen]
+impl CancellationToken {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        CancellationToken { cancelled: Rc::new(Cell::new(false)), shared: None }
+    }
+
+    /// Create a token that is also cancelled by a non-zero first element of
+    /// `flag`, an `Int32Array` over a `SharedArrayBuffer`
+    #[wasm_bindgen]
+    pub fn from_shared(flag: Int32Array) -> CancellationToken {
+        CancellationToken { cancelled: Rc::new(Cell::new(false)), shared: Some(flag) }
+    }
+
+    #[wasm_bindgen]
+    pub fn cancel(&self) {
+        self.cancelled.set(true);
+        if let Some(flag) = &self.shared {
+            let _ = Atomics::store(flag, 0, 1);
+        }
+    }
+
+    /// Clear the flag so the token can be reused for the next operation
+    #[wasm_bindgen]
+    pub fn reset(&self) {
+        self.cancelled.set(false);
+        if let Some(flag) = &self.shared {
+            let _ = Atomics::store(flag, 0, 0);
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.get() || self.shared.as_ref().is_some_and(|flag| Atomics::load(flag, 0).is_ok_and(|v| v != 0))
+    }
+}
+
+/// Run `op` with `token` as the active token, failing with a cancellation
+/// error if it was cancelled before or during the operation
+pub(crate) fn run_cancellable<R, F: FnOnce() -> Result<R, JsValue>>(token: Option<&CancellationToken>, op: F) -> Result<R, JsValue> {
+    let token = match token {
+        Some(token) => token,
+        None => return op(),
+    };
+    if token.is_cancelled() {
+        return Err(JsValue::from_str("Operation cancelled"));
+    }
+
+    ACTIVE.with(|active| *active.borrow_mut() = Some(token.clone()));
+    let result = op();
+    ACTIVE.with(|active| *active.borrow_mut() = None);
+
+    if token.is_cancelled() {
+        Err(JsValue::from_str("Operation cancelled"))
+    } else {
+        result
+    }
+}
+
+/// Whether the running operation can be cancelled at all
+pub(crate) fn cancellation_active() -> bool {
+    ACTIVE.with(|active| active.borrow().is_some())
+}
+
+/// Whether the running operation should stop early; its partial result is
+/// discarded by `run_cancellable`
+pub(crate) fn should_stop() -> bool {
+    ACTIVE.with(|active| active.borrow().as_ref().is_some_and(CancellationToken::is_cancelled))
+}
@@ -0,0 +1,76 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Array, Uint8Array};
+use image::{DynamicImage, GrayImage, Luma, Rgba, RgbaImage};
+use crate::filters::load_mask;
+use crate::image_processor::{ImageProcessor, decode, encode};
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Split an image into R, G, B and A grayscale PNGs (in that order)
+    #[wasm_bindgen]
+    pub fn split_channels(&self, image_data: &[u8]) -> Result<Array, JsValue> {
+        let rgba = decode(image_data)?.to_rgba8();
+        let channels = Array::new();
+
+        for c in 0..4 {
+            let channel = GrayImage::from_fn(rgba.width(), rgba.height(), |x, y| Luma([rgba.get_pixel(x, y)[c]]));
+            let png = encode(&DynamicImage::ImageLuma8(channel), image::ImageOutputFormat::Png)?;
+            channels.push(&Uint8Array::from(png.as_slice()));
+        }
+
+        Ok(channels)
+    }
+
+    /// Recombine grayscale channel images into one RGBA PNG
+    ///
+    /// Color inputs are reduced to luminance. All channels must share the
+    /// same size; without `a` the result is fully opaque.
+    #[wasm_bindgen]
+    pub fn merge_channels(&self, r: &[u8], g: &[u8], b: &[u8], a: Option<Vec<u8>>) -> Result<Vec<u8>, JsValue> {
+        let r = decode(r)?.to_luma8();
+        let g = decode(g)?.to_luma8();
+        let b = decode(b)?.to_luma8();
+        let a = a.map(|a| decode(&a).map(|img| img.to_luma8())).transpose()?;
+
+        let size = r.dimensions();
+        if g.dimensions() != size || b.dimensions() != size || a.as_ref().is_some_and(|a| a.dimensions() != size) {
+            return Err(JsValue::from_str("Channel images must have the same dimensions"));
+        }
+
+        let merged = RgbaImage::from_fn(size.0, size.1, |x, y| {
+            Rgba([
+                r.get_pixel(x, y)[0],
+                g.get_pixel(x, y)[0],
+                b.get_pixel(x, y)[0],
+                a.as_ref().map_or(255, |a| a.get_pixel(x, y)[0]),
+            ])
+        });
+
+        encode(&DynamicImage::ImageRgba8(merged), image::ImageOutputFormat::Png)
+    }
+
+    /// Use a grayscale mask as the alpha channel (white keeps, black cuts out)
+    ///
+    /// The mask is stretched to the image size and multiplied with any
+    /// existing alpha, so repeated masking only ever removes more.
+    #[wasm_bindgen]
+    pub fn apply_mask(&self, image_data: &[u8], mask_data: &[u8]) -> Result<Vec<u8>, JsValue> {
+        let mut rgba = decode(image_data)?.to_rgba8();
+        let mask = load_mask(mask_data, rgba.width(), rgba.height())?;
+
+        for (px, m) in rgba.pixels_mut().zip(mask.pixels()) {
+            px[3] = ((px[3] as u32 * m[0] as u32 + 127) / 255) as u8;
+        }
+
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+
+    /// Extract the alpha channel as a grayscale PNG mask
+    #[wasm_bindgen]
+    pub fn extract_alpha(&self, image_data: &[u8]) -> Result<Vec<u8>, JsValue> {
+        let rgba = decode(image_data)?.to_rgba8();
+        let alpha = GrayImage::from_fn(rgba.width(), rgba.height(), |x, y| Luma([rgba.get_pixel(x, y)[3]]));
+
+        encode(&DynamicImage::ImageLuma8(alpha), image::ImageOutputFormat::Png)
+    }
+}
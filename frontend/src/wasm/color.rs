@@ -0,0 +1,193 @@
+use wasm_bindgen::prelude::*;
+use image::{DynamicImage, Rgba, Rgba32FImage, RgbaImage};
+use crate::adjustments::{linear_to_srgb, srgb_to_linear};
+use crate::image_processor::ImageProcessor;
+
+/// D65 reference white in XYZ
+const WHITE_D65: [f32; 3] = [0.950_47, 1.0, 1.088_83];
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Convert a flat list of color triples between color spaces
+    ///
+    /// Spaces are `srgb` and `linear` (0-1 per channel), `hsv` and `hsl`
+    /// (hue in degrees, the rest 0-1) and `lab` (CIELAB, D65, L 0-100).
+    #[wasm_bindgen]
+    pub fn convert_colors(&self, values: &[f32], from: &str, to: &str) -> Result<Vec<f32>, JsValue> {
+        if !values.len().is_multiple_of(3) {
+            return Err(JsValue::from_str("Color values must come in triples"));
+        }
+        let from = ColorSpace::parse(from)?;
+        let to = ColorSpace::parse(to)?;
+
+        let mut out = Vec::with_capacity(values.len());
+        for triple in values.chunks_exact(3) {
+            let srgb = from.to_srgb([triple[0], triple[1], triple[2]]);
+            out.extend_from_slice(&to.convert_srgb(srgb));
+        }
+
+        Ok(out)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum ColorSpace {
+    Srgb,
+    Linear,
+    Hsv,
+    Hsl,
+    Lab,
+}
+
+impl ColorSpace {
+    fn parse(name: &str) -> Result<Self, JsValue> {
+        match name.to_lowercase().as_str() {
+            "srgb" | "rgb" => Ok(ColorSpace::Srgb),
+            "linear" | "linear-rgb" => Ok(ColorSpace::Linear),
+            "hsv" => Ok(ColorSpace::Hsv),
+            "hsl" => Ok(ColorSpace::Hsl),
+            "lab" | "cielab" => Ok(ColorSpace::Lab),
+            _ => Err(JsValue::from_str("Unsupported color space")),
+        }
+    }
+
+    fn to_srgb(self, c: [f32; 3]) -> [f32; 3] {
+        match self {
+            ColorSpace::Srgb => c,
+            ColorSpace::Linear => c.map(linear_to_srgb),
+            ColorSpace::Hsv => hsv_to_rgb(c),
+            ColorSpace::Hsl => hsl_to_rgb(c),
+            ColorSpace::Lab => lab_to_rgb(c),
+        }
+    }
+
+    fn convert_srgb(self, c: [f32; 3]) -> [f32; 3] {
+        match self {
+            ColorSpace::Srgb => c,
+            ColorSpace::Linear => c.map(srgb_to_linear),
+            ColorSpace::Hsv => rgb_to_hsv(c),
+            ColorSpace::Hsl => rgb_to_hsl(c),
+            ColorSpace::Lab => rgb_to_lab(c),
+        }
+    }
+}
+
+/// Hue in degrees plus max and min channel, shared by HSV and HSL
+fn hue(c: [f32; 3]) -> (f32, f32, f32) {
+    let [r, g, b] = c;
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+
+    (hue, max, min)
+}
+
+/// RGB from hue, chroma and the amount added to every channel
+fn from_hue_chroma(h: f32, chroma: f32, m: f32) -> [f32; 3] {
+    let h = h.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+
+    [r + m, g + m, b + m]
+}
+
+fn rgb_to_hsv(c: [f32; 3]) -> [f32; 3] {
+    let (h, max, min) = hue(c);
+    let s = if max > 0.0 { (max - min) / max } else { 0.0 };
+    [h, s, max]
+}
+
+fn hsv_to_rgb(c: [f32; 3]) -> [f32; 3] {
+    let [h, s, v] = c;
+    let chroma = v * s;
+    from_hue_chroma(h, chroma, v - chroma)
+}
+
+fn rgb_to_hsl(c: [f32; 3]) -> [f32; 3] {
+    let (h, max, min) = hue(c);
+    let l = (max + min) / 2.0;
+    let s = if max == min { 0.0 } else { (max - min) / (1.0 - (2.0 * l - 1.0).abs()) };
+    [h, s, l]
+}
+
+pub(crate) fn hsl_to_rgb(c: [f32; 3]) -> [f32; 3] {
+    let [h, s, l] = c;
+    let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    from_hue_chroma(h, chroma, l - chroma / 2.0)
+}
+
+fn rgb_to_lab(c: [f32; 3]) -> [f32; 3] {
+    let [r, g, b] = c.map(srgb_to_linear);
+    let xyz = [
+        0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b,
+        0.212_672_9 * r + 0.715_152_2 * g + 0.072_175 * b,
+        0.019_333_9 * r + 0.119_192 * g + 0.950_304_1 * b,
+    ];
+
+    let f = |t: f32| if t > 216.0 / 24389.0 { t.cbrt() } else { (24389.0 / 27.0 * t + 16.0) / 116.0 };
+    let fx = f(xyz[0] / WHITE_D65[0]);
+    let fy = f(xyz[1] / WHITE_D65[1]);
+    let fz = f(xyz[2] / WHITE_D65[2]);
+
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+fn lab_to_rgb(c: [f32; 3]) -> [f32; 3] {
+    let [l, a, b] = c;
+    let fy = (l + 16.0) / 116.0;
+    let fx = fy + a / 500.0;
+    let fz = fy - b / 200.0;
+
+    let f_inv = |t: f32| if t.powi(3) > 216.0 / 24389.0 { t.powi(3) } else { (116.0 * t - 16.0) * 27.0 / 24389.0 };
+    let (x, y, z) = (f_inv(fx) * WHITE_D65[0], f_inv(fy) * WHITE_D65[1], f_inv(fz) * WHITE_D65[2]);
+
+    let linear = [
+        3.240_454_2 * x - 1.537_138_5 * y - 0.498_531_4 * z,
+        -0.969_266 * x + 1.876_010_8 * y + 0.041_556 * z,
+        0.055_643_4 * x - 0.204_025_9 * y + 1.057_225_2 * z,
+    ];
+
+    linear.map(|v| linear_to_srgb(v.max(0.0).min(1.0)))
+}
+
+/// Run a resampling operation in linear light with premultiplied alpha,
+/// which avoids the dark fringes of blurring or resizing sRGB values
+pub(crate) fn in_linear_light<F: FnOnce(DynamicImage) -> DynamicImage>(img: &DynamicImage, op: F) -> DynamicImage {
+    let rgba = img.to_rgba8();
+    let linear = Rgba32FImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let px = rgba.get_pixel(x, y);
+        let alpha = px[3] as f32 / 255.0;
+        let c = |v: u8| srgb_to_linear(v as f32 / 255.0) * alpha;
+        Rgba([c(px[0]), c(px[1]), c(px[2]), alpha])
+    });
+
+    let processed = op(DynamicImage::ImageRgba32F(linear)).into_rgba32f();
+    let out = RgbaImage::from_fn(processed.width(), processed.height(), |x, y| {
+        let px = processed.get_pixel(x, y);
+        let alpha = px[3].max(0.0).min(1.0);
+        let c = |v: f32| {
+            let v = if alpha > 0.0 { v / alpha } else { 0.0 };
+            (linear_to_srgb(v.max(0.0).min(1.0)) * 255.0).round() as u8
+        };
+        Rgba([c(px[0]), c(px[1]), c(px[2]), (alpha * 255.0).round() as u8])
+    });
+
+    DynamicImage::ImageRgba8(out)
+}
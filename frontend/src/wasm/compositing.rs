@@ -0,0 +1,363 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Array, Uint8Array};
+use ab_glyph::FontVec;
+use image::{DynamicImage, Rgba, RgbaImage, imageops::FilterType};
+use crate::image_processor::{ImageProcessor, decode, decode_array, encode, parse_color, parse_options};
+use crate::text::{TextAlign, draw_text, load_font, measure_text};
+
+/// Longest collage side we are willing to allocate
+const MAX_COLLAGE_SIDE: u32 = 16384;
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Composite a watermark onto an image
+    ///
+    /// `position` is one of `top-left`, `top-right`, `bottom-left`,
+    /// `bottom-right`, `center` or `tiled`. `scale` is the watermark width as a
+    /// fraction of the base image width; 0 keeps the watermark's own size.
+    #[wasm_bindgen]
+    pub fn apply_watermark(&self, image_data: &[u8], watermark_data: &[u8], position: &str, opacity: f32, scale: f32) -> Result<Vec<u8>, JsValue> {
+        let mut base = decode(image_data)?.to_rgba8();
+        let mut watermark = decode(watermark_data)?;
+
+        let (width, height) = base.dimensions();
+
+        if scale > 0.0 {
+            let target_width = ((width as f32 * scale).round() as u32).max(1);
+            let target_height = ((watermark.height() as f32 * target_width as f32 / watermark.width() as f32).round() as u32).max(1);
+            watermark = watermark.resize_exact(target_width, target_height, FilterType::Lanczos3);
+        }
+
+        let watermark = watermark.to_rgba8();
+        let (wm_width, wm_height) = watermark.dimensions();
+        let opacity = opacity.max(0.0).min(1.0);
+        let margin = (width.min(height) / 50) as i64;
+
+        let right = width as i64 - wm_width as i64 - margin;
+        let bottom = height as i64 - wm_height as i64 - margin;
+
+        match position.to_lowercase().as_str() {
+            "top-left" => blend_over(&mut base, &watermark, margin, margin, opacity),
+            "top-right" => blend_over(&mut base, &watermark, right, margin, opacity),
+            "bottom-left" => blend_over(&mut base, &watermark, margin, bottom, opacity),
+            "bottom-right" => blend_over(&mut base, &watermark, right, bottom, opacity),
+            "center" => blend_over(
+                &mut base,
+                &watermark,
+                (width as i64 - wm_width as i64) / 2,
+                (height as i64 - wm_height as i64) / 2,
+                opacity,
+            ),
+            "tiled" => {
+                // Leave a gap the size of the margin between tiles
+                let step_x = wm_width as i64 + margin.max(1);
+                let step_y = wm_height as i64 + margin.max(1);
+                let mut y = 0;
+                while y < height as i64 {
+                    let mut x = 0;
+                    while x < width as i64 {
+                        blend_over(&mut base, &watermark, x, y, opacity);
+                        x += step_x;
+                    }
+                    y += step_y;
+                }
+            }
+            _ => return Err(JsValue::from_str("Unsupported watermark position")),
+        }
+
+        encode(&DynamicImage::ImageRgba8(base), image::ImageOutputFormat::Png)
+    }
+
+    /// Flatten `overlay` onto `base` at (`x`, `y`) with a blend mode
+    ///
+    /// `blend_mode` is one of `normal`, `multiply`, `screen`, `overlay`,
+    /// `darken` or `lighten`. The overlay may extend past the base edges
+    /// and is clipped.
+    #[wasm_bindgen]
+    pub fn composite(&self, base_data: &[u8], overlay_data: &[u8], x: i32, y: i32, blend_mode: &str, opacity: f32) -> Result<Vec<u8>, JsValue> {
+        let mode = BlendMode::parse(blend_mode)?;
+        let mut base = decode(base_data)?.to_rgba8();
+        let overlay = decode(overlay_data)?.to_rgba8();
+        let opacity = opacity.max(0.0).min(1.0);
+
+        let (width, height) = base.dimensions();
+        for (ox, oy, src) in overlay.enumerate_pixels() {
+            let bx = x as i64 + ox as i64;
+            let by = y as i64 + oy as i64;
+            if bx < 0 || by < 0 || bx >= width as i64 || by >= height as i64 {
+                continue;
+            }
+
+            let dst = base.get_pixel_mut(bx as u32, by as u32);
+            *dst = blend_pixel(*dst, *src, opacity, mode);
+        }
+
+        encode(&DynamicImage::ImageRgba8(base), image::ImageOutputFormat::Png)
+    }
+
+    /// Arrange several images into a single collage
+    ///
+    /// `layout` is `horizontal` (one row, common height), `vertical` (one
+    /// column, common width), `grid` (roughly square) or `grid-N` (N columns).
+    /// Grid cells take the smallest input's size and images are center-cropped
+    /// to fill them. `spacing` pixels of `background` separate the images and
+    /// frame the collage.
+    #[wasm_bindgen]
+    pub fn create_collage(&self, images: &Array, layout: &str, spacing: u32, background: &str) -> Result<Vec<u8>, JsValue> {
+        let images = decode_array(images)?;
+        if images.is_empty() {
+            return Err(JsValue::from_str("Collage needs at least one image"));
+        }
+        if spacing > MAX_COLLAGE_SIDE {
+            return Err(JsValue::from_str("Collage is too large"));
+        }
+        let background = parse_color(background)?;
+        let layout = layout.to_lowercase();
+
+        // Each entry is (image, x, y) on the final canvas
+        let mut placed = Vec::with_capacity(images.len());
+        let (width, height) = match layout.as_str() {
+            "horizontal" => {
+                let row_height = images.iter().map(|img| img.height()).min().unwrap_or(1);
+                let mut x = spacing;
+                for img in &images {
+                    let w = ((img.width() as u64 * row_height as u64 / img.height() as u64) as u32).max(1);
+                    placed.push((img.resize_exact(w, row_height, FilterType::Lanczos3).to_rgba8(), x, spacing));
+                    x += w + spacing;
+                }
+                (x, row_height + 2 * spacing)
+            }
+            "vertical" => {
+                let column_width = images.iter().map(|img| img.width()).min().unwrap_or(1);
+                let mut y = spacing;
+                for img in &images {
+                    let h = ((img.height() as u64 * column_width as u64 / img.width() as u64) as u32).max(1);
+                    placed.push((img.resize_exact(column_width, h, FilterType::Lanczos3).to_rgba8(), spacing, y));
+                    y += h + spacing;
+                }
+                (column_width + 2 * spacing, y)
+            }
+            grid if grid == "grid" || grid.starts_with("grid-") => {
+                let columns = match grid.strip_prefix("grid-") {
+                    Some(n) => n.parse::<u32>().ok().filter(|&n| n > 0).ok_or_else(|| JsValue::from_str("Invalid grid column count"))?,
+                    None => (images.len() as f64).sqrt().ceil() as u32,
+                }
+                .min(images.len() as u32);
+                let rows = (images.len() as u32).div_ceil(columns);
+                let cell_width = images.iter().map(|img| img.width()).min().unwrap_or(1);
+                let cell_height = images.iter().map(|img| img.height()).min().unwrap_or(1);
+
+                for (i, img) in images.iter().enumerate() {
+                    let (col, row) = (i as u32 % columns, i as u32 / columns);
+                    placed.push((
+                        img.resize_to_fill(cell_width, cell_height, FilterType::Lanczos3).to_rgba8(),
+                        spacing + col * (cell_width + spacing),
+                        spacing + row * (cell_height + spacing),
+                    ));
+                }
+                (
+                    spacing + columns * (cell_width + spacing),
+                    spacing + rows * (cell_height + spacing),
+                )
+            }
+            _ => return Err(JsValue::from_str("Unsupported collage layout")),
+        };
+
+        if width > MAX_COLLAGE_SIDE || height > MAX_COLLAGE_SIDE {
+            return Err(JsValue::from_str("Collage is too large"));
+        }
+
+        let mut canvas = RgbaImage::from_pixel(width, height, background);
+        for (img, x, y) in &placed {
+            blend_over(&mut canvas, img, *x as i64, *y as i64, 1.0);
+        }
+
+        encode(&DynamicImage::ImageRgba8(canvas), image::ImageOutputFormat::Png)
+    }
+
+    /// Lay thumbnails out on a white grid, e.g. for album overviews
+    ///
+    /// Each image is fit inside a `cell_size` square and centered.
+    /// `labels` is an optional array of captions (such as file names) drawn
+    /// under the cells in order, which requires `font_data`; captions too
+    /// long for a cell are shortened with an ellipsis. Images that fail to
+    /// decode are shown as gray cells so the sheet still lines up with the
+    /// input.
+    #[wasm_bindgen]
+    pub fn contact_sheet(&self, images: &Array, columns: u32, cell_size: u32, labels: JsValue, font_data: Option<Vec<u8>>) -> Result<Vec<u8>, JsValue> {
+        if images.length() == 0 {
+            return Err(JsValue::from_str("Contact sheet needs at least one image"));
+        }
+        if columns == 0 || cell_size == 0 {
+            return Err(JsValue::from_str("Columns and cell size must be non-zero"));
+        }
+
+        let labels: Vec<String> = parse_options(&labels)?;
+        let font = match (&font_data, labels.is_empty()) {
+            (_, true) => None,
+            (Some(data), false) => Some(load_font(data)?),
+            (None, false) => return Err(JsValue::from_str("Contact sheet labels require font_data")),
+        };
+
+        let count = images.length();
+        let columns = columns.min(count);
+        let rows = count.div_ceil(columns);
+        let gap = (cell_size / 16).max(4);
+        let text_size = (cell_size as f32 / 10.0).max(10.0);
+        let label_height = if font.is_some() { (text_size * 1.5).ceil() as u32 } else { 0 };
+
+        let (row_height, column_width) = (cell_size as u64 + label_height as u64 + gap as u64, cell_size as u64 + gap as u64);
+        let width = gap as u64 + columns as u64 * column_width;
+        let height = gap as u64 + rows as u64 * row_height;
+        if width > MAX_COLLAGE_SIDE as u64 || height > MAX_COLLAGE_SIDE as u64 {
+            return Err(JsValue::from_str("Contact sheet is too large"));
+        }
+
+        let mut canvas = RgbaImage::from_pixel(width as u32, height as u32, Rgba([255, 255, 255, 255]));
+        for (i, value) in images.iter().enumerate() {
+            let (col, row) = (i as u32 % columns, i as u32 / columns);
+            let (x, y) = (gap + col * (cell_size + gap), gap + row * (cell_size + label_height + gap));
+
+            let bytes = Uint8Array::new(&value).to_vec();
+            match decode(&bytes) {
+                Ok(img) => {
+                    let thumb = img.thumbnail(cell_size, cell_size).to_rgba8();
+                    let (dx, dy) = ((cell_size - thumb.width()) / 2, (cell_size - thumb.height()) / 2);
+                    blend_over(&mut canvas, &thumb, (x + dx) as i64, (y + dy) as i64, 1.0);
+                }
+                Err(_) => {
+                    let placeholder = RgbaImage::from_pixel(cell_size, cell_size, Rgba([229, 231, 235, 255]));
+                    blend_over(&mut canvas, &placeholder, x as i64, y as i64, 1.0);
+                }
+            }
+
+            if let (Some(font), Some(label)) = (&font, labels.get(i)) {
+                let label = fit_label(font, label, text_size, cell_size);
+                let center = x as f32 + cell_size as f32 / 2.0;
+                let top = (y + cell_size) as f32 + (label_height as f32 - text_size) / 2.0;
+                draw_text(&mut canvas, font, &label, center, top, text_size, Rgba([55, 65, 81, 255]), TextAlign::Center);
+            }
+        }
+
+        encode(&DynamicImage::ImageRgba8(canvas), image::ImageOutputFormat::Png)
+    }
+}
+
+/// Shorten `label` with a trailing ellipsis until it fits in `max_width`
+fn fit_label(font: &FontVec, label: &str, size: f32, max_width: u32) -> String {
+    let label = label.lines().next().unwrap_or("");
+    if measure_text(font, label, size).0 <= max_width {
+        return label.to_string();
+    }
+
+    let mut chars: Vec<char> = label.chars().collect();
+    while !chars.is_empty() {
+        chars.pop();
+        let candidate: String = chars.iter().collect::<String>() + "\u{2026}";
+        if measure_text(font, &candidate, size).0 <= max_width {
+            return candidate;
+        }
+    }
+    String::new()
+}
+
+/// Separable blend modes from the W3C compositing spec
+#[derive(Clone, Copy, PartialEq)]
+enum BlendMode {
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+}
+
+impl BlendMode {
+    fn parse(name: &str) -> Result<Self, JsValue> {
+        match name.to_lowercase().as_str() {
+            "normal" => Ok(BlendMode::Normal),
+            "multiply" => Ok(BlendMode::Multiply),
+            "screen" => Ok(BlendMode::Screen),
+            "overlay" => Ok(BlendMode::Overlay),
+            "darken" => Ok(BlendMode::Darken),
+            "lighten" => Ok(BlendMode::Lighten),
+            _ => Err(JsValue::from_str("Unsupported blend mode")),
+        }
+    }
+
+    /// Blend one channel, both values in 0..=1
+    fn apply(self, backdrop: f32, source: f32) -> f32 {
+        match self {
+            BlendMode::Normal => source,
+            BlendMode::Multiply => backdrop * source,
+            BlendMode::Screen => backdrop + source - backdrop * source,
+            BlendMode::Overlay => {
+                // Hard light with the layers swapped
+                if backdrop <= 0.5 {
+                    2.0 * backdrop * source
+                } else {
+                    1.0 - 2.0 * (1.0 - backdrop) * (1.0 - source)
+                }
+            }
+            BlendMode::Darken => backdrop.min(source),
+            BlendMode::Lighten => backdrop.max(source),
+        }
+    }
+}
+
+/// Blend `src` onto `dst` with `mode`, then composite "source over"
+///
+/// Where the backdrop is transparent the source color is used unchanged, as
+/// in the W3C model.
+fn blend_pixel(dst: Rgba<u8>, src: Rgba<u8>, opacity: f32, mode: BlendMode) -> Rgba<u8> {
+    if mode == BlendMode::Normal {
+        return alpha_blend(dst, src, opacity);
+    }
+
+    let dst_alpha = dst[3] as f32 / 255.0;
+    let mut mixed = src;
+    for c in 0..3 {
+        let backdrop = dst[c] as f32 / 255.0;
+        let source = src[c] as f32 / 255.0;
+        let value = (1.0 - dst_alpha) * source + dst_alpha * mode.apply(backdrop, source);
+        mixed[c] = (value * 255.0).round().max(0.0).min(255.0) as u8;
+    }
+
+    alpha_blend(dst, mixed, opacity)
+}
+
+/// Alpha-blend `top` onto `base` at the given offset, clipping to the base bounds
+pub(crate) fn blend_over(base: &mut RgbaImage, top: &RgbaImage, x: i64, y: i64, opacity: f32) {
+    let (width, height) = base.dimensions();
+
+    for (tx, ty, src) in top.enumerate_pixels() {
+        let bx = x + tx as i64;
+        let by = y + ty as i64;
+        if bx < 0 || by < 0 || bx >= width as i64 || by >= height as i64 {
+            continue;
+        }
+
+        let dst = base.get_pixel_mut(bx as u32, by as u32);
+        *dst = alpha_blend(*dst, *src, opacity);
+    }
+}
+
+/// Porter-Duff "source over" for a single pixel
+pub(crate) fn alpha_blend(dst: Rgba<u8>, src: Rgba<u8>, opacity: f32) -> Rgba<u8> {
+    let src_alpha = src[3] as f32 / 255.0 * opacity;
+    let dst_alpha = dst[3] as f32 / 255.0;
+    let out_alpha = src_alpha + dst_alpha * (1.0 - src_alpha);
+
+    if out_alpha <= 0.0 {
+        return Rgba([0, 0, 0, 0]);
+    }
+
+    let mut out = [0u8; 4];
+    for c in 0..3 {
+        let value = (src[c] as f32 * src_alpha + dst[c] as f32 * dst_alpha * (1.0 - src_alpha)) / out_alpha;
+        out[c] = value.round().max(0.0).min(255.0) as u8;
+    }
+    out[3] = (out_alpha * 255.0).round() as u8;
+
+    Rgba(out)
+}
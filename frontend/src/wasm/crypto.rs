@@ -1,14 +1,156 @@
 use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use wasm_bindgen_futures::JsFuture;
+use js_sys::{Function, Promise, Reflect, Uint8Array};
 use web_sys::console;
 use sha2::{Sha256, Sha512, Digest};
+use sha3::{Keccak256, Sha3_256, Sha3_512};
 use aes_gcm::{
-    aead::{Aead, KeyInit, OsRng},
+    aead::{Aead, KeyInit, OsRng, Payload},
     Aes256Gcm, Key, Nonce
 };
-use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signature, Signer, Verifier};
+use chacha20poly1305::ChaCha20Poly1305;
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use rand::rngs::OsRng as RandOsRng;
 use base64::{Engine as _, engine::general_purpose};
 
+/// Marks ciphertexts that start with a header; older ones are a bare
+/// AES-GCM nonce and ciphertext
+pub(crate) const HEADER_MAGIC: [u8; 2] = *b"LC";
+const HEADER_VERSION: u8 = 1;
+/// Magic, version and algorithm id
+const HEADER_LEN: usize = 4;
+const NONCE_LEN: usize = 12;
+
+/// Argon2id memory the benchmark starts from and will not go below, the
+/// OWASP minimum of 19 MiB
+const ARGON2_MIN_MEMORY_KIB: u32 = 19 * 1024;
+/// Memory ceiling for Argon2id, well inside what a WASM instance can grow to
+const ARGON2_MAX_MEMORY_KIB: u32 = 1024 * 1024;
+/// Recommended parameters stop adding memory here and add iterations instead
+const ARGON2_RECOMMENDED_MAX_MEMORY_KIB: u32 = 256 * 1024;
+
+/// Authenticated ciphers for `encrypt_bytes`, both taking 32-byte keys
+#[derive(Clone, Copy)]
+pub(crate) enum CipherAlgorithm {
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+impl CipherAlgorithm {
+    pub(crate) fn parse(name: Option<&str>) -> Result<Self, JsValue> {
+        match name.map(str::to_lowercase).as_deref() {
+            None | Some("aes-256-gcm") => Ok(CipherAlgorithm::Aes256Gcm),
+            Some("chacha20-poly1305") => Ok(CipherAlgorithm::ChaCha20Poly1305),
+            Some(other) => Err(JsValue::from_str(&format!(
+                "Unknown cipher algorithm: {} (expected aes-256-gcm or chacha20-poly1305)",
+                other
+            ))),
+        }
+    }
+
+    pub(crate) fn id(self) -> u8 {
+        match self {
+            CipherAlgorithm::Aes256Gcm => 1,
+            CipherAlgorithm::ChaCha20Poly1305 => 2,
+        }
+    }
+
+    pub(crate) fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(CipherAlgorithm::Aes256Gcm),
+            2 => Some(CipherAlgorithm::ChaCha20Poly1305),
+            _ => None,
+        }
+    }
+
+    /// Encrypt with `aad` authenticated alongside the ciphertext
+    pub(crate) fn encrypt(self, key: &[u8; 32], nonce: &[u8], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, aes_gcm::aead::Error> {
+        let nonce = Nonce::from_slice(nonce);
+        let payload = Payload { msg: plaintext, aad };
+        match self {
+            CipherAlgorithm::Aes256Gcm => Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)).encrypt(nonce, payload),
+            CipherAlgorithm::ChaCha20Poly1305 => ChaCha20Poly1305::new(key.into()).encrypt(nonce, payload),
+        }
+    }
+
+    /// Decrypt, failing unless `aad` matches what was passed to `encrypt`
+    pub(crate) fn decrypt(self, key: &[u8; 32], nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>, aes_gcm::aead::Error> {
+        let nonce = Nonce::from_slice(nonce);
+        let payload = Payload { msg: ciphertext, aad };
+        match self {
+            CipherAlgorithm::Aes256Gcm => Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)).decrypt(nonce, payload),
+            CipherAlgorithm::ChaCha20Poly1305 => ChaCha20Poly1305::new(key.into()).decrypt(nonce, payload),
+        }
+    }
+}
+
+/// Encrypt into the headered layout: magic, version and algorithm id, the
+/// nonce, then the ciphertext and tag
+///
+/// The header is passed to the cipher as associated data, so changing the
+/// algorithm id or version byte makes decryption fail instead of handing the
+/// ciphertext to a different cipher.
+fn seal(algorithm: CipherAlgorithm, key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, aes_gcm::aead::Error> {
+    let header = [HEADER_MAGIC[0], HEADER_MAGIC[1], HEADER_VERSION, algorithm.id()];
+    let nonce_bytes = rand::random::<[u8; NONCE_LEN]>();
+    let ciphertext = algorithm.encrypt(key, &nonce_bytes, plaintext, &header)?;
+
+    let mut result = Vec::with_capacity(HEADER_LEN + NONCE_LEN + ciphertext.len());
+    result.extend_from_slice(&header);
+    result.extend_from_slice(&nonce_bytes);
+    result.extend_from_slice(&ciphertext);
+    Ok(result)
+}
+
+/// Decrypt the output of `seal`, or a headerless legacy ciphertext
+///
+/// Data starting with a current header is only ever read as headered; if it
+/// fails to authenticate, that is the error, with no second attempt under
+/// the legacy layout. A legacy nonce starts with the header bytes by chance
+/// about once in 16 million ciphertexts; `decrypt_bytes_legacy` reads those.
+fn open(key: &[u8; 32], combined: &[u8]) -> Result<Vec<u8>, &'static str> {
+    let header = match combined.get(..HEADER_LEN) {
+        Some(header) if header[..2] == HEADER_MAGIC && header[2] == HEADER_VERSION => header,
+        _ => return open_legacy(key, combined),
+    };
+    let algorithm = CipherAlgorithm::from_id(header[3]).ok_or("Unknown cipher algorithm in ciphertext header")?;
+    if combined.len() < HEADER_LEN + NONCE_LEN {
+        return Err("Invalid ciphertext length");
+    }
+
+    let (nonce_bytes, ciphertext) = combined[HEADER_LEN..].split_at(NONCE_LEN);
+    algorithm
+        .decrypt(key, nonce_bytes, ciphertext, header)
+        .map_err(|_| "Decryption failed: ciphertext is corrupted, its header was changed or the key is wrong")
+}
+
+/// Encrypt into the legacy layout: a bare AES-256-GCM nonce and ciphertext
+#[cfg(test)]
+fn seal_legacy(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, aes_gcm::aead::Error> {
+    let nonce_bytes = rand::random::<[u8; NONCE_LEN]>();
+    let ciphertext = CipherAlgorithm::Aes256Gcm.encrypt(key, &nonce_bytes, plaintext, &[])?;
+
+    let mut result = nonce_bytes.to_vec();
+    result.extend_from_slice(&ciphertext);
+    Ok(result)
+}
+
+/// Decrypt the output of `seal_legacy`
+fn open_legacy(key: &[u8; 32], combined: &[u8]) -> Result<Vec<u8>, &'static str> {
+    if combined.len() < NONCE_LEN {
+        return Err("Invalid ciphertext length");
+    }
+
+    let (nonce_bytes, ciphertext) = combined.split_at(NONCE_LEN);
+    CipherAlgorithm::Aes256Gcm
+        .decrypt(key, nonce_bytes, ciphertext, &[])
+        .map_err(|_| "Decryption failed: ciphertext is corrupted or the key is wrong")
+}
+
 #[wasm_bindgen]
 pub struct CryptoModule {
     // Internal state if needed
@@ -29,58 +171,62 @@ impl CryptoModule {
         Ok(general_purpose::STANDARD.encode(key))
     }
 
-    /// Encrypt data using AES-256-GCM
+    /// Encrypt a string with AES-256-GCM, returning `encrypt_bytes` output
+    /// as base64
     #[wasm_bindgen]
     pub fn encrypt_aes(&self, plaintext: &str, key_base64: &str) -> Result<String, JsValue> {
-        let key_bytes = general_purpose::STANDARD
-            .decode(key_base64)
-            .map_err(|e| JsValue::from_str(&format!("Invalid key: {}", e)))?;
-        
-        let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
-        let cipher = Aes256Gcm::new(key);
-        
-        let nonce_bytes = rand::random::<[u8; 12]>();
-        let nonce = Nonce::from_slice(&nonce_bytes);
-        
-        let ciphertext = cipher
-            .encrypt(nonce, plaintext.as_bytes())
-            .map_err(|e| JsValue::from_str(&format!("Encryption failed: {}", e)))?;
-        
-        // Combine nonce and ciphertext
-        let mut result = nonce_bytes.to_vec();
-        result.extend_from_slice(&ciphertext);
-        
-        Ok(general_purpose::STANDARD.encode(result))
+        let combined = self.encrypt_bytes(plaintext.as_bytes(), key_base64, None)?;
+        Ok(general_purpose::STANDARD.encode(combined))
     }
 
-    /// Decrypt data using AES-256-GCM
+    /// Decrypt a string produced by `encrypt_aes`, including headerless
+    /// ciphertexts from before it wrote a header
     #[wasm_bindgen]
     pub fn decrypt_aes(&self, ciphertext_base64: &str, key_base64: &str) -> Result<String, JsValue> {
-        let key_bytes = general_purpose::STANDARD
-            .decode(key_base64)
-            .map_err(|e| JsValue::from_str(&format!("Invalid key: {}", e)))?;
-        
         let combined = general_purpose::STANDARD
             .decode(ciphertext_base64)
             .map_err(|e| JsValue::from_str(&format!("Invalid ciphertext: {}", e)))?;
         
-        if combined.len() < 12 {
-            return Err(JsValue::from_str("Invalid ciphertext length"));
-        }
-        
-        let (nonce_bytes, ciphertext) = combined.split_at(12);
-        let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
-        let cipher = Aes256Gcm::new(key);
-        let nonce = Nonce::from_slice(nonce_bytes);
-        
-        let plaintext = cipher
-            .decrypt(nonce, ciphertext)
-            .map_err(|e| JsValue::from_str(&format!("Decryption failed: {}", e)))?;
-        
+        let plaintext = self.decrypt_bytes(&combined, key_base64)?;
         String::from_utf8(plaintext)
             .map_err(|e| JsValue::from_str(&format!("Invalid UTF-8: {}", e)))
     }
 
+    /// Encrypt binary data with an authenticated cipher
+    ///
+    /// `algorithm` is `aes-256-gcm` (default) or `chacha20-poly1305`, which
+    /// is faster in WASM where AES has no hardware support. Both take the
+    /// 32-byte key from `generate_aes_key`. Returns a 4-byte header naming
+    /// the algorithm, the 12-byte nonce, then the ciphertext and tag; the
+    /// header is authenticated along with the ciphertext.
+    #[wasm_bindgen]
+    pub fn encrypt_bytes(&self, plaintext: &[u8], key_base64: &str, algorithm: Option<String>) -> Result<Vec<u8>, JsValue> {
+        let algorithm = CipherAlgorithm::parse(algorithm.as_deref())?;
+        let key = decode_key(key_base64)?;
+        
+        seal(algorithm, &key, plaintext)
+            .map_err(|e| JsValue::from_str(&format!("Encryption failed: {}", e)))
+    }
+
+    /// Decrypt binary data produced by `encrypt_bytes`, using the algorithm
+    /// recorded in its header
+    ///
+    /// Ciphertexts from before the header was introduced are read as
+    /// AES-256-GCM.
+    #[wasm_bindgen]
+    pub fn decrypt_bytes(&self, combined: &[u8], key_base64: &str) -> Result<Vec<u8>, JsValue> {
+        let key = decode_key(key_base64)?;
+        open(&key, combined).map_err(JsValue::from_str)
+    }
+
+    /// Decrypt a headerless AES-256-GCM ciphertext from before `encrypt_bytes`
+    /// wrote a header, without looking for one
+    #[wasm_bindgen]
+    pub fn decrypt_bytes_legacy(&self, combined: &[u8], key_base64: &str) -> Result<Vec<u8>, JsValue> {
+        let key = decode_key(key_base64)?;
+        open_legacy(&key, combined).map_err(JsValue::from_str)
+    }
+
     /// Generate SHA-256 hash
     #[wasm_bindgen]
     pub fn hash_sha256(&self, data: &str) -> String {
@@ -99,15 +245,163 @@ impl CryptoModule {
         general_purpose::STANDARD.encode(result)
     }
 
+    /// Hash binary data with SHA3-256, returning hex
+    #[wasm_bindgen]
+    pub fn hash_sha3_256(&self, data: &[u8]) -> String {
+        hex::encode(Sha3_256::digest(data))
+    }
+
+    /// Hash binary data with SHA3-512, returning hex
+    #[wasm_bindgen]
+    pub fn hash_sha3_512(&self, data: &[u8]) -> String {
+        hex::encode(Sha3_512::digest(data))
+    }
+
+    /// Hash binary data with Keccak-256, returning hex
+    ///
+    /// This is the original Keccak padding used by Ethereum, which gives
+    /// different digests from the standardized SHA3-256.
+    #[wasm_bindgen]
+    pub fn hash_keccak256(&self, data: &[u8]) -> String {
+        hex::encode(keccak256(data))
+    }
+
+    /// Hash a `Blob`, `File` or `ReadableStream` of bytes without loading
+    /// it into memory at once
+    ///
+    /// `algorithm` is `sha256` (default), `sha512`, `sha3-256`, `sha3-512`,
+    /// `keccak256` or `blake3`. `on_progress(processed, total)` is called
+    /// after each chunk; `total` is the blob size, or `undefined` for a
+    /// stream. Resolves to the hex digest.
+    #[wasm_bindgen]
+    pub async fn hash_blob(source: JsValue, algorithm: Option<String>, on_progress: Option<Function>) -> Result<String, JsValue> {
+        let mut hasher = StreamHasher::new(algorithm.as_deref().unwrap_or("sha256"))?;
+
+        // Blobs expose their size and a byte stream; anything else must
+        // already be a stream
+        let total = Reflect::get(&source, &"size".into())?;
+        let stream = match Reflect::get(&source, &"stream".into())?.dyn_into::<Function>() {
+            Ok(stream_fn) => stream_fn.call0(&source)?,
+            Err(_) => source,
+        };
+        let reader: JsValue = Reflect::get(&stream, &"getReader".into())?
+            .dyn_into::<Function>()
+            .map_err(|_| JsValue::from_str("hash_blob expects a Blob or ReadableStream"))?
+            .call0(&stream)?;
+        let read: Function = Reflect::get(&reader, &"read".into())?.dyn_into()?;
+
+        let mut chunk = Vec::new();
+        let mut processed = 0u64;
+        loop {
+            let promise: Promise = read.call0(&reader)?.dyn_into()?;
+            let result = JsFuture::from(promise).await?;
+            if Reflect::get(&result, &"done".into())?.is_truthy() {
+                break;
+            }
+
+            let value: Uint8Array = Reflect::get(&result, &"value".into())?
+                .dyn_into()
+                .map_err(|_| JsValue::from_str("hash_blob expects a stream of Uint8Array chunks"))?;
+            chunk.resize(value.length() as usize, 0);
+            value.copy_to(&mut chunk);
+            hasher.update(&chunk);
+            processed += chunk.len() as u64;
+
+            if let Some(on_progress) = &on_progress {
+                on_progress.call2(&JsValue::NULL, &(processed as f64).into(), &total)?;
+            }
+        }
+
+        Ok(hasher.finalize_hex())
+    }
+
+    /// Hash binary data with BLAKE3
+    ///
+    /// Returns the 32-byte digest in hex, as `b3sum` prints it, which works
+    /// directly as a content address in URLs and file names.
+    #[wasm_bindgen]
+    pub fn blake3_hash(&self, data: &[u8]) -> String {
+        blake3::hash(data).to_hex().to_string()
+    }
+
+    /// BLAKE3 keyed hash, a fast MAC, with a 32-byte base64 key
+    #[wasm_bindgen]
+    pub fn blake3_keyed_hash(&self, key_base64: &str, data: &[u8]) -> Result<String, JsValue> {
+        let key = decode_key(key_base64)?;
+        Ok(blake3::keyed_hash(&key, data).to_hex().to_string())
+    }
+
+    /// Derive a 32-byte key with BLAKE3's key derivation mode
+    ///
+    /// `context` should be a hardcoded, globally unique string such as
+    /// `"logos 2024-06 upload encryption"`, and `key_material` the secret
+    /// input. Returns the key in base64.
+    #[wasm_bindgen]
+    pub fn blake3_derive_key(&self, context: &str, key_material: &[u8]) -> String {
+        general_purpose::STANDARD.encode(blake3::derive_key(context, key_material))
+    }
+
+    /// Compute an HMAC-SHA256 tag, e.g. to sign an API request
+    ///
+    /// `key` is used as UTF-8 bytes, as webhook secrets usually are.
+    /// Returns the tag in base64.
+    #[wasm_bindgen]
+    pub fn hmac_sha256(&self, key: &str, data: &str) -> String {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any length");
+        mac.update(data.as_bytes());
+        general_purpose::STANDARD.encode(mac.finalize().into_bytes())
+    }
+
+    /// Compute an HMAC-SHA512 tag, returned in base64
+    #[wasm_bindgen]
+    pub fn hmac_sha512(&self, key: &str, data: &str) -> String {
+        let mut mac = <Hmac<Sha512> as Mac>::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any length");
+        mac.update(data.as_bytes());
+        general_purpose::STANDARD.encode(mac.finalize().into_bytes())
+    }
+
+    /// Check an HMAC tag in constant time, e.g. a webhook signature
+    ///
+    /// `mac` may be hex, as most webhook providers send it, or base64 as
+    /// returned by `hmac_sha256`. `algorithm` is `sha256` (default) or
+    /// `sha512`.
+    #[wasm_bindgen]
+    pub fn hmac_verify(&self, key: &str, data: &str, mac: &str, algorithm: Option<String>) -> Result<bool, JsValue> {
+        // Base64 tags of either size end in padding, so they are never all hex
+        let expected = if !mac.is_empty() && mac.chars().all(|c| c.is_ascii_hexdigit()) {
+            hex::decode(mac).map_err(|e| JsValue::from_str(&format!("Invalid MAC: {}", e)))?
+        } else {
+            general_purpose::STANDARD
+                .decode(mac)
+                .map_err(|e| JsValue::from_str(&format!("Invalid MAC: {}", e)))?
+        };
+
+        let valid = match algorithm.as_deref().unwrap_or("sha256").to_lowercase().as_str() {
+            "sha256" => {
+                let mut hmac = <Hmac<Sha256> as Mac>::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any length");
+                hmac.update(data.as_bytes());
+                hmac.verify_slice(&expected).is_ok()
+            }
+            "sha512" => {
+                let mut hmac = <Hmac<Sha512> as Mac>::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any length");
+                hmac.update(data.as_bytes());
+                hmac.verify_slice(&expected).is_ok()
+            }
+            other => return Err(JsValue::from_str(&format!("Unknown HMAC algorithm: {} (expected sha256 or sha512)", other))),
+        };
+
+        Ok(valid)
+    }
+
     /// Generate Ed25519 keypair
     #[wasm_bindgen]
     pub fn generate_keypair() -> Result<JsValue, JsValue> {
         let mut csprng = RandOsRng {};
-        let keypair = Keypair::generate(&mut csprng);
+        let keypair = SigningKey::generate(&mut csprng);
         
         let result = serde_json::json!({
-            "publicKey": general_purpose::STANDARD.encode(keypair.public.as_bytes()),
-            "secretKey": general_purpose::STANDARD.encode(keypair.secret.as_bytes())
+            "publicKey": general_purpose::STANDARD.encode(keypair.verifying_key().as_bytes()),
+            "secretKey": general_purpose::STANDARD.encode(keypair.as_bytes())
         });
         
         JsValue::from_serde(&result)
@@ -121,15 +415,9 @@ impl CryptoModule {
             .decode(secret_key_base64)
             .map_err(|e| JsValue::from_str(&format!("Invalid secret key: {}", e)))?;
         
-        let secret_key = SecretKey::from_bytes(&secret_bytes)
+        let keypair = SigningKey::try_from(secret_bytes.as_slice())
             .map_err(|e| JsValue::from_str(&format!("Invalid secret key format: {}", e)))?;
         
-        let public_key = PublicKey::from(&secret_key);
-        let keypair = Keypair {
-            secret: secret_key,
-            public: public_key,
-        };
-        
         let signature = keypair.sign(message.as_bytes());
         Ok(general_purpose::STANDARD.encode(signature.to_bytes()))
     }
@@ -145,15 +433,72 @@ impl CryptoModule {
             .decode(public_key_base64)
             .map_err(|e| JsValue::from_str(&format!("Invalid public key: {}", e)))?;
         
-        let signature = Signature::from_bytes(&signature_bytes)
+        let signature = Signature::from_slice(&signature_bytes)
             .map_err(|e| JsValue::from_str(&format!("Invalid signature format: {}", e)))?;
         
-        let public_key = PublicKey::from_bytes(&public_bytes)
+        let public_key = VerifyingKey::try_from(public_bytes.as_slice())
             .map_err(|e| JsValue::from_str(&format!("Invalid public key format: {}", e)))?;
         
         Ok(public_key.verify(message.as_bytes(), &signature).is_ok())
     }
 
+    /// Generate an X25519 keypair for key agreement
+    ///
+    /// Returns `{ publicKey, secretKey }` in base64, like `generate_keypair`.
+    /// Publish the public key; the secret key never leaves the device.
+    #[wasm_bindgen]
+    pub fn generate_x25519_keypair() -> Result<JsValue, JsValue> {
+        let secret = StaticSecret::from(rand::random::<[u8; 32]>());
+        let public = X25519PublicKey::from(&secret);
+        
+        let result = serde_json::json!({
+            "publicKey": general_purpose::STANDARD.encode(public.as_bytes()),
+            "secretKey": general_purpose::STANDARD.encode(secret.to_bytes())
+        });
+        
+        JsValue::from_serde(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Agree on a 32-byte symmetric key with another X25519 key holder
+    ///
+    /// The raw Diffie-Hellman output is passed through HKDF-SHA256 with
+    /// both public keys as salt, so both sides derive the same key and it
+    /// is bound to this pair. `info` (default `"logos-x25519"`) separates
+    /// keys for different purposes. The result works with
+    /// `encrypt_bytes`.
+    #[wasm_bindgen]
+    pub fn derive_shared_secret(&self, my_secret_base64: &str, their_public_base64: &str, info: Option<String>) -> Result<String, JsValue> {
+        let secret = StaticSecret::from(
+            decode_key(my_secret_base64).map_err(|_| JsValue::from_str("Invalid secret key: expected 32 bytes of base64"))?,
+        );
+        let their_public = X25519PublicKey::from(
+            decode_key(their_public_base64).map_err(|_| JsValue::from_str("Invalid public key: expected 32 bytes of base64"))?,
+        );
+        let my_public = X25519PublicKey::from(&secret);
+        
+        let shared = secret.diffie_hellman(&their_public);
+        // Low-order public keys force an all-zero output any attacker knows
+        if shared.as_bytes().iter().all(|&b| b == 0) {
+            return Err(JsValue::from_str("Invalid public key: low-order point"));
+        }
+        
+        // Sort the public keys so the salt is the same on both sides
+        let (first, second) = if my_public.as_bytes() <= their_public.as_bytes() {
+            (my_public.as_bytes(), their_public.as_bytes())
+        } else {
+            (their_public.as_bytes(), my_public.as_bytes())
+        };
+        let salt = [first.as_slice(), second.as_slice()].concat();
+        
+        let mut key = [0u8; 32];
+        Hkdf::<Sha256>::new(Some(&salt), shared.as_bytes())
+            .expand(info.as_deref().unwrap_or("logos-x25519").as_bytes(), &mut key)
+            .expect("32 bytes is a valid HKDF-SHA256 output length");
+        
+        Ok(general_purpose::STANDARD.encode(key))
+    }
+
     /// Generate random bytes
     #[wasm_bindgen]
     pub fn random_bytes(&self, length: usize) -> String {
@@ -177,10 +522,277 @@ impl CryptoModule {
         
         general_purpose::STANDARD.encode(key)
     }
+
+    /// Expand a master secret into a subkey using HKDF-SHA256 (RFC 5869)
+    ///
+    /// `ikm_base64` is the input key material, e.g. a key from
+    /// `generate_aes_key`, and `salt_base64` an optional base64 salt (empty
+    /// for none). Different `info` labels such as `"encryption"` and
+    /// `"signing"` give independent keys from the same secret. `length` is
+    /// at most 8160 bytes. Returns the subkey in base64.
+    #[wasm_bindgen]
+    pub fn hkdf_sha256(&self, ikm_base64: &str, salt_base64: &str, info: &str, length: usize) -> Result<String, JsValue> {
+        let ikm = general_purpose::STANDARD
+            .decode(ikm_base64)
+            .map_err(|e| JsValue::from_str(&format!("Invalid input key material: {}", e)))?;
+        
+        let salt = general_purpose::STANDARD
+            .decode(salt_base64)
+            .map_err(|e| JsValue::from_str(&format!("Invalid salt: {}", e)))?;
+        
+        let mut okm = vec![0u8; length];
+        Hkdf::<Sha256>::new(Some(&salt), &ikm)
+            .expand(info.as_bytes(), &mut okm)
+            .map_err(|_| JsValue::from_str("Invalid HKDF length: must be at most 8160 bytes"))?;
+        
+        Ok(general_purpose::STANDARD.encode(okm))
+    }
+
+    /// Derive a 32-byte key from a password using Argon2id (RFC 9106)
+    ///
+    /// `mem_kib` is the memory cost in KiB (at most 1 GiB), `iterations`
+    /// the time cost and `parallelism` the lane count; WASM runs lanes on
+    /// one thread, but the value still has to match wherever the key is
+    /// derived again. The salt must be at least 8 bytes.
+    #[wasm_bindgen]
+    pub fn derive_key_argon2id(&self, password: &str, salt: &str, mem_kib: u32, iterations: u32, parallelism: u32) -> Result<String, JsValue> {
+        let key = argon2id(password.as_bytes(), salt.as_bytes(), mem_kib, iterations, parallelism)?;
+        Ok(general_purpose::STANDARD.encode(key))
+    }
+
+    /// Hash a password for storage as a PHC string
+    ///
+    /// Uses Argon2id with a random salt, giving e.g.
+    /// `$argon2id$v=19$m=19456,t=2,p=1$<salt>$<hash>`. Parameters default
+    /// to the OWASP baseline of 19 MiB, 2 iterations and 1 lane; pass the
+    /// output of `recommend_argon2id_params` for a stronger setting. The
+    /// string embeds everything `verify_password` needs.
+    #[wasm_bindgen]
+    pub fn hash_password(&self, password: &str, mem_kib: Option<u32>, iterations: Option<u32>, parallelism: Option<u32>) -> Result<String, JsValue> {
+        use argon2::password_hash::{PasswordHasher, SaltString};
+
+        let argon2 = argon2_hasher(
+            mem_kib.unwrap_or(ARGON2_MIN_MEMORY_KIB),
+            iterations.unwrap_or(2),
+            parallelism.unwrap_or(1),
+        )?;
+        let salt = SaltString::generate(&mut OsRng);
+
+        argon2
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| JsValue::from_str(&format!("Password hashing failed: {}", e)))
+    }
+
+    /// Check a password against a PHC string from `hash_password`
+    ///
+    /// Returns `false` for a wrong password and an error for a malformed
+    /// or non-Argon2id hash. Verification takes as long as hashing did.
+    #[wasm_bindgen]
+    pub fn verify_password(&self, password: &str, phc_hash: &str) -> Result<bool, JsValue> {
+        use argon2::password_hash::{PasswordHash, PasswordVerifier};
+
+        let hash = PasswordHash::new(phc_hash)
+            .map_err(|e| JsValue::from_str(&format!("Invalid password hash: {}", e)))?;
+        if hash.algorithm.as_str() != "argon2id" {
+            return Err(JsValue::from_str(&format!("Unsupported password hash algorithm: {}", hash.algorithm)));
+        }
+
+        // Check the embedded cost before running it, so a tampered hash
+        // cannot exhaust memory
+        let params = argon2::Params::try_from(&hash)
+            .map_err(|e| JsValue::from_str(&format!("Invalid password hash: {}", e)))?;
+        let argon2 = argon2_hasher(params.m_cost(), params.t_cost(), params.p_cost())?;
+
+        Ok(argon2.verify_password(password.as_bytes(), &hash).is_ok())
+    }
+
+    /// Benchmark Argon2id on this device and suggest parameters
+    ///
+    /// Aims for about `target_ms` (default 500) per derivation with a
+    /// single lane: memory grows from the 19 MiB OWASP minimum up to
+    /// 256 MiB at 2 iterations, then iterations grow. Returns
+    /// `{ memKib, iterations, parallelism, estimatedMs }`. Run it once
+    /// when choosing parameters and store them with the salt, since a
+    /// slower device must be able to derive the same key.
+    #[wasm_bindgen]
+    pub fn recommend_argon2id_params(&self, target_ms: Option<f64>) -> Result<JsValue, JsValue> {
+        let target_ms = target_ms.unwrap_or(500.0).max(1.0);
+
+        // One pass over the minimum memory gives the cost of a KiB-iteration
+        let start = js_sys::Date::now();
+        argon2id(b"benchmark", b"benchmark-salt", ARGON2_MIN_MEMORY_KIB, 1, 1)?;
+        let probe_ms = (js_sys::Date::now() - start).max(1.0);
+        let ms_per_unit = probe_ms / ARGON2_MIN_MEMORY_KIB as f64;
+        let budget = target_ms / ms_per_unit;
+
+        let mem_kib = ((budget / 2.0) as u32).max(ARGON2_MIN_MEMORY_KIB).min(ARGON2_RECOMMENDED_MAX_MEMORY_KIB);
+        let iterations = ((budget / mem_kib as f64) as u32).max(2);
+
+        let result = serde_json::json!({
+            "memKib": mem_kib,
+            "iterations": iterations,
+            "parallelism": 1,
+            "estimatedMs": (mem_kib as f64 * iterations as f64 * ms_per_unit).round()
+        });
+
+        JsValue::from_serde(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+}
+
+fn argon2id(password: &[u8], salt: &[u8], mem_kib: u32, iterations: u32, parallelism: u32) -> Result<[u8; 32], JsValue> {
+    let mut key = [0u8; 32];
+    argon2_hasher(mem_kib, iterations, parallelism)?
+        .hash_password_into(password, salt, &mut key)
+        .map_err(|e| JsValue::from_str(&format!("Key derivation failed: {}", e)))?;
+    Ok(key)
+}
+
+/// Argon2id v1.3 with 32-byte output, refusing memory costs above the cap
+fn argon2_hasher(mem_kib: u32, iterations: u32, parallelism: u32) -> Result<argon2::Argon2<'static>, JsValue> {
+    use argon2::{Algorithm, Argon2, Params, Version};
+
+    if mem_kib > ARGON2_MAX_MEMORY_KIB {
+        return Err(JsValue::from_str(&format!(
+            "Argon2id memory cost {} KiB exceeds the maximum of {} KiB",
+            mem_kib, ARGON2_MAX_MEMORY_KIB
+        )));
+    }
+
+    let params = Params::new(mem_kib, iterations, parallelism, Some(32))
+        .map_err(|e| JsValue::from_str(&format!("Invalid Argon2id parameters: {}", e)))?;
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
+/// Incremental state for the algorithms `hash_blob` accepts
+enum StreamHasher {
+    Sha256(Sha256),
+    Sha512(Sha512),
+    Sha3_256(Sha3_256),
+    Sha3_512(Sha3_512),
+    Keccak256(Keccak256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl StreamHasher {
+    fn new(algorithm: &str) -> Result<Self, JsValue> {
+        Ok(match algorithm.to_lowercase().as_str() {
+            "sha256" => StreamHasher::Sha256(Sha256::new()),
+            "sha512" => StreamHasher::Sha512(Sha512::new()),
+            "sha3-256" => StreamHasher::Sha3_256(Sha3_256::new()),
+            "sha3-512" => StreamHasher::Sha3_512(Sha3_512::new()),
+            "keccak256" => StreamHasher::Keccak256(Keccak256::new()),
+            "blake3" => StreamHasher::Blake3(Box::new(blake3::Hasher::new())),
+            other => return Err(JsValue::from_str(&format!("Unknown hash algorithm: {}", other))),
+        })
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            StreamHasher::Sha256(h) => h.update(data),
+            StreamHasher::Sha512(h) => h.update(data),
+            StreamHasher::Sha3_256(h) => h.update(data),
+            StreamHasher::Sha3_512(h) => h.update(data),
+            StreamHasher::Keccak256(h) => h.update(data),
+            StreamHasher::Blake3(h) => {
+                h.update(data);
+            }
+        }
+    }
+
+    fn finalize_hex(self) -> String {
+        match self {
+            StreamHasher::Sha256(h) => hex::encode(h.finalize()),
+            StreamHasher::Sha512(h) => hex::encode(h.finalize()),
+            StreamHasher::Sha3_256(h) => hex::encode(h.finalize()),
+            StreamHasher::Sha3_512(h) => hex::encode(h.finalize()),
+            StreamHasher::Keccak256(h) => hex::encode(h.finalize()),
+            StreamHasher::Blake3(h) => h.finalize().to_hex().to_string(),
+        }
+    }
 }
 
-// Export initialization function
-#[wasm_bindgen(start)]
-pub fn main() {
-    console::log_1(&"WASM Crypto Module loaded successfully".into());
-}
\ No newline at end of file
+/// Keccak-256 digest, as used for Ethereum addresses and message hashes
+pub(crate) fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+/// Decode a base64 symmetric key, which must be 32 bytes
+pub(crate) fn decode_key(key_base64: &str) -> Result<[u8; 32], JsValue> {
+    let key_bytes = general_purpose::STANDARD
+        .decode(key_base64)
+        .map_err(|e| JsValue::from_str(&format!("Invalid key: {}", e)))?;
+    
+    key_bytes
+        .try_into()
+        .map_err(|_| JsValue::from_str("Invalid key length: expected 32 bytes"))
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 32] = [7; 32];
+
+    #[test]
+    fn headered_round_trip() {
+        for algorithm in [CipherAlgorithm::Aes256Gcm, CipherAlgorithm::ChaCha20Poly1305] {
+            let sealed = seal(algorithm, &KEY, b"hello").unwrap();
+
+            assert_eq!(sealed[..HEADER_LEN], [b'L', b'C', HEADER_VERSION, algorithm.id()]);
+            assert_eq!(open(&KEY, &sealed).unwrap(), b"hello");
+        }
+    }
+
+    #[test]
+    fn header_is_authenticated() {
+        let mut sealed = seal(CipherAlgorithm::Aes256Gcm, &KEY, b"hello").unwrap();
+        // Relabelling the algorithm must not reach the other cipher
+        sealed[3] = CipherAlgorithm::ChaCha20Poly1305.id();
+        assert!(open(&KEY, &sealed).is_err());
+
+        let mut sealed = seal(CipherAlgorithm::ChaCha20Poly1305, &KEY, b"hello").unwrap();
+        sealed[NONCE_LEN + HEADER_LEN] ^= 1;
+        assert!(open(&KEY, &sealed).is_err());
+    }
+
+    #[test]
+    fn failed_header_does_not_fall_back_to_legacy() {
+        let mut sealed = seal(CipherAlgorithm::Aes256Gcm, &KEY, b"hello").unwrap();
+        sealed[3] = 9;
+        assert_eq!(open(&KEY, &sealed), Err("Unknown cipher algorithm in ciphertext header"));
+
+        let sealed = seal(CipherAlgorithm::Aes256Gcm, &KEY, b"hello").unwrap();
+        let error = open(&[8; 32], &sealed).unwrap_err();
+        assert!(error.contains("header was changed"));
+    }
+
+    #[test]
+    fn legacy_layout_has_no_header() {
+        let sealed = seal_legacy(&KEY, b"hello").unwrap();
+
+        assert_eq!(sealed.len(), NONCE_LEN + 5 + 16);
+        assert_eq!(open_legacy(&KEY, &sealed).unwrap(), b"hello");
+        if sealed[..3] != [b'L', b'C', HEADER_VERSION] {
+            assert_eq!(open(&KEY, &sealed).unwrap(), b"hello");
+        }
+    }
+
+    #[test]
+    fn string_aes_wraps_bytes() {
+        let crypto = CryptoModule {};
+        let key = general_purpose::STANDARD.encode(KEY);
+
+        let ciphertext = crypto.encrypt_aes("hello", &key).ok().unwrap();
+        let combined = general_purpose::STANDARD.decode(&ciphertext).unwrap();
+        assert_eq!(combined[..HEADER_LEN], [b'L', b'C', HEADER_VERSION, CipherAlgorithm::Aes256Gcm.id()]);
+        assert_eq!(crypto.decrypt_aes(&ciphertext, &key).ok().unwrap(), "hello");
+
+        // Strings encrypted before the header existed still decrypt
+        let legacy = seal_legacy(&KEY, b"hello").unwrap();
+        if legacy[..3] != [b'L', b'C', HEADER_VERSION] {
+            let legacy = general_purpose::STANDARD.encode(legacy);
+            assert_eq!(crypto.decrypt_aes(&legacy, &key).ok().unwrap(), "hello");
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use wasm_bindgen::prelude::*;
+use hkdf::Hkdf;
+use sha2::Sha256;
+use crate::crypto::{CipherAlgorithm, HEADER_MAGIC, decode_key};
+
+/// Header version of chunked streams; one-shot ciphertexts use 1
+const STREAM_VERSION: u8 = 2;
+const SALT_LEN: usize = 16;
+/// Magic, version, algorithm id and salt
+const STREAM_HEADER_LEN: usize = 4 + SALT_LEN;
+
+/// Plaintext bytes per chunk; every chunk but the last is exactly this long
+const CHUNK_SIZE: usize = 64 * 1024;
+const TAG_LEN: usize = 16;
+
+const KEY_INFO: &[u8] = b"logos-stream-v1";
+
+/// Encrypts a file of any size in 64 KiB chunks
+///
+/// Feed the plaintext in pieces of any size with `push` and call `finish`
+/// once at the end; concatenating all returned bytes gives the ciphertext.
+/// Each chunk is sealed with its own nonce and tag, and the last one is
+/// marked as such, so reordered, truncated or extended streams fail to
+/// decrypt. Only one chunk is ever buffered.
+#[wasm_bindgen]
+pub struct EncryptStream {
+    algorithm: CipherAlgorithm,
+    key: [u8; 32],
+    counter: u64,
+    header: Option<Vec<u8>>,
+    buffer: Vec<u8>,
+    finished: bool,
+}
+
+#[wasm_bindgen]
+impl EncryptStream {
+    /// `key_base64` is a 32-byte key from `generate_aes_key`; `algorithm`
+    /// is `aes-256-gcm` (default) or `chacha20-poly1305`
+    #[wasm_bindgen(constructor)]
+    pub fn new(key_base64: &str, algorithm: Option<String>) -> Result<EncryptStream, JsValue> {
+        let algorithm = CipherAlgorithm::parse(algorithm.as_deref())?;
+        let key = decode_key(key_base64)?;
+        let salt = rand::random::<[u8; SALT_LEN]>();
+
+        let mut header = Vec::with_capacity(STREAM_HEADER_LEN);
+        header.extend_from_slice(&HEADER_MAGIC);
+        header.push(STREAM_VERSION);
+        header.push(algorithm.id());
+        header.extend_from_slice(&salt);
+
+        Ok(EncryptStream {
+            algorithm,
+            key: stream_key(&key, &salt),
+            counter: 0,
+            header: Some(header),
+            buffer: Vec::with_capacity(CHUNK_SIZE),
+            finished: false,
+        })
+    }
+
+    /// Encrypt the next piece of plaintext, returning whatever ciphertext
+    /// is ready (possibly none)
+    #[wasm_bindgen]
+    pub fn push(&mut self, data: &[u8]) -> Result<Vec<u8>, JsValue> {
+        if self.finished {
+            return Err(JsValue::from_str("Stream already finished"));
+        }
+
+        let mut output = self.header.take().unwrap_or_default();
+        self.buffer.extend_from_slice(data);
+        // Hold back a full chunk until more data shows it is not the last
+        while self.buffer.len() > CHUNK_SIZE {
+            let chunk: Vec<u8> = self.buffer.drain(..CHUNK_SIZE).collect();
+            output.extend(self.seal(&chunk, false)?);
+        }
+        Ok(output)
+    }
+
+    /// Encrypt the remaining plaintext as the final chunk
+    #[wasm_bindgen]
+    pub fn finish(&mut self) -> Result<Vec<u8>, JsValue> {
+        if self.finished {
+            return Err(JsValue::from_str("Stream already finished"));
+        }
+        self.finished = true;
+
+        let mut output = self.header.take().unwrap_or_default();
+        let chunk = std::mem::take(&mut self.buffer);
+        output.extend(self.seal(&chunk, true)?);
+        Ok(output)
+    }
+
+    fn seal(&mut self, chunk: &[u8], last: bool) -> Result<Vec<u8>, JsValue> {
+        let nonce = chunk_nonce(self.counter, last);
+        self.counter += 1;
+        self.algorithm
+            .encrypt(&self.key, &nonce, chunk, &[])
+            .map_err(|e| JsValue::from_str(&format!("Encryption failed: {}", e)))
+    }
+}
+
+/// Decrypts a stream produced by `EncryptStream`
+///
+/// Feed the ciphertext in pieces of any size with `push`, then call
+/// `finish`. Plaintext is only returned once its chunk has been
+/// authenticated, but the stream as a whole is only complete and
+/// untruncated if `finish` succeeds.
+#[wasm_bindgen]
+pub struct DecryptStream {
+    key: [u8; 32],
+    state: Option<(CipherAlgorithm, [u8; 32])>,
+    counter: u64,
+    buffer: Vec<u8>,
+    finished: bool,
+}
+
+#[wasm_bindgen]
+impl DecryptStream {
+    #[wasm_bindgen(constructor)]
+    pub fn new(key_base64: &str) -> Result<DecryptStream, JsValue> {
+        Ok(DecryptStream {
+            key: decode_key(key_base64)?,
+            state: None,
+            counter: 0,
+            buffer: Vec::with_capacity(CHUNK_SIZE + TAG_LEN),
+            finished: false,
+        })
+    }
+
+    /// Decrypt the next piece of ciphertext, returning whatever plaintext
+    /// is ready (possibly none)
+    #[wasm_bindgen]
+    pub fn push(&mut self, data: &[u8]) -> Result<Vec<u8>, JsValue> {
+        self.feed(data).map_err(JsValue::from_str)
+    }
+
+    /// Decrypt the final chunk, failing if the stream was cut short
+    #[wasm_bindgen]
+    pub fn finish(&mut self) -> Result<Vec<u8>, JsValue> {
+        self.close().map_err(JsValue::from_str)
+    }
+}
+
+impl DecryptStream {
+    fn feed(&mut self, data: &[u8]) -> Result<Vec<u8>, &'static str> {
+        if self.finished {
+            return Err("Stream already finished");
+        }
+
+        self.buffer.extend_from_slice(data);
+        if self.state.is_none() {
+            if self.buffer.len() < STREAM_HEADER_LEN {
+                return Ok(Vec::new());
+            }
+            self.read_header()?;
+        }
+
+        let mut output = Vec::new();
+        while self.buffer.len() > CHUNK_SIZE + TAG_LEN {
+            let chunk: Vec<u8> = self.buffer.drain(..CHUNK_SIZE + TAG_LEN).collect();
+            output.extend(self.open(&chunk, false)?);
+        }
+        Ok(output)
+    }
+
+    fn close(&mut self) -> Result<Vec<u8>, &'static str> {
+        if self.finished {
+            return Err("Stream already finished");
+        }
+        self.finished = true;
+
+        if self.state.is_none() {
+            if self.buffer.len() < STREAM_HEADER_LEN {
+                return Err("Invalid stream: missing header");
+            }
+            self.read_header()?;
+        }
+
+        let chunk = std::mem::take(&mut self.buffer);
+        self.open(&chunk, true)
+    }
+
+    fn read_header(&mut self) -> Result<(), &'static str> {
+        let header: Vec<u8> = self.buffer.drain(..STREAM_HEADER_LEN).collect();
+        if header[..2] != HEADER_MAGIC || header[2] != STREAM_VERSION {
+            self.finished = true;
+            return Err("Invalid stream header");
+        }
+        let algorithm = CipherAlgorithm::from_id(header[3]).ok_or_else(|| {
+            self.finished = true;
+            "Unknown cipher algorithm in stream header"
+        })?;
+
+        self.state = Some((algorithm, stream_key(&self.key, &header[4..])));
+        Ok(())
+    }
+
+    fn open(&mut self, chunk: &[u8], last: bool) -> Result<Vec<u8>, &'static str> {
+        let (algorithm, key) = self.state.ok_or("Invalid stream: missing header")?;
+        let nonce = chunk_nonce(self.counter, last);
+        self.counter += 1;
+
+        algorithm.decrypt(&key, &nonce, chunk, &[]).map_err(|_| {
+            // A failed chunk leaves the stream unusable
+            self.finished = true;
+            if last {
+                "Decryption failed: stream is truncated, corrupted or used the wrong key"
+            } else {
+                "Decryption failed: stream is corrupted or used the wrong key"
+            }
+        })
+    }
+}
+
+/// Per-stream key, so the chunk counter can start at zero every time
+/// without nonces repeating under the same file key
+fn stream_key(key: &[u8; 32], salt: &[u8]) -> [u8; 32] {
+    let mut derived = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(salt), key)
+        .expand(KEY_INFO, &mut derived)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    derived
+}
+
+/// Three zero bytes, the 64-bit big-endian chunk counter and the
+/// last-chunk flag, in the nonce layout age uses
+fn chunk_nonce(counter: u64, last: bool) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[3..11].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{Engine as _, engine::general_purpose};
+
+    const KEY: [u8; 32] = [7; 32];
+
+    fn encrypt(plaintext: &[u8], algorithm: &str) -> Vec<u8> {
+        let mut stream = EncryptStream::new(&general_purpose::STANDARD.encode(KEY), Some(algorithm.into())).ok().unwrap();
+        // Odd piece sizes so chunk boundaries fall inside pushes
+        let mut ciphertext = Vec::new();
+        for piece in plaintext.chunks(10_007) {
+            ciphertext.extend(stream.push(piece).ok().unwrap());
+        }
+        ciphertext.extend(stream.finish().ok().unwrap());
+        ciphertext
+    }
+
+    fn decrypt(ciphertext: &[u8], key: [u8; 32]) -> Result<Vec<u8>, &'static str> {
+        let mut stream = DecryptStream::new(&general_purpose::STANDARD.encode(key)).ok().unwrap();
+        let mut plaintext = Vec::new();
+        for piece in ciphertext.chunks(7_919) {
+            plaintext.extend(stream.feed(piece)?);
+        }
+        plaintext.extend(stream.close()?);
+        Ok(plaintext)
+    }
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 31 % 251) as u8).collect()
+    }
+
+    #[test]
+    fn round_trip_at_chunk_boundaries() {
+        for algorithm in ["aes-256-gcm", "chacha20-poly1305"] {
+            for len in [0, CHUNK_SIZE, CHUNK_SIZE + 1] {
+                let plaintext = sample(len);
+                let ciphertext = encrypt(&plaintext, algorithm);
+
+                let chunks = len.div_ceil(CHUNK_SIZE).max(1);
+                assert_eq!(ciphertext.len(), STREAM_HEADER_LEN + len + chunks * TAG_LEN);
+                assert_eq!(decrypt(&ciphertext, KEY).unwrap(), plaintext);
+            }
+        }
+    }
+
+    #[test]
+    fn dropped_last_chunk_fails_at_finish() {
+        let ciphertext = encrypt(&sample(CHUNK_SIZE + 1), "aes-256-gcm");
+        let truncated = &ciphertext[..STREAM_HEADER_LEN + CHUNK_SIZE + TAG_LEN];
+
+        let mut stream = DecryptStream::new(&general_purpose::STANDARD.encode(KEY)).ok().unwrap();
+        // The first chunk is held back until more data or `finish` arrives
+        assert_eq!(stream.feed(truncated).unwrap(), b"");
+        assert_eq!(
+            stream.close(),
+            Err("Decryption failed: stream is truncated, corrupted or used the wrong key")
+        );
+    }
+
+    #[test]
+    fn reordered_chunks_fail() {
+        let ciphertext = encrypt(&sample(3 * CHUNK_SIZE), "chacha20-poly1305");
+        let chunk = CHUNK_SIZE + TAG_LEN;
+        let (first, second) = (STREAM_HEADER_LEN, STREAM_HEADER_LEN + chunk);
+
+        let mut swapped = ciphertext.clone();
+        swapped[first..first + chunk].copy_from_slice(&ciphertext[second..second + chunk]);
+        swapped[second..second + chunk].copy_from_slice(&ciphertext[first..first + chunk]);
+        assert_eq!(decrypt(&swapped, KEY), Err("Decryption failed: stream is corrupted or used the wrong key"));
+    }
+
+    #[test]
+    fn tampered_header_fails() {
+        let ciphertext = encrypt(&sample(100), "aes-256-gcm");
+
+        let mut relabelled = ciphertext.clone();
+        relabelled[3] = CipherAlgorithm::ChaCha20Poly1305.id();
+        assert!(decrypt(&relabelled, KEY).is_err());
+
+        let mut version = ciphertext.clone();
+        version[2] = 1;
+        assert_eq!(decrypt(&version, KEY), Err("Invalid stream header"));
+
+        // The salt feeds the key derivation, so flipping it breaks every chunk
+        let mut salted = ciphertext;
+        salted[4] ^= 1;
+        assert!(decrypt(&salted, KEY).is_err());
+    }
+
+    #[test]
+    fn wrong_key_fails() {
+        let ciphertext = encrypt(&sample(100), "aes-256-gcm");
+        assert!(decrypt(&ciphertext, [8; 32]).is_err());
+    }
+}
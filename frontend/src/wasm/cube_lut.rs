@@ -0,0 +1,155 @@
+use wasm_bindgen::prelude::*;
+use image::DynamicImage;
+use crate::image_processor::{ImageProcessor, decode, encode};
+
+/// Largest 3D LUT edge accepted (256^3 entries is already 200 MB of floats)
+const MAX_CUBE_SIZE: usize = 128;
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Color-grade an image with an Adobe/Resolve `.cube` LUT (1D or 3D)
+    #[wasm_bindgen]
+    pub fn apply_lut(&self, image_data: &[u8], cube_file_bytes: &[u8]) -> Result<Vec<u8>, JsValue> {
+        let cube = std::str::from_utf8(cube_file_bytes)
+            .map_err(|e| JsValue::from_str(&format!("Invalid .cube file: {}", e)))?;
+        let lut = CubeLut::parse(cube)?;
+
+        let mut rgba = decode(image_data)?.to_rgba8();
+        for px in rgba.pixels_mut() {
+            let rgb = lut.lookup([
+                px[0] as f32 / 255.0,
+                px[1] as f32 / 255.0,
+                px[2] as f32 / 255.0,
+            ]);
+            for c in 0..3 {
+                px[c] = (rgb[c] * 255.0).round().max(0.0).min(255.0) as u8;
+            }
+        }
+
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+}
+
+struct CubeLut {
+    size: usize,
+    three_d: bool,
+    domain_min: [f32; 3],
+    domain_max: [f32; 3],
+    /// RGB entries with red varying fastest
+    table: Vec<[f32; 3]>,
+}
+
+impl CubeLut {
+    fn parse(source: &str) -> Result<Self, JsValue> {
+        let invalid = |msg: &str| JsValue::from_str(&format!("Invalid .cube file: {}", msg));
+        let parse_triplet = |parts: &[&str]| -> Result<[f32; 3], JsValue> {
+            if parts.len() != 3 {
+                return Err(invalid("expected three values"));
+            }
+            let mut out = [0.0; 3];
+            for (i, part) in parts.iter().enumerate() {
+                out[i] = part.parse().map_err(|_| invalid("malformed number"))?;
+            }
+            Ok(out)
+        };
+
+        let mut size = None;
+        let mut three_d = true;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut table = Vec::new();
+
+        for line in source.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            match parts[0] {
+                "TITLE" => {}
+                "LUT_3D_SIZE" | "LUT_1D_SIZE" => {
+                    three_d = parts[0] == "LUT_3D_SIZE";
+                    let n: usize = parts
+                        .get(1)
+                        .and_then(|v| v.parse().ok())
+                        .ok_or_else(|| invalid("malformed size"))?;
+                    let max = if three_d { MAX_CUBE_SIZE } else { 65536 };
+                    if n < 2 || n > max {
+                        return Err(invalid("size out of range"));
+                    }
+                    size = Some(n);
+                }
+                "DOMAIN_MIN" => domain_min = parse_triplet(&parts[1..])?,
+                "DOMAIN_MAX" => domain_max = parse_triplet(&parts[1..])?,
+                keyword if keyword.chars().next().is_some_and(|c| c.is_ascii_alphabetic()) => {
+                    // Ignore vendor-specific keywords
+                }
+                _ => table.push(parse_triplet(&parts)?),
+            }
+        }
+
+        let size = size.ok_or_else(|| invalid("missing LUT size"))?;
+        let expected = if three_d { size * size * size } else { size };
+        if table.len() != expected {
+            return Err(invalid(&format!("expected {} entries, found {}", expected, table.len())));
+        }
+
+        Ok(CubeLut { size, three_d, domain_min, domain_max, table })
+    }
+
+    fn lookup(&self, rgb: [f32; 3]) -> [f32; 3] {
+        // Position of each channel on the LUT grid
+        let mut pos = [0.0f32; 3];
+        for c in 0..3 {
+            let range = (self.domain_max[c] - self.domain_min[c]).max(f32::EPSILON);
+            let t = ((rgb[c] - self.domain_min[c]) / range).max(0.0).min(1.0);
+            pos[c] = t * (self.size - 1) as f32;
+        }
+
+        if self.three_d {
+            self.trilinear(pos)
+        } else {
+            let mut out = [0.0; 3];
+            for c in 0..3 {
+                let i0 = pos[c].floor() as usize;
+                let i1 = (i0 + 1).min(self.size - 1);
+                let f = pos[c] - i0 as f32;
+                out[c] = self.table[i0][c] * (1.0 - f) + self.table[i1][c] * f;
+            }
+            out
+        }
+    }
+
+    fn trilinear(&self, pos: [f32; 3]) -> [f32; 3] {
+        let n = self.size;
+        let index = |r: usize, g: usize, b: usize| r + g * n + b * n * n;
+
+        let r0 = pos[0].floor() as usize;
+        let g0 = pos[1].floor() as usize;
+        let b0 = pos[2].floor() as usize;
+        let r1 = (r0 + 1).min(n - 1);
+        let g1 = (g0 + 1).min(n - 1);
+        let b1 = (b0 + 1).min(n - 1);
+        let fr = pos[0] - r0 as f32;
+        let fg = pos[1] - g0 as f32;
+        let fb = pos[2] - b0 as f32;
+
+        let mut out = [0.0; 3];
+        for c in 0..3 {
+            let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+            let at = |r, g, b| self.table[index(r, g, b)][c];
+
+            let c00 = lerp(at(r0, g0, b0), at(r1, g0, b0), fr);
+            let c10 = lerp(at(r0, g1, b0), at(r1, g1, b0), fr);
+            let c01 = lerp(at(r0, g0, b1), at(r1, g0, b1), fr);
+            let c11 = lerp(at(r0, g1, b1), at(r1, g1, b1), fr);
+
+            let c0 = lerp(c00, c10, fg);
+            let c1 = lerp(c01, c11, fg);
+            out[c] = lerp(c0, c1, fb);
+        }
+
+        out
+    }
+}
@@ -0,0 +1,310 @@
+use wasm_bindgen::prelude::*;
+use js_sys::Array;
+use image::{DynamicImage, GrayImage, ImageBuffer, Luma, Rgba, RgbaImage, imageops};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use crate::adjustments::luma;
+use crate::image_processor::{ImageProcessor, decode, encode, parse_color};
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Binarize to black and white: pixels brighter than `value` become white
+    #[wasm_bindgen]
+    pub fn threshold(&self, image_data: &[u8], value: u8) -> Result<Vec<u8>, JsValue> {
+        let gray = decode(image_data)?.to_luma8();
+        encode(&DynamicImage::ImageLuma8(binarize(gray, value)), image::ImageOutputFormat::Png)
+    }
+
+    /// Binarize using the threshold chosen by Otsu's method
+    #[wasm_bindgen]
+    pub fn threshold_otsu(&self, image_data: &[u8]) -> Result<Vec<u8>, JsValue> {
+        let gray = decode(image_data)?.to_luma8();
+        let level = otsu_level(&gray);
+        encode(&DynamicImage::ImageLuma8(binarize(gray, level)), image::ImageOutputFormat::Png)
+    }
+
+    /// Compute the Otsu threshold without applying it
+    #[wasm_bindgen]
+    pub fn otsu_threshold(&self, image_data: &[u8]) -> Result<u8, JsValue> {
+        Ok(otsu_level(&decode(image_data)?.to_luma8()))
+    }
+
+    /// Mosaic a rectangular region, e.g. to redact faces or account numbers
+    #[wasm_bindgen]
+    pub fn pixelate(&self, image_data: &[u8], x: u32, y: u32, width: u32, height: u32, block_size: u32) -> Result<Vec<u8>, JsValue> {
+        let mut rgba = decode(image_data)?.to_rgba8();
+        pixelate_region(&mut rgba, x, y, width, height, block_size);
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+
+    /// Mosaic the whole image
+    #[wasm_bindgen]
+    pub fn pixelate_all(&self, image_data: &[u8], block_size: u32) -> Result<Vec<u8>, JsValue> {
+        let mut rgba = decode(image_data)?.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        pixelate_region(&mut rgba, 0, 0, width, height, block_size);
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+
+    /// Add Gaussian noise or film grain
+    ///
+    /// `amount` is the noise standard deviation as a fraction of the full
+    /// range (0.02 hides banding, 0.15 is heavy grain). `monochrome` adds the
+    /// same noise to all channels. A `grain_size` in pixels switches to film
+    /// grain: clumped noise that is strongest in the midtones. Passing a
+    /// `seed` makes the pattern repeatable, e.g. across preview renders.
+    #[wasm_bindgen]
+    pub fn add_noise(&self, image_data: &[u8], amount: f32, monochrome: bool, grain_size: Option<f32>, seed: Option<u32>) -> Result<Vec<u8>, JsValue> {
+        let mut rgba = decode(image_data)?.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        let sigma = amount.max(0.0) * 255.0;
+        let grain_size = grain_size.unwrap_or(0.0).max(0.0);
+
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed as u64),
+            None => StdRng::from_entropy(),
+        };
+        let fields: Vec<Vec<f32>> = (0..if monochrome { 1 } else { 3 })
+            .map(|_| noise_field(&mut rng, width, height, grain_size))
+            .collect();
+
+        for (i, px) in rgba.pixels_mut().enumerate() {
+            let weight = if grain_size > 0.0 {
+                // Film grain shows most in the midtones and fades out in
+                // clipped shadows and highlights
+                let l = luma(px[0], px[1], px[2]) as f32 / 255.0;
+                0.25 + 3.0 * l * (1.0 - l)
+            } else {
+                1.0
+            };
+
+            for (c, channel) in px.0.iter_mut().take(3).enumerate() {
+                let noise = fields[c % fields.len()][i] * sigma * weight;
+                *channel = (*channel as f32 + noise).round().max(0.0).min(255.0) as u8;
+            }
+        }
+
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+
+    /// Map luminance onto a two-color gradient, shadows to `shadow_color`
+    /// and highlights to `highlight_color` (hex); alpha is kept
+    #[wasm_bindgen]
+    pub fn duotone(&self, image_data: &[u8], shadow_color: &str, highlight_color: &str) -> Result<Vec<u8>, JsValue> {
+        let stops = [parse_color(shadow_color)?, parse_color(highlight_color)?];
+        map_gradient(image_data, &stops)
+    }
+
+    /// Map luminance onto evenly spaced color stops, darkest first
+    ///
+    /// Generalizes `duotone` to tritones and beyond, e.g. navy, magenta and
+    /// yellow. Stops are interpolated in sRGB like design tools do.
+    #[wasm_bindgen]
+    pub fn gradient_map(&self, image_data: &[u8], colors: &Array) -> Result<Vec<u8>, JsValue> {
+        if colors.length() < 2 {
+            return Err(JsValue::from_str("Gradient map needs at least 2 colors"));
+        }
+        let stops = colors
+            .iter()
+            .map(|value| value.as_string().ok_or_else(|| JsValue::from_str("Colors must be strings")).and_then(|c| parse_color(&c)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        map_gradient(image_data, &stops)
+    }
+}
+
+/// Recolor by luminance through evenly spaced stops (at least 2)
+fn map_gradient(image_data: &[u8], stops: &[Rgba<u8>]) -> Result<Vec<u8>, JsValue> {
+    let table = gradient_table(stops);
+    let mut rgba = decode(image_data)?.to_rgba8();
+    for px in rgba.pixels_mut() {
+        let [r, g, b] = table[luma(px[0], px[1], px[2]) as usize];
+        px[0] = r;
+        px[1] = g;
+        px[2] = b;
+    }
+
+    encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+}
+
+/// Color for each luminance level, interpolating between adjacent stops
+fn gradient_table(stops: &[Rgba<u8>]) -> Vec<[u8; 3]> {
+    let segments = (stops.len() - 1) as f32;
+    (0..256)
+        .map(|level| {
+            let position = level as f32 / 255.0 * segments;
+            let index = (position as usize).min(stops.len() - 2);
+            let t = position - index as f32;
+            let (a, b) = (stops[index], stops[index + 1]);
+            [0, 1, 2].map(|c| (a[c] as f32 + (b[c] as f32 - a[c] as f32) * t).round() as u8)
+        })
+        .collect()
+}
+
+/// Unit-variance Gaussian noise, optionally blurred into grain clumps of
+/// about `grain_size` pixels and renormalized
+fn noise_field(rng: &mut StdRng, width: u32, height: u32, grain_size: f32) -> Vec<f32> {
+    // Box-Muller; `1 - u` keeps the logarithm finite
+    let mut gaussian = || {
+        let u1: f32 = 1.0 - rng.gen::<f32>();
+        let u2: f32 = rng.gen();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos()
+    };
+    let noise: Vec<f32> = (0..width as usize * height as usize).map(|_| gaussian()).collect();
+    if grain_size <= 0.0 {
+        return noise;
+    }
+
+    let field = ImageBuffer::<Luma<f32>, Vec<f32>>::from_raw(width, height, noise).unwrap_or_else(|| ImageBuffer::new(width, height));
+    let blurred = imageops::blur(&field, grain_size / 2.0).into_raw();
+
+    let variance = blurred.iter().map(|v| v * v).sum::<f32>() / blurred.len().max(1) as f32;
+    let scale = if variance > 0.0 { 1.0 / variance.sqrt() } else { 0.0 };
+    blurred.into_iter().map(|v| v * scale).collect()
+}
+
+/// Replace each block inside the region with its average color.
+/// The region is clipped to the image bounds.
+pub(crate) fn pixelate_region(rgba: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, block_size: u32) {
+    let block_size = block_size.max(1);
+    let x_end = x.saturating_add(width).min(rgba.width());
+    let y_end = y.saturating_add(height).min(rgba.height());
+
+    let mut by = y;
+    while by < y_end {
+        let block_height = block_size.min(y_end - by);
+        let mut bx = x;
+        while bx < x_end {
+            let block_width = block_size.min(x_end - bx);
+
+            let mut sums = [0u64; 4];
+            for py in by..by + block_height {
+                for px in bx..bx + block_width {
+                    let pixel = rgba.get_pixel(px, py);
+                    for c in 0..4 {
+                        sums[c] += pixel[c] as u64;
+                    }
+                }
+            }
+
+            let count = (block_width * block_height) as u64;
+            let average = Rgba([
+                (sums[0] / count) as u8,
+                (sums[1] / count) as u8,
+                (sums[2] / count) as u8,
+                (sums[3] / count) as u8,
+            ]);
+            for py in by..by + block_height {
+                for px in bx..bx + block_width {
+                    rgba.put_pixel(px, py, average);
+                }
+            }
+
+            bx += block_width;
+        }
+        by += block_height;
+    }
+}
+
+fn binarize(mut gray: GrayImage, level: u8) -> GrayImage {
+    for px in gray.pixels_mut() {
+        px[0] = if px[0] > level { 255 } else { 0 };
+    }
+    gray
+}
+
+/// Pick the level that maximizes between-class variance of the histogram
+pub(crate) fn otsu_level(gray: &GrayImage) -> u8 {
+    let mut histogram = [0u64; 256];
+    for px in gray.pixels() {
+        histogram[px[0] as usize] += 1;
+    }
+
+    let total: u64 = histogram.iter().sum();
+    let sum_all: f64 = histogram.iter().enumerate().map(|(i, &n)| i as f64 * n as f64).sum();
+
+    let mut weight_background = 0u64;
+    let mut sum_background = 0f64;
+    let mut best_level = 0u8;
+    let mut best_variance = 0f64;
+
+    for (level, &count) in histogram.iter().enumerate() {
+        weight_background += count;
+        if weight_background == 0 {
+            continue;
+        }
+        let weight_foreground = total - weight_background;
+        if weight_foreground == 0 {
+            break;
+        }
+
+        sum_background += level as f64 * count as f64;
+        let mean_background = sum_background / weight_background as f64;
+        let mean_foreground = (sum_all - sum_background) / weight_foreground as f64;
+
+        let variance = weight_background as f64
+            * weight_foreground as f64
+            * (mean_background - mean_foreground).powi(2);
+        if variance > best_variance {
+            best_variance = variance;
+            best_level = level as u8;
+        }
+    }
+
+    best_level
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn otsu_splits_a_bimodal_histogram() {
+        // Dark and bright clusters spread over 30-50 and 190-210
+        let gray = GrayImage::from_fn(21, 2, |x, y| Luma([if y == 0 { 30 + x as u8 } else { 190 + x as u8 }]));
+        let level = otsu_level(&gray);
+        assert!((50..190).contains(&level));
+
+        let binary = binarize(gray, level);
+        assert!(binary.rows().next().unwrap().all(|px| px[0] == 0));
+        assert!(binary.rows().nth(1).unwrap().all(|px| px[0] == 255));
+    }
+
+    #[test]
+    fn pixelate_averages_blocks_inside_the_region() {
+        let mut rgba = RgbaImage::from_fn(4, 4, |x, _| Rgba([if x % 2 == 0 { 0 } else { 200 }, 0, 0, 255]));
+        // The region runs past the right edge and is clipped
+        pixelate_region(&mut rgba, 2, 0, 10, 2, 2);
+
+        assert_eq!(rgba.get_pixel(2, 0), &Rgba([100, 0, 0, 255]));
+        assert_eq!(rgba.get_pixel(3, 1), &Rgba([100, 0, 0, 255]));
+        assert_eq!(rgba.get_pixel(1, 0), &Rgba([200, 0, 0, 255]));
+        assert_eq!(rgba.get_pixel(2, 2), &Rgba([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn noise_is_seeded_and_unit_variance() {
+        let variance = |field: &[f32]| field.iter().map(|v| v * v).sum::<f32>() / field.len() as f32;
+
+        let a = noise_field(&mut StdRng::seed_from_u64(7), 64, 64, 0.0);
+        let b = noise_field(&mut StdRng::seed_from_u64(7), 64, 64, 0.0);
+        assert_eq!(a, b);
+        assert!((variance(&a) - 1.0).abs() < 0.1);
+
+        // Blurring into grain is renormalized to the same strength
+        let grain = noise_field(&mut StdRng::seed_from_u64(7), 64, 64, 4.0);
+        assert!((variance(&grain) - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn gradient_table_hits_every_stop() {
+        let duotone = gradient_table(&[Rgba([0, 0, 128, 255]), Rgba([255, 200, 0, 255])]);
+        assert_eq!(duotone.len(), 256);
+        assert_eq!((duotone[0], duotone[255]), ([0, 0, 128], [255, 200, 0]));
+
+        // A middle stop lands halfway up the luminance range
+        let tritone = gradient_table(&[Rgba([0, 0, 0, 255]), Rgba([255, 0, 255, 255]), Rgba([255, 255, 0, 255])]);
+        assert_eq!(tritone[0], [0, 0, 0]);
+        assert_eq!(tritone[128], [255, 1, 254]);
+        assert_eq!(tritone[255], [255, 255, 0]);
+    }
+}
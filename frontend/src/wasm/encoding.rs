@@ -0,0 +1,213 @@
+use wasm_bindgen::prelude::*;
+use serde::Deserialize;
+use image::DynamicImage;
+use crate::icc::srgb_profile;
+use crate::image_processor::{encode, output_format};
+use crate::limits::check_output_size;
+use crate::quantize::{Palette, map_nearest};
+
+/// Encoder settings accepted as a plain JS object by `convert_format`,
+/// `compress` and `LoadedImage.encode_with_options`
+///
+/// Fields that don't apply to the chosen format are ignored.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct EncodeOptions {
+    /// Lossy quality (1-100)
+    pub quality: Option<u8>,
+    /// JPEG: emit a progressive rather than baseline scan
+    pub progressive: bool,
+    /// JPEG: chroma subsampling, one of `4:4:4`, `4:2:2` or `4:2:0`
+    pub subsampling: Option<String>,
+    /// PNG: deflate effort, one of `fast`, `default` or `best`
+    pub compression: Option<String>,
+    /// PNG: row filter, one of `none`, `sub`, `up`, `average`, `paeth` or `adaptive`
+    pub filter: Option<String>,
+    /// PNG: quantize to an 8-bit palette
+    pub palette: bool,
+    /// PNG: palette size when `palette` is set (2-256)
+    pub max_colors: Option<u16>,
+    /// WebP: encode losslessly instead of using `quality`; builds without
+    /// the `libwebp` feature, including the browser one, always do
+    pub lossless: bool,
+    /// JPEG: embed an sRGB ICC profile; PNG: write an `sRGB` chunk
+    pub embed_srgb: bool,
+}
+
+impl EncodeOptions {
+    pub(crate) fn with_quality(quality: u8) -> Self {
+        EncodeOptions { quality: Some(quality), ..Default::default() }
+    }
+}
+
+/// Encode with default options at the given lossy quality
+pub(crate) fn encode_as(img: &DynamicImage, format: &str, quality: u8) -> Result<Vec<u8>, JsValue> {
+    encode_with_options(img, format, &EncodeOptions::with_quality(quality))
+}
+
+/// Encode using format-specific options where the format supports them
+pub(crate) fn encode_with_options(img: &DynamicImage, format: &str, options: &EncodeOptions) -> Result<Vec<u8>, JsValue> {
+    let quality = options.quality.unwrap_or(85).max(1).min(100);
+
+    let output = match format.to_lowercase().as_str() {
+        "jpeg" | "jpg" => encode_jpeg(img, quality, options)?,
+        "png" => encode_png(img, options)?,
+        "webp" => encode_webp(img, quality, options.lossless)?,
+        _ => return encode(img, output_format(format, quality)?),
+    };
+
+    check_output_size(output.len())?;
+    Ok(output)
+}
+
+fn encode_jpeg(img: &DynamicImage, quality: u8, options: &EncodeOptions) -> Result<Vec<u8>, JsValue> {
+    use jpeg_encoder::{ColorType, Encoder, SamplingFactor};
+
+    let sampling = match options.subsampling.as_deref().unwrap_or("4:2:0") {
+        "4:4:4" => SamplingFactor::R_4_4_4,
+        "4:2:2" => SamplingFactor::R_4_2_2,
+        "4:2:0" => SamplingFactor::R_4_2_0,
+        other => return Err(JsValue::from_str(&format!("Unsupported chroma subsampling: {}", other))),
+    };
+
+    let rgb = img.to_rgb8();
+    let (width, height) = rgb.dimensions();
+    if width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(JsValue::from_str("JPEG dimensions are limited to 65535 pixels"));
+    }
+
+    let mut output = Vec::new();
+    let mut encoder = Encoder::new(&mut output, quality);
+    encoder.set_progressive(options.progressive);
+    encoder.set_sampling_factor(sampling);
+    if options.embed_srgb {
+        encoder
+            .add_icc_profile(&srgb_profile())
+            .map_err(|e| JsValue::from_str(&format!("Failed to encode JPEG: {}", e)))?;
+    }
+    encoder
+        .encode(rgb.as_raw(), width as u16, height as u16, ColorType::Rgb)
+        .map_err(|e| JsValue::from_str(&format!("Failed to encode JPEG: {}", e)))?;
+
+    Ok(output)
+}
+
+/// Encode WebP through libwebp, which unlike the built-in encoder supports lossy output
+#[cfg(feature = "libwebp")]
+fn encode_webp(img: &DynamicImage, quality: u8, lossless: bool) -> Result<Vec<u8>, JsValue> {
+    let rgba = img.to_rgba8();
+    let encoder = webp::Encoder::from_rgba(rgba.as_raw(), rgba.width(), rgba.height());
+
+    let encoded = if lossless {
+        encoder.encode_lossless()
+    } else {
+        encoder.encode(quality as f32)
+    };
+
+    Ok(encoded.to_vec())
+}
+
+/// Encode lossless WebP with the pure-Rust encoder, ignoring `quality`;
+/// libwebp can't be linked into the wasm32 build
+#[cfg(not(feature = "libwebp"))]
+fn encode_webp(img: &DynamicImage, _quality: u8, _lossless: bool) -> Result<Vec<u8>, JsValue> {
+    let rgba = img.to_rgba8();
+    let mut output = Vec::new();
+    image::codecs::webp::WebPEncoder::new_lossless(&mut output)
+        .encode(rgba.as_raw(), rgba.width(), rgba.height(), image::ColorType::Rgba8)
+        .map_err(|e| JsValue::from_str(&format!("Failed to encode WebP: {}", e)))?;
+
+    Ok(output)
+}
+
+fn encode_png(img: &DynamicImage, options: &EncodeOptions) -> Result<Vec<u8>, JsValue> {
+    let compression = match options.compression.as_deref().unwrap_or("default") {
+        "fast" => png::Compression::Fast,
+        "default" => png::Compression::Default,
+        "best" => png::Compression::Best,
+        other => return Err(JsValue::from_str(&format!("Unsupported PNG compression: {}", other))),
+    };
+
+    let (filter, adaptive) = match options.filter.as_deref().unwrap_or("adaptive") {
+        "none" => (png::FilterType::NoFilter, false),
+        "sub" => (png::FilterType::Sub, false),
+        "up" => (png::FilterType::Up, false),
+        "average" => (png::FilterType::Avg, false),
+        "paeth" => (png::FilterType::Paeth, false),
+        "adaptive" => (png::FilterType::Sub, true),
+        other => return Err(JsValue::from_str(&format!("Unsupported PNG filter: {}", other))),
+    };
+
+    let to_js = |e: png::EncodingError| JsValue::from_str(&format!("Failed to encode PNG: {}", e));
+
+    let (width, height) = (img.width(), img.height());
+    let mut output = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut output, width, height);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_compression(compression);
+        encoder.set_filter(filter);
+        if adaptive {
+            encoder.set_adaptive_filter(png::AdaptiveFilterType::Adaptive);
+        }
+        if options.embed_srgb {
+            encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+        }
+
+        let data = if options.palette {
+            let max_colors = options.max_colors.unwrap_or(256).max(2).min(256) as usize;
+            let rgba = img.to_rgba8();
+            let palette = Palette::adaptive(&rgba, max_colors);
+            encoder.set_color(png::ColorType::Indexed);
+            encoder.set_palette(palette.rgb());
+            encoder.set_trns(palette.alpha());
+            map_nearest(&rgba, &palette)
+        } else if img.color().has_alpha() {
+            encoder.set_color(png::ColorType::Rgba);
+            img.to_rgba8().into_raw()
+        } else {
+            encoder.set_color(png::ColorType::Rgb);
+            img.to_rgb8().into_raw()
+        };
+
+        let mut writer = encoder.write_header().map_err(to_js)?;
+        writer.write_image_data(&data).map_err(to_js)?;
+    }
+
+    Ok(output)
+}
+
+/// Encode palette indices as an 8-bit indexed PNG
+pub(crate) fn encode_indexed_png(width: u32, height: u32, palette: &Palette, indices: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let to_js = |e: png::EncodingError| JsValue::from_str(&format!("Failed to encode PNG: {}", e));
+
+    let mut output = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut output, width, height);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(palette.rgb());
+        encoder.set_trns(palette.alpha());
+
+        let mut writer = encoder.write_header().map_err(to_js)?;
+        writer.write_image_data(indices).map_err(to_js)?;
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn lossless_webp_round_trip() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(16, 8, |x, y| image::Rgba([x as u8 * 16, y as u8 * 32, 90, 200])));
+        let options = EncodeOptions { lossless: true, ..Default::default() };
+
+        let encoded = encode_with_options(&img, "webp", &options).ok().unwrap();
+        let decoded = image::load_from_memory(&encoded).unwrap();
+        assert_eq!(decoded.to_rgba8(), img.to_rgba8());
+    }
+}
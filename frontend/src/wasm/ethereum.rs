@@ -0,0 +1,136 @@
+use wasm_bindgen::prelude::*;
+use base64::{Engine as _, engine::general_purpose};
+use k256::PublicKey;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use crate::crypto::{CryptoModule, keccak256};
+use crate::secp256k1_keys::{decode_secret_key, recover_public_key, sign_recoverable};
+
+#[wasm_bindgen]
+impl CryptoModule {
+    /// Ethereum address of a secp256k1 public key, with EIP-55 checksum
+    ///
+    /// Accepts the compressed or uncompressed key in base64, e.g. the
+    /// `publicKey` from `generate_secp256k1_keypair` or `derive_path`.
+    #[wasm_bindgen]
+    pub fn ethereum_address(&self, public_key_base64: &str) -> Result<String, JsValue> {
+        let public_bytes = general_purpose::STANDARD
+            .decode(public_key_base64)
+            .map_err(|e| JsValue::from_str(&format!("Invalid public key: {}", e)))?;
+
+        let public_key = PublicKey::from_sec1_bytes(&public_bytes)
+            .map_err(|e| JsValue::from_str(&format!("Invalid public key format: {}", e)))?;
+        Ok(address_of(&public_key))
+    }
+
+    /// Sign a message the way wallets do for `personal_sign` (EIP-191)
+    ///
+    /// The message is prefixed with `"\x19Ethereum Signed Message:\n"` and
+    /// its byte length before hashing, so it can never be a valid
+    /// transaction. Returns the 65-byte signature as `0x` hex with `v` of
+    /// 27 or 28, as Sign-In with Ethereum backends expect.
+    #[wasm_bindgen]
+    pub fn personal_sign(&self, message: &str, secret_key_base64: &str) -> Result<String, JsValue> {
+        let signing_key = decode_secret_key(secret_key_base64)?;
+        let mut signature = sign_recoverable(&signing_key, &personal_message_hash(message))?;
+        signature[64] += 27;
+        Ok(format!("0x{}", hex::encode(signature)))
+    }
+
+    /// Address that produced a `personal_sign` signature over `message`
+    #[wasm_bindgen]
+    pub fn recover_personal_sign(&self, message: &str, signature_hex: &str) -> Result<String, JsValue> {
+        let signature = hex::decode(signature_hex.trim_start_matches("0x"))
+            .map_err(|e| JsValue::from_str(&format!("Invalid signature: {}", e)))?;
+
+        let public_key = recover_public_key(&personal_message_hash(message), &signature)?;
+        Ok(address_of(&public_key.into()))
+    }
+
+    /// Check that `address` signed `message` with `personal_sign`
+    ///
+    /// The address comparison ignores case, so checksummed and lowercase
+    /// addresses both work. Malformed signatures count as invalid.
+    #[wasm_bindgen]
+    pub fn verify_personal_sign(&self, message: &str, signature_hex: &str, address: &str) -> bool {
+        self.recover_personal_sign(message, signature_hex)
+            .map(|recovered| recovered.eq_ignore_ascii_case(address.trim()))
+            .unwrap_or(false)
+    }
+}
+
+/// EIP-191 version 0x45 hash of a message
+fn personal_message_hash(message: &str) -> [u8; 32] {
+    let mut data = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+    data.extend_from_slice(message.as_bytes());
+    keccak256(&data)
+}
+
+/// Last 20 bytes of the Keccak-256 of the uncompressed point, checksummed
+fn address_of(public_key: &PublicKey) -> String {
+    let point = public_key.to_encoded_point(false);
+    let hash = keccak256(&point.as_bytes()[1..]);
+    checksum_address(&hex::encode(&hash[12..]))
+}
+
+/// EIP-55: capitalize the letters of a lowercase hex address where the
+/// hash of that address has a high nibble
+fn checksum_address(lower: &str) -> String {
+    let checksum = keccak256(lower.as_bytes());
+    let checksummed: String = lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (checksum[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+            if nibble >= 8 { c.to_ascii_uppercase() } else { c }
+        })
+        .collect();
+
+    format!("0x{}", checksummed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eip55_checksums() {
+        // Examples from EIP-55
+        for address in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            assert_eq!(checksum_address(&address[2..].to_lowercase()), address);
+        }
+    }
+
+    #[test]
+    fn address_of_secret_key_one() {
+        let mut secret = [0u8; 32];
+        secret[31] = 1;
+        let public_key = k256::SecretKey::from_slice(&secret).unwrap().public_key();
+
+        assert_eq!(address_of(&public_key), "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf");
+    }
+
+    #[test]
+    fn eip191_message_hash() {
+        assert_eq!(
+            hex::encode(personal_message_hash("hello world")),
+            "d9eba16ed0ecae432b71fe008c98cc872bb4cc214d3220a36f365326cf807d68"
+        );
+    }
+
+    #[test]
+    fn personal_sign_recovers_signer() {
+        let crypto = CryptoModule {};
+        let mut secret = [0u8; 32];
+        secret[31] = 1;
+
+        let signature = crypto.personal_sign("hello world", &general_purpose::STANDARD.encode(secret)).ok().unwrap();
+        assert!(matches!(&signature[signature.len() - 2..], "1b" | "1c"));
+        assert!(crypto.verify_personal_sign("hello world", &signature, "0x7e5f4552091a69125d5dfcb7b8c2659029395bdf"));
+        assert!(!crypto.verify_personal_sign("hello world!", &signature, "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf"));
+    }
+}
@@ -0,0 +1,284 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use serde::Deserialize;
+use image::{DynamicImage, imageops::FilterType};
+use crate::effects::pixelate_region;
+use crate::filters::blur_rect;
+use crate::image_processor::{ImageProcessor, decode, encode, parse_options};
+
+/// Longest side of the grayscale copy the cascade scans; larger images are
+/// downscaled first, which keeps detection fast without missing real faces
+const DETECT_MAX_SIDE: u32 = 1024;
+
+/// Overlap above which two raw detections count as the same face
+const CLUSTER_IOU: f32 = 0.2;
+
+/// Settings shared by `detect_faces` and `blur_faces`
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct FaceOptions {
+    /// Smallest face side in source pixels (default 4% of the shorter side)
+    min_size: Option<f32>,
+    /// Largest face side in source pixels (default the shorter side)
+    max_size: Option<f32>,
+    /// Minimum summed score of a clustered detection (default 10)
+    threshold: Option<f32>,
+    /// Margin added around each face as a fraction of its size (default 0.2)
+    padding: Option<f32>,
+    /// `blur` (default) or `pixelate`
+    style: Option<String>,
+}
+
+/// A face as a square box in source pixels
+struct Face {
+    x: f32,
+    y: f32,
+    size: f32,
+    score: f32,
+}
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Find frontal faces with a pico cascade
+    ///
+    /// `cascade` is a pico binary cascade such as the `facefinder` file from
+    /// the pico project, supplied by the caller so the module stays small.
+    /// `options` accepts `{ minSize, maxSize, threshold }`. Returns
+    /// `[{ x, y, width, height, score }]`, best matches first.
+    #[wasm_bindgen]
+    pub fn detect_faces(&self, image_data: &[u8], cascade: &[u8], options: JsValue) -> Result<JsValue, JsValue> {
+        let options: FaceOptions = parse_options(&options)?;
+        let cascade = Cascade::parse(cascade)?;
+        let faces = find_faces(&decode(image_data)?, &cascade, &options);
+
+        let result: Vec<_> = faces
+            .iter()
+            .map(|face| serde_json::json!({
+                "x": face.x.round(),
+                "y": face.y.round(),
+                "width": face.size.round(),
+                "height": face.size.round(),
+                "score": face.score
+            }))
+            .collect();
+
+        JsValue::from_serde(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Redact every detected face, e.g. before sharing event photos
+    ///
+    /// Takes the same `cascade` and options as `detect_faces`, plus
+    /// `padding` around each face and `style` (`blur` or `pixelate`). Blur
+    /// strength scales with each face so features are fully smeared out.
+    #[wasm_bindgen]
+    pub fn blur_faces(&self, image_data: &[u8], cascade: &[u8], options: JsValue) -> Result<Vec<u8>, JsValue> {
+        let options: FaceOptions = parse_options(&options)?;
+        let cascade = Cascade::parse(cascade)?;
+        let pixelate = match options.style.as_deref().unwrap_or("blur") {
+            "blur" => false,
+            "pixelate" => true,
+            _ => return Err(JsValue::from_str("Face redaction style must be blur or pixelate")),
+        };
+
+        let img = decode(image_data)?;
+        let faces = find_faces(&img, &cascade, &options);
+        let padding = options.padding.unwrap_or(0.2).max(0.0);
+
+        let mut rgba = img.to_rgba8();
+        for face in &faces {
+            let margin = face.size * padding;
+            let x = (face.x - margin).max(0.0) as u32;
+            let y = (face.y - margin).max(0.0) as u32;
+            let side = (face.size + 2.0 * margin).ceil() as u32;
+
+            if pixelate {
+                pixelate_region(&mut rgba, x, y, side, side, (side / 8).max(4));
+            } else {
+                blur_rect(&mut rgba, x, y, side, side, side as f32 / 6.0);
+            }
+        }
+
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+}
+
+/// A pico cascade of pixel-comparison decision trees
+struct Cascade {
+    depth: u32,
+    trees: Vec<Tree>,
+}
+
+struct Tree {
+    /// Row/column offset pairs to compare, 4 per internal node; the first
+    /// 4 bytes are padding so that node indices start at 1
+    codes: Vec<i8>,
+    /// Leaf outputs
+    predictions: Vec<f32>,
+    /// Running score below which the region is rejected
+    threshold: f32,
+}
+
+impl Cascade {
+    fn parse(data: &[u8]) -> Result<Cascade, JsValue> {
+        let invalid = || JsValue::from_str("Invalid face cascade");
+        let read_u32 = |pos: usize| data.get(pos..pos + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).ok_or_else(invalid);
+
+        // The first 8 bytes hold the training parameters
+        let depth = read_u32(8)?;
+        let tree_count = read_u32(12)? as usize;
+        if depth == 0 || depth > 16 {
+            return Err(invalid());
+        }
+
+        let leaves = 1usize << depth;
+        let mut pos = 16;
+        let mut trees = Vec::with_capacity(tree_count.min(4096));
+        for _ in 0..tree_count {
+            let mut codes = vec![0i8; 4];
+            let node_bytes = data.get(pos..pos + 4 * leaves - 4).ok_or_else(invalid)?;
+            codes.extend(node_bytes.iter().map(|&b| b as i8));
+            pos += 4 * leaves - 4;
+
+            let predictions = (0..leaves)
+                .map(|i| read_u32(pos + 4 * i).map(f32::from_bits))
+                .collect::<Result<Vec<_>, _>>()?;
+            pos += 4 * leaves;
+
+            let threshold = f32::from_bits(read_u32(pos)?);
+            pos += 4;
+
+            trees.push(Tree { codes, predictions, threshold });
+        }
+
+        if trees.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Cascade { depth, trees })
+    }
+
+    /// Score of the square of side `size` centered at (`row`, `col`), or
+    /// `None` if a stage rejects it. The region must lie inside the image.
+    fn classify(&self, gray: &[u8], width: usize, row: i32, col: i32, size: i32) -> Option<f32> {
+        let (row, col) = (row * 256, col * 256);
+        let pixel = |dr: i8, dc: i8| {
+            let r = ((row + dr as i32 * size) >> 8) as usize;
+            let c = ((col + dc as i32 * size) >> 8) as usize;
+            gray[r * width + c]
+        };
+
+        let mut score = 0.0;
+        for tree in &self.trees {
+            let mut node = 1;
+            for _ in 0..self.depth {
+                let code = &tree.codes[4 * node..4 * node + 4];
+                node = 2 * node + (pixel(code[0], code[1]) <= pixel(code[2], code[3])) as usize;
+            }
+            score += tree.predictions[node - (1 << self.depth)];
+            if score <= tree.threshold {
+                return None;
+            }
+        }
+
+        Some(score - self.trees[self.trees.len() - 1].threshold)
+    }
+}
+
+/// Scan a grayscale copy at every scale, then merge overlapping hits
+fn find_faces(img: &DynamicImage, cascade: &Cascade, options: &FaceOptions) -> Vec<Face> {
+    let (source_width, source_height) = (img.width(), img.height());
+    let ratio = (DETECT_MAX_SIDE as f32 / source_width.max(source_height) as f32).min(1.0);
+    let gray = if ratio < 1.0 {
+        img.resize(
+            ((source_width as f32 * ratio) as u32).max(1),
+            ((source_height as f32 * ratio) as u32).max(1),
+            FilterType::Triangle,
+        )
+        .to_luma8()
+    } else {
+        img.to_luma8()
+    };
+
+    let (width, height) = (gray.width() as i32, gray.height() as i32);
+    let shorter = width.min(height) as f32;
+    let min_size = options.min_size.map_or(shorter * 0.04, |size| size * ratio).max(20.0);
+    let max_size = options.max_size.map_or(shorter, |size| size * ratio).min(shorter);
+    let pixels = gray.as_raw();
+
+    let mut detections = Vec::new();
+    let mut size = min_size;
+    while size <= max_size {
+        let side = size as i32;
+        let step = ((size * 0.1) as i32).max(1);
+        let offset = side / 2 + 1;
+
+        let mut row = offset;
+        while row <= height - offset {
+            let mut col = offset;
+            while col <= width - offset {
+                if let Some(score) = cascade.classify(pixels, width as usize, row, col, side) {
+                    detections.push((row as f32, col as f32, size, score));
+                }
+                col += step;
+            }
+            row += step;
+        }
+        size *= 1.1;
+    }
+
+    let threshold = options.threshold.unwrap_or(10.0);
+    cluster(detections)
+        .into_iter()
+        .filter(|&(_, _, _, score)| score >= threshold)
+        .map(|(row, col, size, score)| Face {
+            x: (col - size / 2.0) / ratio,
+            y: (row - size / 2.0) / ratio,
+            size: size / ratio,
+            score,
+        })
+        .collect()
+}
+
+/// Merge overlapping `(row, col, size, score)` detections, averaging their
+/// boxes and summing their scores, strongest first
+fn cluster(mut detections: Vec<(f32, f32, f32, f32)>) -> Vec<(f32, f32, f32, f32)> {
+    detections.sort_by(|a, b| b.3.partial_cmp(&a.3).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut assigned = vec![false; detections.len()];
+    let mut clusters = Vec::new();
+    for (i, &anchor) in detections.iter().enumerate() {
+        if assigned[i] {
+            continue;
+        }
+
+        let members: Vec<_> = detections
+            .iter()
+            .zip(assigned.iter_mut())
+            .skip(i)
+            .filter(|(detection, taken)| !**taken && iou(anchor, **detection) > CLUSTER_IOU)
+            .map(|(detection, taken)| {
+                *taken = true;
+                *detection
+            })
+            .collect();
+
+        let count = members.len() as f32;
+        let (row, col, size, score) = members
+            .iter()
+            .fold((0.0, 0.0, 0.0, 0.0), |acc, d| (acc.0 + d.0, acc.1 + d.1, acc.2 + d.2, acc.3 + d.3));
+        clusters.push((row / count, col / count, size / count, score));
+    }
+
+    clusters.sort_by(|a, b| b.3.partial_cmp(&a.3).unwrap_or(std::cmp::Ordering::Equal));
+    clusters
+}
+
+/// Intersection over union of two centered square boxes
+fn iou(a: (f32, f32, f32, f32), b: (f32, f32, f32, f32)) -> f32 {
+    let overlap = |ca: f32, sa: f32, cb: f32, sb: f32| {
+        ((ca + sa / 2.0).min(cb + sb / 2.0) - (ca - sa / 2.0).max(cb - sb / 2.0)).max(0.0)
+    };
+    let rows = overlap(a.0, a.2, b.0, b.2);
+    let cols = overlap(a.1, a.2, b.1, b.2);
+    rows * cols / (a.2 * a.2 + b.2 * b.2 - rows * cols)
+}
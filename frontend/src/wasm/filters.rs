@@ -0,0 +1,539 @@
+use wasm_bindgen::prelude::*;
+use image::{DynamicImage, GrayImage, RgbaImage, imageops};
+use crate::image_processor::{ImageProcessor, decode, encode};
+use crate::parallel::for_each_row;
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Sharpen with an unsharp mask
+    ///
+    /// `amount` scales the added detail (1.0 = 100%), `radius` is the blur
+    /// sigma used to find detail, and differences at or below `threshold`
+    /// (0-255) are left alone so flat areas and noise aren't amplified.
+    #[wasm_bindgen]
+    pub fn sharpen(&self, image_data: &[u8], amount: f32, radius: f32, threshold: u8) -> Result<Vec<u8>, JsValue> {
+        let rgba = decode(image_data)?.to_rgba8();
+        let sharpened = unsharp_mask(&rgba, amount, radius, threshold);
+        encode(&DynamicImage::ImageRgba8(sharpened), image::ImageOutputFormat::Png)
+    }
+
+    /// Reduce noise with a median filter over a (2 * radius + 1)² window
+    #[wasm_bindgen]
+    pub fn denoise(&self, image_data: &[u8], radius: u32) -> Result<Vec<u8>, JsValue> {
+        if radius > MAX_FILTER_RADIUS {
+            return Err(JsValue::from_str(&format!("Radius must be at most {}", MAX_FILTER_RADIUS)));
+        }
+
+        let rgba = decode(image_data)?.to_rgba8();
+        encode(&DynamicImage::ImageRgba8(median_filter(&rgba, radius)), image::ImageOutputFormat::Png)
+    }
+
+    /// Edge-preserving smoothing with a bilateral filter
+    ///
+    /// `sigma_color` (0-255) controls how different neighbors may be before
+    /// they stop contributing; `sigma_space` is the spatial falloff in pixels.
+    #[wasm_bindgen]
+    pub fn denoise_bilateral(&self, image_data: &[u8], radius: u32, sigma_color: f32, sigma_space: f32) -> Result<Vec<u8>, JsValue> {
+        if radius > MAX_FILTER_RADIUS {
+            return Err(JsValue::from_str(&format!("Radius must be at most {}", MAX_FILTER_RADIUS)));
+        }
+
+        let rgba = decode(image_data)?.to_rgba8();
+        let filtered = bilateral_filter(&rgba, radius, sigma_color.max(0.1), sigma_space.max(0.1));
+        encode(&DynamicImage::ImageRgba8(filtered), image::ImageOutputFormat::Png)
+    }
+
+    /// Produce an edge map with the `sobel` or `canny` detector
+    ///
+    /// For Sobel, gradient magnitudes at or below `threshold` are zeroed. For
+    /// Canny, `threshold` is the strong-edge level and half of it the weak
+    /// level used for hysteresis; the result is a binary edge map.
+    #[wasm_bindgen]
+    pub fn detect_edges(&self, image_data: &[u8], method: &str, threshold: f32) -> Result<Vec<u8>, JsValue> {
+        let gray = decode(image_data)?.to_luma8();
+
+        let edges = match method.to_lowercase().as_str() {
+            "sobel" => sobel_edges(&gray, threshold),
+            "canny" => canny_edges(&gray, threshold / 2.0, threshold),
+            _ => return Err(JsValue::from_str("Edge detection method must be sobel or canny")),
+        };
+
+        encode(&DynamicImage::ImageLuma8(edges), image::ImageOutputFormat::Png)
+    }
+
+    /// Blur only a rectangular region (e.g. a license plate)
+    #[wasm_bindgen]
+    pub fn blur_region(&self, image_data: &[u8], x: u32, y: u32, width: u32, height: u32, sigma: f32) -> Result<Vec<u8>, JsValue> {
+        let mut rgba = decode(image_data)?.to_rgba8();
+        blur_rect(&mut rgba, x, y, width, height, sigma);
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+
+    /// Blur where a grayscale mask is white, blending smoothly through gray
+    ///
+    /// The mask is stretched to the image size if the dimensions differ.
+    #[wasm_bindgen]
+    pub fn blur_masked(&self, image_data: &[u8], mask_data: &[u8], sigma: f32) -> Result<Vec<u8>, JsValue> {
+        let rgba = decode(image_data)?.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        let mask = load_mask(mask_data, width, height)?;
+
+        let blurred = imageops::blur(&rgba, sigma.max(0.1));
+        let mut output = rgba;
+        for ((out, blur), m) in output.pixels_mut().zip(blurred.pixels()).zip(mask.pixels()) {
+            let t = m[0] as f32 / 255.0;
+            for c in 0..4 {
+                out[c] = (out[c] as f32 * (1.0 - t) + blur[c] as f32 * t).round() as u8;
+            }
+        }
+
+        encode(&DynamicImage::ImageRgba8(output), image::ImageOutputFormat::Png)
+    }
+}
+
+/// Decode a mask as grayscale, stretched to the target dimensions
+pub(crate) fn load_mask(mask_data: &[u8], width: u32, height: u32) -> Result<GrayImage, JsValue> {
+    let mask = decode(mask_data)?;
+    let mask = if (mask.width(), mask.height()) != (width, height) {
+        mask.resize_exact(width, height, imageops::FilterType::Triangle)
+    } else {
+        mask
+    };
+
+    Ok(mask.to_luma8())
+}
+
+/// Gaussian-blur a rectangle in place. The blur samples a margin around the
+/// rectangle so its edges don't darken, but only the rectangle is written.
+pub(crate) fn blur_rect(rgba: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, sigma: f32) {
+    let (image_width, image_height) = rgba.dimensions();
+    let x_end = x.saturating_add(width).min(image_width);
+    let y_end = y.saturating_add(height).min(image_height);
+    if x >= x_end || y >= y_end {
+        return;
+    }
+
+    let sigma = sigma.max(0.1);
+    let margin = (sigma * 3.0).ceil() as u32;
+    let (mx, my) = (x.saturating_sub(margin), y.saturating_sub(margin));
+    let (mx_end, my_end) = ((x_end + margin).min(image_width), (y_end + margin).min(image_height));
+
+    let patch = imageops::crop_imm(rgba, mx, my, mx_end - mx, my_end - my).to_image();
+    let blurred = imageops::blur(&patch, sigma);
+
+    for py in y..y_end {
+        for px in x..x_end {
+            rgba.put_pixel(px, py, *blurred.get_pixel(px - mx, py - my));
+        }
+    }
+}
+
+/// Horizontal and vertical Sobel gradients, with clamped borders
+pub(crate) fn sobel_gradients(gray: &GrayImage) -> (Vec<f32>, Vec<f32>) {
+    let (width, height) = gray.dimensions();
+    let (w, h) = (width as i64, height as i64);
+    let at = |x: i64, y: i64| gray.get_pixel(x.max(0).min(w - 1) as u32, y.max(0).min(h - 1) as u32)[0] as f32;
+
+    let mut gx = vec![0f32; (width * height) as usize];
+    let mut gy = vec![0f32; (width * height) as usize];
+
+    for y in 0..h {
+        for x in 0..w {
+            let i = (y * w + x) as usize;
+            gx[i] = (at(x + 1, y - 1) + 2.0 * at(x + 1, y) + at(x + 1, y + 1))
+                - (at(x - 1, y - 1) + 2.0 * at(x - 1, y) + at(x - 1, y + 1));
+            gy[i] = (at(x - 1, y + 1) + 2.0 * at(x, y + 1) + at(x + 1, y + 1))
+                - (at(x - 1, y - 1) + 2.0 * at(x, y - 1) + at(x + 1, y - 1));
+        }
+    }
+
+    (gx, gy)
+}
+
+fn sobel_edges(gray: &GrayImage, threshold: f32) -> GrayImage {
+    let (width, height) = gray.dimensions();
+    let (gx, gy) = sobel_gradients(gray);
+
+    let magnitudes = gx.iter().zip(&gy).map(|(x, y)| {
+        // The 3x3 Sobel kernel yields magnitudes up to ~1442; scale into 0-255
+        let magnitude = (x * x + y * y).sqrt() / 4.0;
+        if magnitude <= threshold { 0 } else { magnitude.min(255.0) as u8 }
+    });
+
+    GrayImage::from_raw(width, height, magnitudes.collect()).unwrap_or_else(|| GrayImage::new(width, height))
+}
+
+/// Canny: smoothing, gradients, non-maximum suppression and hysteresis
+fn canny_edges(gray: &GrayImage, low: f32, high: f32) -> GrayImage {
+    let (width, height) = gray.dimensions();
+    let (w, h) = (width as usize, height as usize);
+
+    let smoothed = imageops::blur(gray, 1.4);
+    let (gx, gy) = sobel_gradients(&smoothed);
+    let magnitude: Vec<f32> = gx.iter().zip(&gy).map(|(x, y)| (x * x + y * y).sqrt() / 4.0).collect();
+
+    // Keep only local maxima along the gradient direction
+    let mut thin = vec![0f32; w * h];
+    for y in 1..h.saturating_sub(1) {
+        for x in 1..w.saturating_sub(1) {
+            let i = y * w + x;
+            let angle = gy[i].atan2(gx[i]).to_degrees();
+            let angle = if angle < 0.0 { angle + 180.0 } else { angle };
+
+            let (a, b) = if !(22.5..157.5).contains(&angle) {
+                (i - 1, i + 1)
+            } else if angle < 67.5 {
+                (i - w + 1, i + w - 1)
+            } else if angle < 112.5 {
+                (i - w, i + w)
+            } else {
+                (i - w - 1, i + w + 1)
+            };
+
+            if magnitude[i] >= magnitude[a] && magnitude[i] >= magnitude[b] {
+                thin[i] = magnitude[i];
+            }
+        }
+    }
+
+    // Grow strong edges through connected weak edges
+    let mut edges = vec![0u8; w * h];
+    let mut stack: Vec<usize> = (0..w * h).filter(|&i| thin[i] >= high).collect();
+    for &i in &stack {
+        edges[i] = 255;
+    }
+
+    while let Some(i) = stack.pop() {
+        let (x, y) = ((i % w) as i64, (i / w) as i64);
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= w as i64 || ny >= h as i64 {
+                    continue;
+                }
+                let n = ny as usize * w + nx as usize;
+                if edges[n] == 0 && thin[n] >= low {
+                    edges[n] = 255;
+                    stack.push(n);
+                }
+            }
+        }
+    }
+
+    GrayImage::from_raw(width, height, edges).unwrap_or_else(|| GrayImage::new(width, height))
+}
+
+/// Largest neighborhood radius accepted by window-based filters
+const MAX_FILTER_RADIUS: u32 = 32;
+
+/// Median filter using Huang's sliding histogram, per channel
+pub(crate) fn median_filter(rgba: &RgbaImage, radius: u32) -> RgbaImage {
+    let (width, height) = rgba.dimensions();
+    let r = radius as i64;
+    let window = ((2 * r + 1) * (2 * r + 1)) as u32;
+    let mut output = rgba.clone();
+
+    let sample = |x: i64, y: i64, c: usize| -> usize {
+        let x = x.max(0).min(width as i64 - 1) as u32;
+        let y = y.max(0).min(height as i64 - 1) as u32;
+        rgba.get_pixel(x, y)[c] as usize
+    };
+
+    for y in 0..height as i64 {
+        let mut histograms = [[0u32; 256]; 4];
+        for dy in -r..=r {
+            for dx in -r..=r {
+                for (c, histogram) in histograms.iter_mut().enumerate() {
+                    histogram[sample(dx, y + dy, c)] += 1;
+                }
+            }
+        }
+
+        for x in 0..width as i64 {
+            if x > 0 {
+                // Slide the window one column to the right
+                for dy in -r..=r {
+                    for (c, histogram) in histograms.iter_mut().enumerate() {
+                        histogram[sample(x - r - 1, y + dy, c)] -= 1;
+                        histogram[sample(x + r, y + dy, c)] += 1;
+                    }
+                }
+            }
+
+            let out = output.get_pixel_mut(x as u32, y as u32);
+            for (c, histogram) in histograms.iter().enumerate() {
+                let mut count = 0;
+                for (level, &n) in histogram.iter().enumerate() {
+                    count += n;
+                    if count * 2 > window {
+                        out[c] = level as u8;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    output
+}
+
+fn bilateral_filter(rgba: &RgbaImage, radius: u32, sigma_color: f32, sigma_space: f32) -> RgbaImage {
+    let (width, height) = rgba.dimensions();
+    let r = radius as i64;
+    let mut output = rgba.clone();
+
+    // Weights are looked up rather than computed per neighbor
+    let max_distance = 442; // ceil(sqrt(3 * 255²))
+    let range_weights: Vec<f32> = (0..=max_distance)
+        .map(|d| (-((d * d) as f32) / (2.0 * sigma_color * sigma_color)).exp())
+        .collect();
+    let side = (2 * r + 1) as usize;
+    let mut spatial_weights = vec![0f32; side * side];
+    for dy in -r..=r {
+        for dx in -r..=r {
+            let index = ((dy + r) as usize) * side + (dx + r) as usize;
+            spatial_weights[index] = (-((dx * dx + dy * dy) as f32) / (2.0 * sigma_space * sigma_space)).exp();
+        }
+    }
+
+    for y in 0..height as i64 {
+        for x in 0..width as i64 {
+            let center = rgba.get_pixel(x as u32, y as u32);
+            let mut sums = [0f32; 3];
+            let mut total = 0f32;
+
+            for dy in -r..=r {
+                let ny = (y + dy).max(0).min(height as i64 - 1) as u32;
+                for dx in -r..=r {
+                    let nx = (x + dx).max(0).min(width as i64 - 1) as u32;
+                    let neighbor = rgba.get_pixel(nx, ny);
+
+                    let distance = (0..3)
+                        .map(|c| (neighbor[c] as f32 - center[c] as f32).powi(2))
+                        .sum::<f32>()
+                        .sqrt() as usize;
+                    let weight = spatial_weights[((dy + r) as usize) * side + (dx + r) as usize]
+                        * range_weights[distance.min(max_distance)];
+
+                    for c in 0..3 {
+                        sums[c] += neighbor[c] as f32 * weight;
+                    }
+                    total += weight;
+                }
+            }
+
+            let out = output.get_pixel_mut(x as u32, y as u32);
+            for c in 0..3 {
+                out[c] = (sums[c] / total).round().max(0.0).min(255.0) as u8;
+            }
+        }
+    }
+
+    output
+}
+
+pub(crate) fn unsharp_mask(rgba: &RgbaImage, amount: f32, radius: f32, threshold: u8) -> RgbaImage {
+    let blurred = imageops::blur(rgba, radius.max(0.1));
+    let mut output = rgba.clone();
+
+    for (out, (src, blur)) in output.pixels_mut().zip(rgba.pixels().zip(blurred.pixels())) {
+        for c in 0..3 {
+            let detail = src[c] as f32 - blur[c] as f32;
+            if detail.abs() > threshold as f32 {
+                out[c] = (src[c] as f32 + amount * detail).round().max(0.0).min(255.0) as u8;
+            }
+        }
+    }
+
+    output
+}
+
+/// Largest kernel side accepted by `convolve`
+pub(crate) const MAX_KERNEL_SIZE: usize = 31;
+
+/// How pixels outside the image are sampled by `convolve`
+#[derive(Clone, Copy)]
+pub(crate) enum EdgeMode {
+    /// Repeat the nearest edge pixel
+    Clamp,
+    /// Tile the image
+    Wrap,
+    /// Reflect about the edge, without repeating the edge pixel
+    Mirror,
+}
+
+impl EdgeMode {
+    pub(crate) fn parse(name: &str) -> Result<Self, JsValue> {
+        match name.to_lowercase().as_str() {
+            "clamp" | "extend" => Ok(EdgeMode::Clamp),
+            "wrap" | "tile" => Ok(EdgeMode::Wrap),
+            "mirror" | "reflect" => Ok(EdgeMode::Mirror),
+            _ => Err(JsValue::from_str("Edge mode must be clamp, wrap or mirror")),
+        }
+    }
+
+    /// Map a possibly out-of-range coordinate into `0..len`
+    fn index(self, i: i64, len: i64) -> i64 {
+        match self {
+            EdgeMode::Clamp => i.max(0).min(len - 1),
+            EdgeMode::Wrap => i.rem_euclid(len),
+            EdgeMode::Mirror => {
+                if len == 1 {
+                    return 0;
+                }
+                let period = 2 * (len - 1);
+                let i = i.rem_euclid(period);
+                if i < len { i } else { period - i }
+            }
+        }
+    }
+}
+
+/// Convolve the color channels with a square kernel of odd `size`, given
+/// row-major. Each sum is divided by `divisor` and shifted by `offset`
+/// (0-255 scale); alpha is left as is.
+pub(crate) fn convolve(rgba: &RgbaImage, kernel: &[f32], size: usize, divisor: f32, offset: f32, edge: EdgeMode) -> RgbaImage {
+    let (width, height) = rgba.dimensions();
+    let half = (size / 2) as i64;
+    let (w, h) = (width as i64, height as i64);
+
+    // Edge lookups per kernel tap, computed once per row and column
+    let columns: Vec<Vec<u32>> = (0..w)
+        .map(|x| (-half..=half).map(|d| edge.index(x + d, w) as u32).collect())
+        .collect();
+
+    let mut out = RgbaImage::new(width, height);
+    for_each_row(&mut out, width as usize * 4, |y, row| {
+        let rows: Vec<u32> = (-half..=half).map(|d| edge.index(y as i64 + d, h) as u32).collect();
+
+        for (x, taps) in columns.iter().enumerate() {
+            let mut sum = [0f32; 3];
+            for (ky, &sy) in rows.iter().enumerate() {
+                for (kx, &sx) in taps.iter().enumerate() {
+                    let weight = kernel[ky * size + kx];
+                    let px = rgba.get_pixel(sx, sy);
+                    for (total, &v) in sum.iter_mut().zip(px.0.iter()) {
+                        *total += v as f32 * weight;
+                    }
+                }
+            }
+
+            let channel = |v: f32| (v / divisor + offset).round().max(0.0).min(255.0) as u8;
+            row[x * 4] = channel(sum[0]);
+            row[x * 4 + 1] = channel(sum[1]);
+            row[x * 4 + 2] = channel(sum[2]);
+            row[x * 4 + 3] = rgba.get_pixel(x as u32, y as u32)[3];
+        }
+    });
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn step(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, _| if x < width / 2 { Rgba([50, 50, 50, 255]) } else { Rgba([200, 200, 200, 255]) })
+    }
+
+    #[test]
+    fn unsharp_mask_adds_overshoot_at_edges_only() {
+        let flat = RgbaImage::from_pixel(8, 8, Rgba([90, 120, 150, 255]));
+        assert_eq!(unsharp_mask(&flat, 1.5, 2.0, 0), flat);
+
+        let edge = step(16, 4);
+        let sharpened = unsharp_mask(&edge, 1.0, 1.0, 0);
+        assert!(sharpened.get_pixel(7, 1)[0] < 50 && sharpened.get_pixel(8, 1)[0] > 200);
+        assert_eq!(sharpened.get_pixel(0, 1)[0], 50);
+
+        // Differences at or below the threshold are left alone
+        assert_eq!(unsharp_mask(&edge, 1.0, 1.0, 255), edge);
+    }
+
+    #[test]
+    fn median_removes_salt_and_pepper() {
+        let mut rgba = RgbaImage::from_pixel(5, 5, Rgba([100, 100, 100, 255]));
+        rgba.put_pixel(2, 2, Rgba([255, 255, 255, 255]));
+        rgba.put_pixel(0, 4, Rgba([0, 0, 0, 0]));
+
+        let filtered = median_filter(&rgba, 1);
+        assert!(filtered.pixels().all(|px| px == &Rgba([100, 100, 100, 255])));
+
+        // Radius 0 is a no-op, and edges survive a median
+        assert_eq!(median_filter(&rgba, 0), rgba);
+        let edge = step(8, 8);
+        assert_eq!(median_filter(&edge, 2), edge);
+    }
+
+    #[test]
+    fn edges_follow_the_step() {
+        let flat = GrayImage::from_pixel(16, 16, image::Luma([120]));
+        assert!(sobel_edges(&flat, 0.0).pixels().all(|px| px[0] == 0));
+
+        let gray = DynamicImage::ImageRgba8(step(16, 16)).to_luma8();
+        let sobel = sobel_edges(&gray, 10.0);
+        assert_eq!(sobel.get_pixel(7, 8)[0], 150);
+        assert_eq!(sobel.get_pixel(3, 8)[0], 0);
+
+        // Canny thins the step to a binary line near the boundary
+        let canny = canny_edges(&gray, 20.0, 40.0);
+        for y in 2..14 {
+            let row: Vec<u32> = (0..16).filter(|&x| canny.get_pixel(x, y)[0] == 255).collect();
+            assert!(!row.is_empty() && row.iter().all(|x| (7..=8).contains(x)), "row {}: {:?}", y, row);
+        }
+        assert!(canny.pixels().all(|px| px[0] == 0 || px[0] == 255));
+    }
+
+    #[test]
+    fn blur_rect_writes_only_inside_the_rectangle() {
+        let mut rgba = step(16, 8);
+        let original = rgba.clone();
+        blur_rect(&mut rgba, 6, 2, 4, 4, 2.0);
+
+        for (x, y, px) in rgba.enumerate_pixels() {
+            if (6..10).contains(&x) && (2..6).contains(&y) {
+                assert!(px[0] > 50 && px[0] < 200);
+            } else {
+                assert_eq!(px, original.get_pixel(x, y));
+            }
+        }
+
+        // Empty or out-of-bounds rectangles do nothing
+        let blurred = rgba.clone();
+        blur_rect(&mut rgba, 20, 0, 4, 4, 2.0);
+        blur_rect(&mut rgba, 0, 0, 0, 4, 2.0);
+        assert_eq!(rgba, blurred);
+    }
+
+    #[test]
+    fn edge_modes_map_outside_coordinates() {
+        let map = |edge: EdgeMode| [-2, -1, 0, 3, 4, 5].map(|i| edge.index(i, 4));
+        assert_eq!(map(EdgeMode::Clamp), [0, 0, 0, 3, 3, 3]);
+        assert_eq!(map(EdgeMode::Wrap), [2, 3, 0, 3, 0, 1]);
+        assert_eq!(map(EdgeMode::Mirror), [2, 1, 0, 3, 2, 1]);
+        assert_eq!(EdgeMode::Mirror.index(-3, 1), 0);
+    }
+
+    #[test]
+    fn convolve_identity_and_shift_kernels() {
+        let rgba = RgbaImage::from_fn(4, 3, |x, y| Rgba([(x * 60) as u8, (y * 100) as u8, 7, 100 + x as u8]));
+        let mut identity = [0.0; 25];
+        identity[12] = 1.0;
+        for edge in [EdgeMode::Clamp, EdgeMode::Wrap, EdgeMode::Mirror] {
+            assert_eq!(convolve(&rgba, &identity, 5, 1.0, 0.0, edge), rgba);
+        }
+
+        // Sampling the left neighbor shows how each mode fills column 0
+        let left = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+        let first_red = |edge| convolve(&rgba, &left, 3, 1.0, 0.0, edge).get_pixel(0, 0)[0];
+        assert_eq!(first_red(EdgeMode::Clamp), 0);
+        assert_eq!(first_red(EdgeMode::Wrap), 180);
+        assert_eq!(first_red(EdgeMode::Mirror), 60);
+
+        // Divisor and offset apply after the sum, alpha is untouched
+        let halved = convolve(&rgba, &identity, 5, 2.0, 10.0, EdgeMode::Clamp);
+        assert_eq!(halved.get_pixel(3, 2), &Rgba([100, 110, 14, 103]));
+    }
+}
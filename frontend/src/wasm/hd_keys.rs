@@ -0,0 +1,259 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use base64::{Engine as _, engine::general_purpose};
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use k256::{FieldBytes, Scalar, elliptic_curve::PrimeField};
+use crate::crypto::CryptoModule;
+
+/// Child indices from here up are hardened
+const HARDENED: u32 = 0x8000_0000;
+
+/// Curves `derive_path` supports, with their master key HMAC keys
+#[derive(Clone, Copy, PartialEq)]
+enum HdCurve {
+    Secp256k1,
+    Ed25519,
+}
+
+impl HdCurve {
+    fn seed_key(self) -> &'static [u8] {
+        match self {
+            HdCurve::Secp256k1 => b"Bitcoin seed",
+            HdCurve::Ed25519 => b"ed25519 seed",
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl CryptoModule {
+    /// Derive a key from a seed along a BIP-32 path such as `m/44'/0'/0'/0/0`
+    ///
+    /// `seed_base64` is usually the output of `mnemonic_to_seed`. `curve` is
+    /// `secp256k1` (default, BIP-32) or `ed25519` (SLIP-0010), which only
+    /// allows hardened segments. Hardened segments are marked with `'` or
+    /// `h`. Returns `{ secretKey, publicKey, chainCode }` in base64; the keys
+    /// work with `sign_secp256k1` or `sign_ed25519`, and the public key is
+    /// compressed SEC1 for secp256k1 and 32 bytes for Ed25519.
+    #[wasm_bindgen]
+    pub fn derive_path(&self, seed_base64: &str, path: &str, curve: Option<String>) -> Result<JsValue, JsValue> {
+        let curve = match curve.as_deref().map(str::to_lowercase).as_deref() {
+            None | Some("secp256k1") => HdCurve::Secp256k1,
+            Some("ed25519") => HdCurve::Ed25519,
+            Some(other) => return Err(JsValue::from_str(&format!("Unknown HD curve: {} (expected secp256k1 or ed25519)", other))),
+        };
+
+        let seed = general_purpose::STANDARD
+            .decode(seed_base64)
+            .map_err(|e| JsValue::from_str(&format!("Invalid seed: {}", e)))?;
+        if !(16..=64).contains(&seed.len()) {
+            return Err(JsValue::from_str("Seed must be 16 to 64 bytes"));
+        }
+
+        let derived = derive(&seed, path, curve)?;
+        let result = serde_json::json!({
+            "secretKey": general_purpose::STANDARD.encode(derived.secret_key),
+            "publicKey": general_purpose::STANDARD.encode(derived.public_key),
+            "chainCode": general_purpose::STANDARD.encode(derived.chain_code)
+        });
+
+        JsValue::from_serde(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+}
+
+/// Keys and chain code at the end of a derivation path
+struct DerivedKey {
+    secret_key: [u8; 32],
+    /// Compressed SEC1 for secp256k1, 32 bytes for Ed25519
+    public_key: Vec<u8>,
+    chain_code: [u8; 32],
+}
+
+/// Walk `path` from the master key of `seed`
+fn derive(seed: &[u8], path: &str, curve: HdCurve) -> Result<DerivedKey, JsValue> {
+    let indices = parse_path(path)?;
+    if curve == HdCurve::Ed25519 && indices.iter().any(|&index| index < HARDENED) {
+        return Err(JsValue::from_str("Ed25519 derivation only supports hardened path segments"));
+    }
+
+    let (mut key, mut chain_code) = split(hmac_sha512(curve.seed_key(), seed));
+    if curve == HdCurve::Secp256k1 {
+        secp256k1_scalar(&key)?;
+    }
+
+    for index in indices {
+        let mut data = Vec::with_capacity(37);
+        if index >= HARDENED {
+            data.push(0);
+            data.extend_from_slice(&key);
+        } else {
+            data.extend_from_slice(&secp256k1_public_key(&key)?);
+        }
+        data.extend_from_slice(&index.to_be_bytes());
+
+        let (tweak, child_chain_code) = split(hmac_sha512(&chain_code, &data));
+        key = match curve {
+            HdCurve::Ed25519 => tweak,
+            HdCurve::Secp256k1 => {
+                let child = secp256k1_scalar(&tweak)? + secp256k1_scalar(&key)?;
+                if child == Scalar::ZERO {
+                    return Err(JsValue::from_str("Derived key is invalid; use the next index"));
+                }
+                child.to_bytes().into()
+            }
+        };
+        chain_code = child_chain_code;
+    }
+
+    let public_key = match curve {
+        HdCurve::Secp256k1 => secp256k1_public_key(&key)?.to_vec(),
+        HdCurve::Ed25519 => {
+            ed25519_dalek::SigningKey::from_bytes(&key).verifying_key().as_bytes().to_vec()
+        }
+    };
+
+    Ok(DerivedKey { secret_key: key, public_key, chain_code })
+}
+
+/// Child indices of a path like `m/44'/60'/0'/0/0`
+fn parse_path(path: &str) -> Result<Vec<u32>, JsValue> {
+    let invalid = || JsValue::from_str(&format!("Invalid derivation path: {}", path));
+
+    let mut segments = path.trim().split('/');
+    if segments.next() != Some("m") {
+        return Err(invalid());
+    }
+
+    segments
+        .map(|segment| {
+            let (number, hardened) = match segment.strip_suffix(['\'', 'h', 'H']) {
+                Some(number) => (number, true),
+                None => (segment, false),
+            };
+            let index: u32 = number.parse().map_err(|_| invalid())?;
+            if index >= HARDENED {
+                return Err(invalid());
+            }
+            Ok(if hardened { index + HARDENED } else { index })
+        })
+        .collect()
+}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> [u8; 64] {
+    let mut mac = <Hmac<Sha512> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+
+    let mut output = [0u8; 64];
+    output.copy_from_slice(&mac.finalize().into_bytes());
+    output
+}
+
+/// Key and chain code halves of an HMAC output
+fn split(output: [u8; 64]) -> ([u8; 32], [u8; 32]) {
+    let mut key = [0u8; 32];
+    let mut chain_code = [0u8; 32];
+    key.copy_from_slice(&output[..32]);
+    chain_code.copy_from_slice(&output[32..]);
+    (key, chain_code)
+}
+
+/// The bytes as a secp256k1 scalar, rejecting zero and values past the
+/// group order as BIP-32 requires
+fn secp256k1_scalar(bytes: &[u8; 32]) -> Result<Scalar, JsValue> {
+    Option::<Scalar>::from(Scalar::from_repr(FieldBytes::clone_from_slice(bytes)))
+        .filter(|scalar| *scalar != Scalar::ZERO)
+        .ok_or_else(|| JsValue::from_str("Derived key is invalid; use the next index"))
+}
+
+/// Compressed SEC1 public key for a secp256k1 secret key
+fn secp256k1_public_key(secret: &[u8; 32]) -> Result<[u8; 33], JsValue> {
+    let signing_key = k256::ecdsa::SigningKey::from_slice(secret)
+        .map_err(|e| JsValue::from_str(&format!("Invalid derived key: {}", e)))?;
+
+    let mut public_key = [0u8; 33];
+    public_key.copy_from_slice(signing_key.verifying_key().to_encoded_point(true).as_bytes());
+    Ok(public_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Seed of test vector 1 in BIP-32 and SLIP-0010
+    const SEED: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+    fn derived_hex(path: &str, curve: HdCurve) -> (String, String, String) {
+        let derived = derive(&SEED, path, curve).ok().unwrap();
+        (hex::encode(derived.secret_key), hex::encode(derived.public_key), hex::encode(derived.chain_code))
+    }
+
+    #[test]
+    fn bip32_test_vector_1() {
+        let cases = [
+            (
+                "m",
+                "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35",
+                "0339a36013301597daef41fbe593a02cc513d0b55527ec2df1050e2e8ff49c85c2",
+                "873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508",
+            ),
+            (
+                "m/0'",
+                "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea",
+                "035a784662a4a20a65bf6aab9ae98a6c068a81c52e4b032c0fb5400c706cfccc56",
+                "47fdacbd0f1097043b78c63c20c34ef4ed9a111d980047ad16282c7ae6236141",
+            ),
+            (
+                "m/0'/1",
+                "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368",
+                "03501e454bf00751f24b1b489aa925215d66af2234e3891c3b21a52bedb3cd711c",
+                "2a7857631386ba23dacac34180dd1983734e444fdbf774041578e9b6adb37c19",
+            ),
+            (
+                "m/0h/1/2H",
+                "cbce0d719ecf7431d88e6a89fa1483e02e35092af60c042b1df2ff59fa424dca",
+                "0357bfe1e341d01c69fe5654309956cbea516822fba8a601743a012a7896ee8dc2",
+                "04466b9cc8e161e966409ca52986c584f07e9dc81f735db683c3ff6ec7b1503f",
+            ),
+        ];
+
+        for (path, secret, public, chain_code) in cases {
+            assert_eq!(derived_hex(path, HdCurve::Secp256k1), (secret.into(), public.into(), chain_code.into()), "{}", path);
+        }
+    }
+
+    #[test]
+    fn slip10_ed25519_test_vector_1() {
+        // SLIP-0010 lists public keys with a leading 0x00 byte, dropped here
+        let cases = [
+            (
+                "m",
+                "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7",
+                "a4b2856bfec510abab89753fac1ac0e1112364e7d250545963f135f2a33188ed",
+                "90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb",
+            ),
+            (
+                "m/0'",
+                "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3",
+                "8c8a13df77a28f3445213a0f432fde644acaa215fc72dcdf300d5efaa85d350c",
+                "8b59aa11380b624e81507a27fedda59fea6d0b779a778918a2fd3590e16e9c69",
+            ),
+            (
+                "m/0'/1'",
+                "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2",
+                "1932a5270f335bed617d5b935c80aedb1a35bd9fc1e31acafd5372c30f5c1187",
+                "a320425f77d1b5c2505a6b1b27382b37368ee640e3557c315416801243552f14",
+            ),
+        ];
+
+        for (path, secret, public, chain_code) in cases {
+            assert_eq!(derived_hex(path, HdCurve::Ed25519), (secret.into(), public.into(), chain_code.into()), "{}", path);
+        }
+    }
+
+    #[test]
+    fn parses_hardened_markers() {
+        assert_eq!(parse_path("m/44'/60h/0H/0/7").ok().unwrap(), vec![44 + HARDENED, 60 + HARDENED, HARDENED, 0, 7]);
+        assert!(parse_path("m").ok().unwrap().is_empty());
+    }
+}
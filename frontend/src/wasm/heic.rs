@@ -0,0 +1,33 @@
+use wasm_bindgen::prelude::*;
+use image::{DynamicImage, RgbaImage};
+use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+use crate::limits::check_dimensions;
+
+/// Decode the primary image of a HEIC/HEIF container
+pub(crate) fn decode_heic(image_data: &[u8]) -> Result<DynamicImage, JsValue> {
+    let to_js = |e: libheif_rs::HeifError| JsValue::from_str(&format!("Failed to load HEIC: {}", e));
+
+    let lib_heif = LibHeif::new();
+    let context = HeifContext::read_from_bytes(image_data).map_err(to_js)?;
+    let handle = context.primary_image_handle().map_err(to_js)?;
+    check_dimensions(handle.width(), handle.height())?;
+    let image = lib_heif
+        .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgba), None)
+        .map_err(to_js)?;
+
+    let planes = image.planes();
+    let plane = planes
+        .interleaved
+        .ok_or_else(|| JsValue::from_str("Failed to load HEIC: missing interleaved plane"))?;
+
+    // Rows may be padded, so copy them out one at a time
+    let row_bytes = plane.width as usize * 4;
+    let mut rgba = Vec::with_capacity(row_bytes * plane.height as usize);
+    for row in plane.data.chunks(plane.stride).take(plane.height as usize) {
+        rgba.extend_from_slice(&row[..row_bytes]);
+    }
+
+    RgbaImage::from_raw(plane.width, plane.height, rgba)
+        .map(DynamicImage::ImageRgba8)
+        .ok_or_else(|| JsValue::from_str("Failed to load HEIC: unexpected buffer size"))
+}
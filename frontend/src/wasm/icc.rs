@@ -0,0 +1,414 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use image::DynamicImage;
+use qcms::{DataType, Intent, Profile, Transform};
+use crate::adjustments::srgb_to_linear;
+use crate::image_processor::{ImageProcessor, into_color_type};
+use crate::riff;
+
+/// Refuse to inflate compressed PNG profiles beyond this size
+const MAX_ICC_SIZE: usize = 4 * 1024 * 1024;
+
+/// D50 illuminant, the ICC profile connection space white point
+const D50: [f32; 3] = [0.9642, 1.0, 0.8249];
+
+/// sRGB primaries chromatically adapted to D50, as columns of the RGB to XYZ matrix
+const SRGB_RED_D50: [f32; 3] = [0.436_074_7, 0.222_504_5, 0.013_932_2];
+const SRGB_GREEN_D50: [f32; 3] = [0.385_064_9, 0.716_878_6, 0.097_104_5];
+const SRGB_BLUE_D50: [f32; 3] = [0.143_080_4, 0.060_616_9, 0.714_173_3];
+
+/// Samples in the generated tone curves
+const TRC_SAMPLES: usize = 1024;
+
+/// Grid points per axis when transforming images deeper than 8 bits, spaced
+/// so every point falls on an exact 8-bit value
+const LATTICE_SIZE: usize = 18;
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Return the embedded ICC profile of a JPEG, PNG or WebP, if any
+    #[wasm_bindgen]
+    pub fn extract_icc_profile(&self, image_data: &[u8]) -> Option<Vec<u8>> {
+        extract_icc(image_data)
+    }
+
+    /// Describe the embedded ICC profile, or `null` if there is none
+    ///
+    /// Returns `{ description, colorSpace, deviceClass, version, size }`.
+    #[wasm_bindgen]
+    pub fn get_icc_info(&self, image_data: &[u8]) -> Result<JsValue, JsValue> {
+        let result = match icc_info(image_data) {
+            Some(info) => info,
+            None => return Ok(JsValue::NULL),
+        };
+
+        JsValue::from_serde(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+}
+
+/// Summary of the embedded ICC profile, shared with `inspect`
+pub(crate) fn icc_info(image_data: &[u8]) -> Option<serde_json::Value> {
+    let profile = extract_icc(image_data).filter(|profile| profile.len() >= 128)?;
+
+    let signature = |offset: usize| String::from_utf8_lossy(&profile[offset..offset + 4]).trim_end().to_string();
+    Some(serde_json::json!({
+        "description": description(&profile),
+        "colorSpace": signature(16),
+        "deviceClass": signature(12),
+        "version": format!("{}.{}", profile[8], profile[9] >> 4),
+        "size": profile.len()
+    }))
+}
+
+/// Find the ICC profile embedded in a JPEG (APP2), PNG (iCCP) or WebP (ICCP)
+pub(crate) fn extract_icc(data: &[u8]) -> Option<Vec<u8>> {
+    match image::guess_format(data).ok()? {
+        image::ImageFormat::Jpeg => jpeg_icc(data),
+        image::ImageFormat::Png => png_icc(data),
+        image::ImageFormat::WebP => webp_icc(data),
+        _ => None,
+    }
+}
+
+/// Reassemble the possibly multi-segment `ICC_PROFILE` APP2 payload
+fn jpeg_icc(data: &[u8]) -> Option<Vec<u8>> {
+    let mut chunks: Vec<(u8, &[u8])> = Vec::new();
+    let mut pos = 2;
+
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            return None;
+        }
+        let marker = data[pos + 1];
+        if marker == 0xFF {
+            pos += 1;
+            continue;
+        }
+        // Profiles always precede the scan data
+        if marker == 0xDA || marker == 0xD9 {
+            break;
+        }
+        if marker == 0x01 || (0xD0..=0xD8).contains(&marker) {
+            pos += 2;
+            continue;
+        }
+
+        let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let end = (pos + 2 + length).min(data.len());
+        let payload = &data[(pos + 4).min(end)..end];
+        if marker == 0xE2 && payload.len() > 14 && payload.starts_with(b"ICC_PROFILE\0") {
+            chunks.push((payload[12], &payload[14..]));
+        }
+
+        pos = end;
+    }
+
+    if chunks.is_empty() {
+        return None;
+    }
+    chunks.sort_by_key(|(sequence, _)| *sequence);
+    Some(chunks.iter().flat_map(|(_, chunk)| chunk.iter().copied()).collect())
+}
+
+fn png_icc(data: &[u8]) -> Option<Vec<u8>> {
+    let mut pos = 8;
+
+    while pos + 8 <= data.len() {
+        let length = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+        let chunk_type = &data[pos + 4..pos + 8];
+        // Length, type, payload and CRC; checked so a huge length can't wrap on wasm32
+        let end = length.checked_add(12).and_then(|n| n.checked_add(pos))?;
+        let payload = data.get(pos + 8..end - 4)?;
+
+        match chunk_type {
+            // Profile name, NUL, compression method, then a zlib stream
+            b"iCCP" => {
+                let name_end = payload.iter().position(|&b| b == 0)?;
+                let compressed = payload.get(name_end + 2..)?;
+                return miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(compressed, MAX_ICC_SIZE).ok();
+            }
+            b"IDAT" | b"IEND" => return None,
+            _ => {}
+        }
+
+        pos = end;
+    }
+
+    None
+}
+
+fn webp_icc(data: &[u8]) -> Option<Vec<u8>> {
+    riff::chunks(data)
+        .map_while(Result::ok)
+        .find(|chunk| chunk.fourcc == b"ICCP")
+        .map(|chunk| chunk.payload.to_vec())
+}
+
+/// Profile description from a v2 `desc` or v4 `mluc` tag
+fn description(profile: &[u8]) -> Option<String> {
+    let u32_at = |offset: usize| -> Option<usize> {
+        profile.get(offset..offset + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize)
+    };
+    // Offsets and sizes are read from the file and may be anything
+    let span = |start: usize, len: usize| Some(start..start.checked_add(len)?);
+
+    // A forged count can't make us scan past the tag table the data holds
+    let tag_count = u32_at(128)?.min(profile.len().saturating_sub(132) / 12);
+    let (offset, size) = (0..tag_count).find_map(|i| {
+        let entry = 132 + i * 12;
+        if profile.get(entry..entry + 4)? == b"desc" {
+            Some((u32_at(entry + 4)?, u32_at(entry + 8)?))
+        } else {
+            None
+        }
+    })?;
+    let tag = profile.get(span(offset, size)?)?;
+
+    match tag.get(0..4)? {
+        b"desc" => {
+            let count = u32_at(offset + 8)?;
+            let text = tag.get(span(12, count)?)?;
+            Some(String::from_utf8_lossy(text).trim_end_matches('\0').to_string())
+        }
+        b"mluc" => {
+            // First record: language, country, byte length, offset from tag start
+            let length = u32_at(offset + 20)?;
+            let start = u32_at(offset + 24)?;
+            let utf16: Vec<u16> = tag
+                .get(span(start, length)?)?
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            Some(String::from_utf16_lossy(&utf16))
+        }
+        _ => None,
+    }
+}
+
+/// Convert pixels tagged with an embedded RGB profile to sRGB
+///
+/// Images without a profile, with an sRGB profile, or with a profile the
+/// CMS can't use are returned unchanged.
+pub(crate) fn convert_to_srgb(image_data: &[u8], img: DynamicImage) -> DynamicImage {
+    let profile = match extract_icc(image_data) {
+        Some(profile) => profile,
+        None => return img,
+    };
+    if profile.get(16..20) != Some(&b"RGB "[..]) || description(&profile).is_some_and(|d| d.starts_with("sRGB")) {
+        return img;
+    }
+
+    transform_to_srgb(&profile, &img).unwrap_or(img)
+}
+
+/// Run pixels through the CMS from `profile` to sRGB, keeping their color type
+///
+/// qcms only takes 8-bit samples, so deeper images are looked up in a
+/// lattice of transformed colors with trilinear interpolation instead of
+/// being truncated; 16-bit and float gradients come out smooth.
+fn transform_to_srgb(profile: &[u8], img: &DynamicImage) -> Option<DynamicImage> {
+    let input = Profile::new_from_slice(profile, false)?;
+    let mut output = Profile::new_sRGB();
+    output.precache_output_transform();
+
+    let color = img.color();
+    if color.bytes_per_pixel() == color.channel_count() {
+        let transform = Transform::new(&input, &output, DataType::RGBA8, Intent::Perceptual)?;
+        let mut rgba = img.to_rgba8();
+        transform.apply(&mut rgba);
+        return Some(into_color_type(DynamicImage::ImageRgba8(rgba), color));
+    }
+
+    let transform = Transform::new(&input, &output, DataType::RGB8, Intent::Perceptual)?;
+    let step = 255 / (LATTICE_SIZE - 1);
+    let mut lattice: Vec<u8> = (0..LATTICE_SIZE.pow(3))
+        .flat_map(|i| [i / (LATTICE_SIZE * LATTICE_SIZE), i / LATTICE_SIZE % LATTICE_SIZE, i % LATTICE_SIZE])
+        .map(|node| (node * step) as u8)
+        .collect();
+    transform.apply(&mut lattice);
+
+    let scale = (LATTICE_SIZE - 1) as f32;
+    let mut rgba = img.to_rgba32f();
+    for pixel in rgba.pixels_mut() {
+        let mut base = [0; 3];
+        let mut frac = [0.0; 3];
+        for c in 0..3 {
+            let v = pixel[c].max(0.0).min(1.0) * scale;
+            base[c] = (v as usize).min(LATTICE_SIZE - 2);
+            frac[c] = v - base[c] as f32;
+        }
+
+        let mut mixed = [0.0; 3];
+        for corner in 0..8 {
+            let offset = [(corner >> 2) & 1, (corner >> 1) & 1, corner & 1];
+            let weight: f32 = (0..3).map(|c| if offset[c] == 1 { frac[c] } else { 1.0 - frac[c] }).product();
+            let node = ((base[0] + offset[0]) * LATTICE_SIZE + base[1] + offset[1]) * LATTICE_SIZE + base[2] + offset[2];
+            for c in 0..3 {
+                mixed[c] += weight * lattice[node * 3 + c] as f32 / 255.0;
+            }
+        }
+        pixel.0[..3].copy_from_slice(&mixed);
+    }
+
+    Some(into_color_type(DynamicImage::ImageRgba32F(rgba), color))
+}
+
+/// Build a minimal ICC v2 display profile describing sRGB
+pub(crate) fn srgb_profile() -> Vec<u8> {
+    let s15f16 = |v: f32| ((v * 65536.0).round() as i32).to_be_bytes();
+    let xyz = |c: [f32; 3]| {
+        let mut tag = b"XYZ \0\0\0\0".to_vec();
+        for v in c {
+            tag.extend_from_slice(&s15f16(v));
+        }
+        tag
+    };
+
+    let mut curve = b"curv\0\0\0\0".to_vec();
+    curve.extend_from_slice(&(TRC_SAMPLES as u32).to_be_bytes());
+    for i in 0..TRC_SAMPLES {
+        let v = srgb_to_linear(i as f32 / (TRC_SAMPLES - 1) as f32);
+        curve.extend_from_slice(&((v * 65535.0).round() as u16).to_be_bytes());
+    }
+
+    let name = b"sRGB IEC61966-2.1\0";
+    let mut desc = b"desc\0\0\0\0".to_vec();
+    desc.extend_from_slice(&(name.len() as u32).to_be_bytes());
+    desc.extend_from_slice(name);
+    // Empty Unicode and ScriptCode descriptions
+    desc.extend_from_slice(&[0; 8]);
+    desc.extend_from_slice(&[0; 3]);
+    desc.extend_from_slice(&[0; 67]);
+
+    let mut copyright = b"text\0\0\0\0".to_vec();
+    copyright.extend_from_slice(b"No copyright, use freely\0");
+
+    // The three tone curves share one copy of the curve data
+    let tags: Vec<(&[u8; 4], Vec<u8>)> = vec![
+        (b"desc", desc),
+        (b"cprt", copyright),
+        (b"wtpt", xyz(D50)),
+        (b"rXYZ", xyz(SRGB_RED_D50)),
+        (b"gXYZ", xyz(SRGB_GREEN_D50)),
+        (b"bXYZ", xyz(SRGB_BLUE_D50)),
+        (b"rTRC", curve),
+    ];
+
+    let table_size = 4 + (tags.len() + 2) * 12;
+    let mut data = Vec::new();
+    let mut table = Vec::with_capacity(table_size);
+    table.extend_from_slice(&((tags.len() + 2) as u32).to_be_bytes());
+
+    let mut trc = (0, 0);
+    for (signature, tag) in &tags {
+        let offset = 128 + table_size + data.len();
+        table.extend_from_slice(*signature);
+        table.extend_from_slice(&(offset as u32).to_be_bytes());
+        table.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+        if *signature == b"rTRC" {
+            trc = (offset, tag.len());
+        }
+
+        data.extend_from_slice(tag);
+        // Tag data is 4-byte aligned
+        while data.len() % 4 != 0 {
+            data.push(0);
+        }
+    }
+    for signature in [b"gTRC", b"bTRC"] {
+        table.extend_from_slice(signature);
+        table.extend_from_slice(&(trc.0 as u32).to_be_bytes());
+        table.extend_from_slice(&(trc.1 as u32).to_be_bytes());
+    }
+
+    let mut header = vec![0u8; 128];
+    let size = (128 + table.len() + data.len()) as u32;
+    header[0..4].copy_from_slice(&size.to_be_bytes());
+    header[8..12].copy_from_slice(&[2, 0x10, 0, 0]);
+    header[12..16].copy_from_slice(b"mntr");
+    header[16..20].copy_from_slice(b"RGB ");
+    header[20..24].copy_from_slice(b"XYZ ");
+    header[36..40].copy_from_slice(b"acsp");
+    for (i, v) in D50.iter().enumerate() {
+        header[68 + i * 4..72 + i * 4].copy_from_slice(&s15f16(*v));
+    }
+
+    let mut profile = header;
+    profile.extend_from_slice(&table);
+    profile.extend_from_slice(&data);
+    profile
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb};
+
+    fn png_with_chunk(chunk_type: &[u8; 4], length: u32, payload: &[u8]) -> Vec<u8> {
+        let mut data = b"\x89PNG\r\n\x1a\n".to_vec();
+        data.extend_from_slice(&length.to_be_bytes());
+        data.extend_from_slice(chunk_type);
+        data.extend_from_slice(payload);
+        data
+    }
+
+    #[test]
+    fn png_icc_rejects_overflowing_length() {
+        assert_eq!(png_icc(&png_with_chunk(b"iCCP", u32::MAX, b"name\0\0")), None);
+        assert_eq!(png_icc(&png_with_chunk(b"tEXt", u32::MAX - 8, &[])), None);
+    }
+
+    #[test]
+    fn description_reads_generated_profile() {
+        assert_eq!(description(&srgb_profile()).as_deref(), Some("sRGB IEC61966-2.1"));
+    }
+
+    #[test]
+    fn description_caps_forged_tag_count() {
+        let mut profile = srgb_profile();
+        profile[128..132].copy_from_slice(&u32::MAX.to_be_bytes());
+        // Still finds the real tag without walking four billion entries
+        assert_eq!(description(&profile).as_deref(), Some("sRGB IEC61966-2.1"));
+
+        profile.truncate(140);
+        assert_eq!(description(&profile), None);
+    }
+
+    #[test]
+    fn description_rejects_wrapping_offsets() {
+        let mut profile = srgb_profile();
+        let tag_count = u32::from_be_bytes([profile[128], profile[129], profile[130], profile[131]]) as usize;
+        for i in 0..tag_count {
+            let entry = 132 + i * 12;
+            if &profile[entry..entry + 4] == b"desc" {
+                profile[entry + 4..entry + 12].copy_from_slice(&[0xFF; 8]);
+            }
+        }
+        assert_eq!(description(&profile), None);
+    }
+
+    #[test]
+    fn transform_keeps_16_bit_depth() {
+        let gradient = ImageBuffer::from_fn(256, 1, |x, _| Rgb([x as u16 * 250 + 100, 30000, 65535 - x as u16 * 200]));
+        let img = DynamicImage::ImageRgb16(gradient.clone());
+
+        let converted = transform_to_srgb(&srgb_profile(), &img).unwrap();
+        let converted = converted.as_rgb16().expect("still 16-bit RGB");
+
+        // sRGB to sRGB is close to identity and not quantized to 8 bits
+        for (a, b) in gradient.pixels().zip(converted.pixels()) {
+            for c in 0..3 {
+                assert!((a[c] as i32 - b[c] as i32).abs() < 1024, "{:?} vs {:?}", a, b);
+            }
+        }
+        assert!(converted.pixels().any(|p| p[0] % 257 != 0));
+    }
+
+    #[test]
+    fn transform_keeps_8_bit_color_type() {
+        let img = DynamicImage::ImageLuma8(ImageBuffer::from_pixel(4, 4, image::Luma([128])));
+        let converted = transform_to_srgb(&srgb_profile(), &img).unwrap();
+        assert_eq!(converted.color(), image::ColorType::L8);
+    }
+}
@@ -0,0 +1,70 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Object, Reflect, Uint8Array};
+use image::{DynamicImage, GenericImageView, RgbaImage, imageops::{self, FilterType}};
+use image::codecs::ico::{IcoEncoder, IcoFrame};
+use crate::image_processor::{ImageProcessor, decode, encode};
+
+/// Sizes bundled into `favicon.ico`
+const ICO_SIZES: [u32; 4] = [16, 32, 48, 64];
+
+/// Standalone PNG icons and their conventional file names
+const PNG_ICONS: [(&str, u32); 3] = [
+    ("apple-touch-icon.png", 180),
+    ("icon-192.png", 192),
+    ("icon-512.png", 512),
+];
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Generate a favicon bundle from a logo
+    ///
+    /// Returns an object mapping file names to bytes: a multi-resolution
+    /// `favicon.ico` plus the standard PNG touch/app icons. Non-square
+    /// logos are centered on a transparent square canvas.
+    #[wasm_bindgen]
+    pub fn generate_favicon(&self, image_data: &[u8]) -> Result<Object, JsValue> {
+        let img = square_canvas(&decode(image_data)?);
+        let result = Object::new();
+
+        let mut ico_pngs = Vec::with_capacity(ICO_SIZES.len());
+        for size in ICO_SIZES {
+            ico_pngs.push((size, icon_png(&img, size)?));
+        }
+        let frames = ico_pngs
+            .iter()
+            .map(|(size, png)| IcoFrame::with_encoded(png.as_slice(), *size, *size, image::ColorType::Rgba8))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| JsValue::from_str(&format!("Failed to encode ICO: {}", e)))?;
+
+        let mut ico = Vec::new();
+        IcoEncoder::new(&mut ico)
+            .encode_images(&frames)
+            .map_err(|e| JsValue::from_str(&format!("Failed to encode ICO: {}", e)))?;
+        Reflect::set(&result, &"favicon.ico".into(), &Uint8Array::from(ico.as_slice()))?;
+
+        for (name, size) in PNG_ICONS {
+            let png = icon_png(&img, size)?;
+            Reflect::set(&result, &name.into(), &Uint8Array::from(png.as_slice()))?;
+        }
+
+        Ok(result)
+    }
+}
+
+/// Center the image on a transparent square canvas
+fn square_canvas(img: &DynamicImage) -> DynamicImage {
+    let (width, height) = img.dimensions();
+    if width == height {
+        return img.clone();
+    }
+
+    let side = width.max(height);
+    let mut canvas = RgbaImage::new(side, side);
+    imageops::overlay(&mut canvas, &img.to_rgba8(), ((side - width) / 2) as i64, ((side - height) / 2) as i64);
+    DynamicImage::ImageRgba8(canvas)
+}
+
+fn icon_png(img: &DynamicImage, size: u32) -> Result<Vec<u8>, JsValue> {
+    let icon = DynamicImage::ImageRgba8(img.resize_exact(size, size, FilterType::Lanczos3).to_rgba8());
+    encode(&icon, image::ImageOutputFormat::Png)
+}
@@ -0,0 +1,104 @@
+use wasm_bindgen::prelude::*;
+use sha2::{Sha256, Digest};
+use image::{DynamicImage, Rgba, RgbaImage};
+use crate::color::hsl_to_rgb;
+use crate::image_processor::{ImageProcessor, encode};
+use crate::limits::check_dimensions;
+
+/// Background behind the GitHub-style pattern
+const IDENTICON_BACKGROUND: Rgba<u8> = Rgba([240, 240, 240, 255]);
+
+/// Cells per side of the GitHub-style grid
+const GRID: u32 = 5;
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Render a square default avatar as PNG from a seed such as a username
+    ///
+    /// The seed is hashed with SHA-256, so the same seed always gives the
+    /// same avatar on every device. `style` is `github` (default), a
+    /// mirrored 5x5 grid in one color, or `geometric`, a four-fold
+    /// symmetric tiling of triangles in two colors.
+    #[wasm_bindgen]
+    pub fn generate_identicon(&self, seed: &str, size: u32, style: Option<String>) -> Result<Vec<u8>, JsValue> {
+        if size == 0 {
+            return Err(JsValue::from_str("Identicon size must be non-zero"));
+        }
+        check_dimensions(size, size)?;
+
+        let hash: [u8; 32] = Sha256::digest(seed.as_bytes()).into();
+        let img = match style.as_deref().unwrap_or("github") {
+            "github" => github_identicon(&hash, size),
+            "geometric" => geometric_identicon(&hash, size),
+            other => return Err(JsValue::from_str(&format!("Unknown identicon style: {}", other))),
+        };
+
+        encode(&DynamicImage::ImageRgba8(img), image::ImageOutputFormat::Png)
+    }
+}
+
+/// Opaque color with hue from `hue_bytes` and saturation and lightness kept
+/// in a range that reads well on both light and dark pages
+fn hash_color(hue_bytes: [u8; 2], saturation: u8, lightness: u8) -> Rgba<u8> {
+    let hue = u16::from_be_bytes(hue_bytes) as f32 / 65_536.0 * 360.0;
+    let s = 0.45 + saturation as f32 / 255.0 * 0.2;
+    let l = 0.45 + lightness as f32 / 255.0 * 0.2;
+    let [r, g, b] = hsl_to_rgb([hue, s, l]);
+    Rgba([(r * 255.0).round() as u8, (g * 255.0).round() as u8, (b * 255.0).round() as u8, 255])
+}
+
+/// Left three columns come from the hash and are mirrored onto the right,
+/// inside a margin of half a cell
+fn github_identicon(hash: &[u8; 32], size: u32) -> RgbaImage {
+    let color = hash_color([hash[0], hash[1]], hash[2], hash[3]);
+    let filled = |column: u32, row: u32| {
+        let column = column.min(GRID - 1 - column);
+        hash[4 + (column * GRID + row) as usize].is_multiple_of(2)
+    };
+
+    let cell = size as f32 / (GRID as f32 + 1.0);
+    let margin = cell / 2.0;
+    RgbaImage::from_fn(size, size, |x, y| {
+        let gx = ((x as f32 + 0.5 - margin) / cell).floor();
+        let gy = ((y as f32 + 0.5 - margin) / cell).floor();
+        let inside = (0.0..GRID as f32).contains(&gx) && (0.0..GRID as f32).contains(&gy);
+        if inside && filled(gx as u32, gy as u32) {
+            color
+        } else {
+            IDENTICON_BACKGROUND
+        }
+    })
+}
+
+/// A 2x2 quadrant of cells, each empty, full or a right triangle, reflected
+/// into the other three quadrants
+fn geometric_identicon(hash: &[u8; 32], size: u32) -> RgbaImage {
+    let background = hash_color([hash[0], hash[1]], hash[2], hash[3]);
+    let foreground = {
+        // Opposite side of the color wheel, lighter so the shapes stand out
+        let hue = u16::from_be_bytes([hash[0], hash[1]]).wrapping_add(32_768);
+        let base = hash_color(hue.to_be_bytes(), hash[4], 255);
+        Rgba([base[0].saturating_add(40), base[1].saturating_add(40), base[2].saturating_add(40), 255])
+    };
+    let shapes: [u8; 4] = [hash[5] % 6, hash[6] % 6, hash[7] % 6, hash[8] % 6];
+
+    let cell = size as f32 / 4.0;
+    RgbaImage::from_fn(size, size, |x, y| {
+        let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+        let (column, row) = (((px / cell) as u32).min(3), ((py / cell) as u32).min(3));
+        // Reflect into the top-left quadrant, flipping the in-cell coordinates too
+        let (u, column) = if column >= 2 { (1.0 - (px / cell).fract(), 3 - column) } else { ((px / cell).fract(), column) };
+        let (v, row) = if row >= 2 { (1.0 - (py / cell).fract(), 3 - row) } else { ((py / cell).fract(), row) };
+
+        let on = match shapes[(row * 2 + column) as usize] {
+            0 => false,
+            1 => true,
+            // Triangles filling the corner at top-left, top-right, bottom-left, bottom-right
+            2 => u + v < 1.0,
+            3 => u > v,
+            4 => u < v,
+            _ => u + v > 1.0,
+        };
+        if on { foreground } else { background }
+    })
+}
@@ -1,12 +1,29 @@
 use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
 use wasm_bindgen::Clamped;
 use web_sys::{console, ImageData};
 use image::{ImageBuffer, Rgba, DynamicImage, GenericImageView, imageops::FilterType};
 use base64::{Engine as _, engine::general_purpose};
+use std::cell::RefCell;
+use std::io::Cursor;
+use crate::cache::{DEFAULT_CACHE_BUDGET, ImageCache};
+use crate::cancel::{CancellationToken, run_cancellable};
+use crate::color::in_linear_light;
+use crate::encoding::{EncodeOptions, encode_as, encode_with_options};
+use crate::filters::{EdgeMode, MAX_KERNEL_SIZE, convolve};
+use crate::icc::convert_to_srgb;
+use crate::limits::{check_dimensions, check_header, check_output_size, decoder_limits};
+use crate::parallel;
+use crate::pipeline::LoadedImage;
 
 #[wasm_bindgen]
 pub struct ImageProcessor {
-    // Internal cache for processed images
+    /// Internal cache for processed images, see `set_cache_budget`
+    pub(crate) cache: RefCell<ImageCache>,
+    /// Resample in linear light, see `set_linear_light`
+    linear_light: bool,
+    /// Polled by resize, blur and convolution, see `set_cancellation_token`
+    pub(crate) cancel_token: Option<CancellationToken>,
 }
 
 #[wasm_bindgen]
@@ -14,114 +31,141 @@ impl ImageProcessor {
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
         console::log_1(&"Image Processor WASM module initialized".into());
-        ImageProcessor {}
+        ImageProcessor {
+            cache: RefCell::new(ImageCache::new(DEFAULT_CACHE_BUDGET)),
+            linear_light: false,
+            cancel_token: None,
+        }
+    }
+
+    /// Resize, blur and thumbnail in linear light instead of sRGB
+    ///
+    /// Slower, but avoids the dark halos gamma-space resampling leaves
+    /// around bright edges.
+    #[wasm_bindgen]
+    pub fn set_linear_light(&mut self, enabled: bool) {
+        self.linear_light = enabled;
+    }
+
+    /// Let `token` abort resize, blur and convolution calls midway
+    ///
+    /// A cancelled call fails with "Operation cancelled"; `reset()` the
+    /// token before starting the next one.
+    #[wasm_bindgen]
+    pub fn set_cancellation_token(&mut self, token: &CancellationToken) {
+        self.cancel_token = Some(token.clone());
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_cancellation_token(&mut self) {
+        self.cancel_token = None;
     }
 
     /// Resize image to specified dimensions
+    ///
+    /// The target size is held to `set_limits` before anything is
+    /// allocated.
     #[wasm_bindgen]
     pub fn resize_image(&self, image_data: &[u8], width: u32, height: u32, maintain_aspect: bool) -> Result<Vec<u8>, JsValue> {
-        let img = image::load_from_memory(image_data)
-            .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?;
-        
-        let resized = if maintain_aspect {
-            img.resize(width, height, FilterType::Lanczos3)
-        } else {
-            img.resize_exact(width, height, FilterType::Lanczos3)
-        };
-        
-        let mut output = Vec::new();
-        resized.write_to(&mut output, image::ImageOutputFormat::Png)
-            .map_err(|e| JsValue::from_str(&format!("Failed to encode image: {}", e)))?;
-        
-        Ok(output)
+        check_dimensions(width, height)?;
+        self.cached(image_data, format!("resize:{}x{}:{}:{}", width, height, maintain_aspect, self.linear_light), || {
+            let img = decode(image_data)?;
+
+            let resize = |img: DynamicImage| if maintain_aspect {
+                parallel::resize(&img, width, height, FilterType::Lanczos3)
+            } else {
+                parallel::resize_exact(&img, width, height, FilterType::Lanczos3)
+            };
+            let resized = run_cancellable(self.cancel_token.as_ref(), || {
+                Ok(if self.linear_light { in_linear_light(&img, resize) } else { resize(img) })
+            })?;
+
+            encode(&resized, image::ImageOutputFormat::Png)
+        })
     }
 
     /// Convert image format
     #[wasm_bindgen]
-    pub fn convert_format(&self, image_data: &[u8], format: &str) -> Result<Vec<u8>, JsValue> {
-        let img = image::load_from_memory(image_data)
-            .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?;
+    pub fn convert_format(&self, image_data: &[u8], format: &str, options: JsValue) -> Result<Vec<u8>, JsValue> {
+        let img = decode(image_data)?;
         
-        let output_format = match format.to_lowercase().as_str() {
-            "png" => image::ImageOutputFormat::Png,
-            "jpeg" | "jpg" => image::ImageOutputFormat::Jpeg(85),
-            "webp" => image::ImageOutputFormat::WebP,
-            "bmp" => image::ImageOutputFormat::Bmp,
-            _ => return Err(JsValue::from_str("Unsupported format")),
-        };
-        
-        let mut output = Vec::new();
-        img.write_to(&mut output, output_format)
-            .map_err(|e| JsValue::from_str(&format!("Failed to convert image: {}", e)))?;
+        let options: EncodeOptions = parse_options(&options)?;
         
-        Ok(output)
+        encode_with_options(&img, format, &options)
     }
 
     /// Apply blur filter
     #[wasm_bindgen]
     pub fn apply_blur(&self, image_data: &[u8], sigma: f32) -> Result<Vec<u8>, JsValue> {
-        let img = image::load_from_memory(image_data)
-            .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?;
-        
-        let blurred = img.blur(sigma);
-        
-        let mut output = Vec::new();
-        blurred.write_to(&mut output, image::ImageOutputFormat::Png)
-            .map_err(|e| JsValue::from_str(&format!("Failed to encode image: {}", e)))?;
-        
-        Ok(output)
+        self.cached(image_data, format!("blur:{}:{}", sigma, self.linear_light), || {
+            let img = decode(image_data)?;
+
+            let blurred = run_cancellable(self.cancel_token.as_ref(), || {
+                Ok(if self.linear_light { in_linear_light(&img, |img| parallel::blur(&img, sigma)) } else { parallel::blur(&img, sigma) })
+            })?;
+
+            encode(&blurred, image::ImageOutputFormat::Png)
+        })
     }
 
     /// Apply grayscale filter
+    ///
+    /// `weights` optionally gives the red, green and blue contributions to
+    /// the gray value (e.g. `[0.6, 0.3, 0.1]` to keep red detail); by
+    /// default the standard luminance weights are used.
     #[wasm_bindgen]
-    pub fn apply_grayscale(&self, image_data: &[u8]) -> Result<Vec<u8>, JsValue> {
-        let img = image::load_from_memory(image_data)
-            .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?;
-        
-        let grayscale = img.grayscale();
-        
-        let mut output = Vec::new();
-        grayscale.write_to(&mut output, image::ImageOutputFormat::Png)
-            .map_err(|e| JsValue::from_str(&format!("Failed to encode image: {}", e)))?;
-        
-        Ok(output)
+    pub fn apply_grayscale(&self, image_data: &[u8], weights: Option<Vec<f32>>) -> Result<Vec<u8>, JsValue> {
+        self.cached(image_data, format!("grayscale:{:?}", weights), || {
+            let img = decode(image_data)?;
+
+            let grayscale = match weights {
+                None => img.grayscale(),
+                Some(weights) => {
+                    if weights.len() != 3 {
+                        return Err(JsValue::from_str("Grayscale weights must have 3 values"));
+                    }
+                    let rgba = img.to_rgba8();
+                    let gray = ImageBuffer::from_fn(rgba.width(), rgba.height(), |x, y| {
+                        let px = rgba.get_pixel(x, y);
+                        let value = weights[0] * px[0] as f32 + weights[1] * px[1] as f32 + weights[2] * px[2] as f32;
+                        image::LumaA([value.round().max(0.0).min(255.0) as u8, px[3]])
+                    });
+                    DynamicImage::ImageLumaA8(gray)
+                }
+            };
+
+            encode(&grayscale, image::ImageOutputFormat::Png)
+        })
     }
 
     /// Adjust brightness
     #[wasm_bindgen]
     pub fn adjust_brightness(&self, image_data: &[u8], value: i32) -> Result<Vec<u8>, JsValue> {
-        let img = image::load_from_memory(image_data)
-            .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?;
-        
-        let adjusted = img.brighten(value);
-        
-        let mut output = Vec::new();
-        adjusted.write_to(&mut output, image::ImageOutputFormat::Png)
-            .map_err(|e| JsValue::from_str(&format!("Failed to encode image: {}", e)))?;
-        
-        Ok(output)
+        self.cached(image_data, format!("brightness:{}", value), || {
+            let img = decode(image_data)?;
+
+            let adjusted = img.brighten(value);
+
+            encode(&adjusted, image::ImageOutputFormat::Png)
+        })
     }
 
     /// Adjust contrast
     #[wasm_bindgen]
     pub fn adjust_contrast(&self, image_data: &[u8], contrast: f32) -> Result<Vec<u8>, JsValue> {
-        let img = image::load_from_memory(image_data)
-            .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?;
-        
-        let adjusted = img.adjust_contrast(contrast);
-        
-        let mut output = Vec::new();
-        adjusted.write_to(&mut output, image::ImageOutputFormat::Png)
-            .map_err(|e| JsValue::from_str(&format!("Failed to encode image: {}", e)))?;
-        
-        Ok(output)
+        self.cached(image_data, format!("contrast:{}", contrast), || {
+            let img = decode(image_data)?;
+
+            let adjusted = img.adjust_contrast(contrast);
+
+            encode(&adjusted, image::ImageOutputFormat::Png)
+        })
     }
 
     /// Rotate image
     #[wasm_bindgen]
     pub fn rotate(&self, image_data: &[u8], degrees: u32) -> Result<Vec<u8>, JsValue> {
-        let img = image::load_from_memory(image_data)
-            .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?;
+        let img = decode(image_data)?;
         
         let rotated = match degrees {
             90 => img.rotate90(),
@@ -130,18 +174,13 @@ impl ImageProcessor {
             _ => return Err(JsValue::from_str("Only 90, 180, 270 degree rotations supported")),
         };
         
-        let mut output = Vec::new();
-        rotated.write_to(&mut output, image::ImageOutputFormat::Png)
-            .map_err(|e| JsValue::from_str(&format!("Failed to encode image: {}", e)))?;
-        
-        Ok(output)
+        encode(&rotated, image::ImageOutputFormat::Png)
     }
 
     /// Flip image
     #[wasm_bindgen]
     pub fn flip(&self, image_data: &[u8], horizontal: bool) -> Result<Vec<u8>, JsValue> {
-        let img = image::load_from_memory(image_data)
-            .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?;
+        let img = decode(image_data)?;
         
         let flipped = if horizontal {
             img.fliph()
@@ -149,61 +188,93 @@ impl ImageProcessor {
             img.flipv()
         };
         
-        let mut output = Vec::new();
-        flipped.write_to(&mut output, image::ImageOutputFormat::Png)
-            .map_err(|e| JsValue::from_str(&format!("Failed to encode image: {}", e)))?;
-        
-        Ok(output)
+        encode(&flipped, image::ImageOutputFormat::Png)
     }
 
     /// Crop image
     #[wasm_bindgen]
     pub fn crop(&self, image_data: &[u8], x: u32, y: u32, width: u32, height: u32) -> Result<Vec<u8>, JsValue> {
-        let mut img = image::load_from_memory(image_data)
-            .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?;
+        let mut img = decode(image_data)?;
         
         let cropped = img.crop(x, y, width, height);
         
-        let mut output = Vec::new();
-        cropped.write_to(&mut output, image::ImageOutputFormat::Png)
-            .map_err(|e| JsValue::from_str(&format!("Failed to encode image: {}", e)))?;
-        
-        Ok(output)
+        encode(&cropped, image::ImageOutputFormat::Png)
     }
 
     /// Compress image with quality setting
     #[wasm_bindgen]
-    pub fn compress(&self, image_data: &[u8], quality: u8) -> Result<Vec<u8>, JsValue> {
-        let img = image::load_from_memory(image_data)
-            .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?;
+    pub fn compress(&self, image_data: &[u8], quality: u8, options: JsValue) -> Result<Vec<u8>, JsValue> {
+        let img = decode(image_data)?;
         
-        let mut output = Vec::new();
-        img.write_to(&mut output, image::ImageOutputFormat::Jpeg(quality))
-            .map_err(|e| JsValue::from_str(&format!("Failed to compress image: {}", e)))?;
+        let mut options: EncodeOptions = parse_options(&options)?;
+        options.quality = Some(quality);
         
-        Ok(output)
+        encode_with_options(&img, "jpeg", &options)
+    }
+
+    /// Encode as AVIF with quality (1-100) and speed (1 = slowest/smallest, 10 = fastest)
+    #[cfg(feature = "avif")]
+    #[wasm_bindgen]
+    pub fn encode_avif(&self, image_data: &[u8], quality: u8, speed: u8) -> Result<Vec<u8>, JsValue> {
+        let img = decode(image_data)?;
+        encode_avif(&img, quality, speed)
     }
 
     /// Generate thumbnail
     #[wasm_bindgen]
     pub fn generate_thumbnail(&self, image_data: &[u8], max_width: u32, max_height: u32) -> Result<Vec<u8>, JsValue> {
-        let img = image::load_from_memory(image_data)
-            .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?;
-        
-        let thumbnail = img.thumbnail(max_width, max_height);
-        
-        let mut output = Vec::new();
-        thumbnail.write_to(&mut output, image::ImageOutputFormat::Jpeg(80))
-            .map_err(|e| JsValue::from_str(&format!("Failed to generate thumbnail: {}", e)))?;
-        
-        Ok(output)
+        self.cached(image_data, format!("thumbnail:{}x{}:{}", max_width, max_height, self.linear_light), || {
+            let img = decode(image_data)?;
+
+            let thumbnail = if self.linear_light {
+                in_linear_light(&img, |img| img.thumbnail(max_width, max_height))
+            } else {
+                img.thumbnail(max_width, max_height)
+            };
+
+            encode(&thumbnail, image::ImageOutputFormat::Jpeg(80))
+        })
+    }
+
+    /// Generate `srcset` variants, decoding the source only once
+    ///
+    /// Returns one encoded image per entry of `widths`, in the same order.
+    /// Widths beyond the source width are clamped rather than upscaled.
+    /// Smaller variants are downscaled from the next larger one, which is
+    /// much cheaper than resampling the full image each time.
+    #[wasm_bindgen]
+    pub fn generate_responsive_set(&self, image_data: &[u8], widths: &[u32], format: &str, quality: u8) -> Result<js_sys::Array, JsValue> {
+        if widths.contains(&0) {
+            return Err(JsValue::from_str("Widths must be non-zero"));
+        }
+
+        let img = decode(image_data)?;
+        let (src_width, src_height) = img.dimensions();
+
+        let mut order: Vec<usize> = (0..widths.len()).collect();
+        order.sort_by(|&a, &b| widths[b].cmp(&widths[a]));
+
+        let mut outputs = vec![Vec::new(); widths.len()];
+        let mut previous = img;
+        for i in order {
+            let width = widths[i].min(src_width);
+            let height = ((src_height as u64 * width as u64 / src_width as u64) as u32).max(1);
+            if previous.dimensions() != (width, height) {
+                previous = previous.resize_exact(width, height, FilterType::Lanczos3);
+            }
+            outputs[i] = encode_as(&previous, format, quality)?;
+        }
+
+        Ok(outputs
+            .iter()
+            .map(|bytes| js_sys::Uint8Array::from(bytes.as_slice()))
+            .collect())
     }
 
     /// Get image dimensions
     #[wasm_bindgen]
     pub fn get_dimensions(&self, image_data: &[u8]) -> Result<JsValue, JsValue> {
-        let img = image::load_from_memory(image_data)
-            .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?;
+        let img = decode(image_data)?;
         
         let (width, height) = img.dimensions();
         
@@ -217,56 +288,67 @@ impl ImageProcessor {
     }
 
     /// Apply custom filter using convolution matrix
+    ///
+    /// `kernel` is a square, row-major matrix with an odd side (3x3, 5x5,
+    /// 7x7, ...). Sums are divided by `divisor` (default 1) and shifted by
+    /// `offset` (default 0, on a 0-255 scale). `edge_mode` picks how the
+    /// border is sampled: `clamp` (default), `wrap` or `mirror`.
     #[wasm_bindgen]
-    pub fn apply_convolution(&self, image_data: &[u8], kernel: &[f32]) -> Result<Vec<u8>, JsValue> {
-        if kernel.len() != 9 {
-            return Err(JsValue::from_str("Kernel must be 3x3 (9 values)"));
-        }
-        
-        let img = image::load_from_memory(image_data)
-            .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?;
-        
-        // Convert to RGBA8
-        let rgba = img.to_rgba8();
-        let (width, height) = rgba.dimensions();
-        
-        let mut output = ImageBuffer::<Rgba<u8>, Vec<u8>>::new(width, height);
-        
-        // Apply convolution
-        for y in 1..height-1 {
-            for x in 1..width-1 {
-                let mut r = 0.0;
-                let mut g = 0.0;
-                let mut b = 0.0;
-                
-                for ky in 0..3 {
-                    for kx in 0..3 {
-                        let px = rgba.get_pixel(x + kx - 1, y + ky - 1);
-                        let k_val = kernel[ky * 3 + kx];
-                        
-                        r += px[0] as f32 * k_val;
-                        g += px[1] as f32 * k_val;
-                        b += px[2] as f32 * k_val;
-                    }
-                }
-                
-                let pixel = Rgba([
-                    r.max(0.0).min(255.0) as u8,
-                    g.max(0.0).min(255.0) as u8,
-                    b.max(0.0).min(255.0) as u8,
-                    rgba.get_pixel(x, y)[3]
-                ]);
-                
-                output.put_pixel(x, y, pixel);
+    pub fn apply_convolution(
+        &self,
+        image_data: &[u8],
+        kernel: &[f32],
+        divisor: Option<f32>,
+        offset: Option<f32>,
+        edge_mode: Option<String>,
+    ) -> Result<Vec<u8>, JsValue> {
+        self.cached(image_data, format!("convolution:{:?}:{:?}:{:?}:{:?}", kernel, divisor, offset, edge_mode), || {
+            let size = (kernel.len() as f64).sqrt() as usize;
+            if size * size != kernel.len() || size.is_multiple_of(2) {
+                return Err(JsValue::from_str("Kernel must be square with an odd size (9, 25, 49, ... values)"));
             }
-        }
-        
-        let dynamic_output = DynamicImage::ImageRgba8(output);
-        let mut encoded = Vec::new();
-        dynamic_output.write_to(&mut encoded, image::ImageOutputFormat::Png)
-            .map_err(|e| JsValue::from_str(&format!("Failed to encode image: {}", e)))?;
-        
-        Ok(encoded)
+            if size > MAX_KERNEL_SIZE {
+                return Err(JsValue::from_str(&format!("Kernel size must be at most {}x{}", MAX_KERNEL_SIZE, MAX_KERNEL_SIZE)));
+            }
+            let divisor = divisor.unwrap_or(1.0);
+            if divisor == 0.0 {
+                return Err(JsValue::from_str("Divisor must not be zero"));
+            }
+            let edge = edge_mode.as_deref().map_or(Ok(EdgeMode::Clamp), EdgeMode::parse)?;
+
+            let rgba = decode(image_data)?.to_rgba8();
+            let output = run_cancellable(self.cancel_token.as_ref(), || {
+                Ok(convolve(&rgba, kernel, size, divisor, offset.unwrap_or(0.0), edge))
+            })?;
+
+            encode(&DynamicImage::ImageRgba8(output), image::ImageOutputFormat::Png)
+        })
+    }
+
+    /// Decode an image once into a handle that supports chained operations
+    #[wasm_bindgen]
+    pub fn load(&self, image_data: &[u8]) -> Result<LoadedImage, JsValue> {
+        LoadedImage::new(image_data)
+    }
+
+    /// Decode an image straight into canvas `ImageData`
+    #[wasm_bindgen]
+    pub fn decode_to_image_data(&self, image_data: &[u8]) -> Result<ImageData, JsValue> {
+        to_image_data(&decode(image_data)?)
+    }
+
+    /// Encode canvas `ImageData` into the requested format
+    #[wasm_bindgen]
+    pub fn encode_image_data(&self, image_data: &ImageData, format: &str, quality: u8) -> Result<Vec<u8>, JsValue> {
+        let img = from_image_data(image_data)?;
+        encode_as(&img, format, quality)
+    }
+
+    /// Encode a raw RGBA buffer into the requested format
+    #[wasm_bindgen]
+    pub fn encode_rgba(&self, rgba: &[u8], width: u32, height: u32, format: &str, quality: u8) -> Result<Vec<u8>, JsValue> {
+        let img = from_rgba(rgba.to_vec(), width, height)?;
+        encode_as(&img, format, quality)
     }
 
     /// Convert to base64
@@ -284,8 +366,167 @@ impl ImageProcessor {
     }
 }
 
-// Export initialization function
-#[wasm_bindgen(start)]
-pub fn main() {
-    console::log_1(&"WASM Image Processor loaded successfully".into());
+/// Decode raw image bytes, mapping failures to a JS error
+pub(crate) fn decode(image_data: &[u8]) -> Result<DynamicImage, JsValue> {
+    #[cfg(not(feature = "avif"))]
+    if let Ok(image::ImageFormat::Avif) = image::guess_format(image_data) {
+        return Err(JsValue::from_str("AVIF support is not enabled in this build"));
+    }
+
+    if is_heic(image_data) {
+        #[cfg(feature = "heic")]
+        return crate::heic::decode_heic(image_data);
+        #[cfg(not(feature = "heic"))]
+        return Err(JsValue::from_str("HEIC needs a native build with the `heic` feature; convert to JPEG or PNG first"));
+    }
+
+    check_header(image_data)?;
+    let mut reader = image::io::Reader::new(std::io::Cursor::new(image_data))
+        .with_guessed_format()
+        .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?;
+    reader.limits(decoder_limits());
+    let img = reader
+        .decode()
+        .map_err(|e| JsValue::from_str(&format!("Failed to load image: {}", e)))?;
+
+    // Wide-gamut sources would otherwise look washed out once re-encoded untagged
+    Ok(convert_to_srgb(image_data, img))
+}
+
+/// Detect HEIC/HEIF by the ISO-BMFF `ftyp` major brand
+pub(crate) fn is_heic(image_data: &[u8]) -> bool {
+    image_data.len() >= 12
+        && &image_data[4..8] == b"ftyp"
+        && matches!(&image_data[8..12], b"heic" | b"heix" | b"heim" | b"heis" | b"hevc" | b"hevx")
+}
+
+/// Decode every `Uint8Array` in a JS array
+pub(crate) fn decode_array(images: &js_sys::Array) -> Result<Vec<DynamicImage>, JsValue> {
+    images
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+            let bytes = js_sys::Uint8Array::new(&value).to_vec();
+            decode(&bytes).map_err(|e| match e.as_string() {
+                Some(message) => JsValue::from_str(&format!("Image {}: {}", index, message)),
+                // Structured errors such as limit violations pass through as is
+                None => e,
+            })
+        })
+        .collect()
+}
+
+/// Encode an image into the given output format
+pub(crate) fn encode(img: &DynamicImage, format: image::ImageOutputFormat) -> Result<Vec<u8>, JsValue> {
+    check_dimensions(img.width(), img.height())?;
+    let mut output = Vec::new();
+    img.write_to(&mut Cursor::new(&mut output), format)
+        .map_err(|e| JsValue::from_str(&format!("Failed to encode image: {}", e)))?;
+
+    check_output_size(output.len())?;
+    Ok(output)
+}
+
+/// Convert `img` back to `color` after an operation that had to work in
+/// another pixel layout, keeping the caller's channels and bit depth
+pub(crate) fn into_color_type(img: DynamicImage, color: image::ColorType) -> DynamicImage {
+    use image::ColorType;
+
+    match color {
+        ColorType::L8 => DynamicImage::ImageLuma8(img.into_luma8()),
+        ColorType::La8 => DynamicImage::ImageLumaA8(img.into_luma_alpha8()),
+        ColorType::Rgb8 => DynamicImage::ImageRgb8(img.into_rgb8()),
+        ColorType::Rgba8 => DynamicImage::ImageRgba8(img.into_rgba8()),
+        ColorType::L16 => DynamicImage::ImageLuma16(img.into_luma16()),
+        ColorType::La16 => DynamicImage::ImageLumaA16(img.into_luma_alpha16()),
+        ColorType::Rgb16 => DynamicImage::ImageRgb16(img.into_rgb16()),
+        ColorType::Rgba16 => DynamicImage::ImageRgba16(img.into_rgba16()),
+        ColorType::Rgb32F => DynamicImage::ImageRgb32F(img.into_rgb32f()),
+        ColorType::Rgba32F => DynamicImage::ImageRgba32F(img.into_rgba32f()),
+        _ => img,
+    }
+}
+
+/// Build an image from a raw RGBA buffer
+pub(crate) fn from_rgba(rgba: Vec<u8>, width: u32, height: u32) -> Result<DynamicImage, JsValue> {
+    check_dimensions(width, height)?;
+    ImageBuffer::<Rgba<u8>, Vec<u8>>::from_raw(width, height, rgba)
+        .map(DynamicImage::ImageRgba8)
+        .ok_or_else(|| JsValue::from_str("RGBA buffer length does not match width * height * 4"))
+}
+
+/// Copy canvas `ImageData` into an image
+pub(crate) fn from_image_data(image_data: &ImageData) -> Result<DynamicImage, JsValue> {
+    from_rgba(image_data.data().0, image_data.width(), image_data.height())
+}
+
+/// Convert an image into canvas `ImageData`
+pub(crate) fn to_image_data(img: &DynamicImage) -> Result<ImageData, JsValue> {
+    let rgba = img.to_rgba8();
+    let (width, height) = rgba.dimensions();
+    ImageData::new_with_u8_clamped_array_and_sh(Clamped(rgba.as_raw()), width, height)
+}
+
+/// Parse a `#rgb`, `#rrggbb` or `#rrggbbaa` hex color, or `transparent`
+pub(crate) fn parse_color(color: &str) -> Result<Rgba<u8>, JsValue> {
+    if color.trim().eq_ignore_ascii_case("transparent") {
+        return Ok(Rgba([0, 0, 0, 0]));
+    }
+
+    let hex = color.trim().trim_start_matches('#');
+    let invalid = || JsValue::from_str(&format!("Invalid color: {}", color));
+
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    let expanded: String = match hex.len() {
+        3 => hex.chars().flat_map(|c| [c, c]).collect(),
+        6 | 8 => hex.to_string(),
+        _ => return Err(invalid()),
+    };
+
+    let channel = |i: usize| u8::from_str_radix(&expanded[i..i + 2], 16).map_err(|_| invalid());
+    let alpha = if expanded.len() == 8 { channel(6)? } else { 255 };
+
+    Ok(Rgba([channel(0)?, channel(2)?, channel(4)?, alpha]))
+}
+
+/// Encode as AVIF with explicit quality and speed settings
+#[cfg(feature = "avif")]
+pub(crate) fn encode_avif(img: &DynamicImage, quality: u8, speed: u8) -> Result<Vec<u8>, JsValue> {
+    use image::ImageEncoder;
+    use image::codecs::avif::AvifEncoder;
+
+    let rgba = img.to_rgba8();
+    let mut output = Vec::new();
+    AvifEncoder::new_with_speed_quality(&mut output, speed.max(1).min(10), quality.max(1).min(100))
+        .write_image(rgba.as_raw(), rgba.width(), rgba.height(), image::ColorType::Rgba8)
+        .map_err(|e| JsValue::from_str(&format!("Failed to encode AVIF: {}", e)))?;
+
+    Ok(output)
+}
+
+/// Deserialize an optional JS options object, falling back to defaults
+pub(crate) fn parse_options<T: serde::de::DeserializeOwned + Default>(options: &JsValue) -> Result<T, JsValue> {
+    if options.is_undefined() || options.is_null() {
+        return Ok(T::default());
+    }
+
+    options
+        .into_serde()
+        .map_err(|e| JsValue::from_str(&format!("Invalid options: {}", e)))
+}
+
+/// Map a format name to an encoder, using `quality` for lossy formats
+pub(crate) fn output_format(format: &str, quality: u8) -> Result<image::ImageOutputFormat, JsValue> {
+    match format.to_lowercase().as_str() {
+        "png" => Ok(image::ImageOutputFormat::Png),
+        "jpeg" | "jpg" => Ok(image::ImageOutputFormat::Jpeg(quality)),
+        "webp" => Ok(image::ImageOutputFormat::WebP),
+        "bmp" => Ok(image::ImageOutputFormat::Bmp),
+        #[cfg(feature = "avif")]
+        "avif" => Ok(image::ImageOutputFormat::Avif),
+        _ => Err(JsValue::from_str("Unsupported format")),
+    }
 }
\ No newline at end of file
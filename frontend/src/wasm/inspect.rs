@@ -0,0 +1,114 @@
+use std::io::Cursor;
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use image::{ColorType, ImageDecoder, ImageFormat, ImageResult};
+use image::codecs::{bmp::BmpDecoder, gif::GifDecoder, jpeg::JpegDecoder, png::PngDecoder, tiff::TiffDecoder, webp::WebPDecoder};
+use crate::icc::icc_info;
+use crate::image_processor::{ImageProcessor, decode};
+use crate::limits::check_dimensions;
+use crate::riff;
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Describe an image from its headers, without decoding the pixels
+    ///
+    /// Returns `{ format, width, height, colorType, bitDepth, channels,
+    /// hasAlpha, frameCount, decodedBytes, withinLimits, iccProfile }`.
+    /// `decodedBytes` estimates the memory for decoding every frame at its
+    /// native color type; `withinLimits` tells whether `decode` would accept
+    /// the dimensions. `iccProfile` matches `get_icc_info`. Formats without
+    /// a header reader here are decoded in full to answer.
+    #[wasm_bindgen]
+    pub fn inspect(&self, image_data: &[u8]) -> Result<JsValue, JsValue> {
+        let format = image::guess_format(image_data)
+            .map_err(|e| JsValue::from_str(&format!("Failed to detect format: {}", e)))?;
+
+        let ((width, height), color) = match header(image_data, format) {
+            Some(header) => header,
+            None => {
+                let img = decode(image_data)?;
+                ((img.width(), img.height()), img.color())
+            }
+        };
+
+        let channels = color.channel_count() as u64;
+        let frame_count = frame_count(self, image_data, format);
+        let decoded_bytes = width as u64 * height as u64 * color.bytes_per_pixel() as u64 * frame_count as u64;
+
+        let result = serde_json::json!({
+            "format": format!("{:?}", format).to_lowercase(),
+            "width": width,
+            "height": height,
+            "colorType": format!("{:?}", color).to_lowercase(),
+            "bitDepth": color.bits_per_pixel() as u64 / channels,
+            "channels": channels,
+            "hasAlpha": color.has_alpha(),
+            "frameCount": frame_count,
+            "decodedBytes": decoded_bytes,
+            "withinLimits": check_dimensions(width, height).is_ok(),
+            "iccProfile": icc_info(image_data)
+        });
+
+        JsValue::from_serde(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+}
+
+/// Dimensions and color type from the format's header
+fn header(image_data: &[u8], format: ImageFormat) -> Option<((u32, u32), ColorType)> {
+    fn read<'a, D: ImageDecoder<'a>>(decoder: ImageResult<D>) -> Option<((u32, u32), ColorType)> {
+        decoder.ok().map(|decoder| (decoder.dimensions(), decoder.color_type()))
+    }
+
+    let cursor = Cursor::new(image_data);
+    match format {
+        ImageFormat::Png => read(PngDecoder::new(cursor)),
+        ImageFormat::Jpeg => read(JpegDecoder::new(cursor)),
+        ImageFormat::Gif => read(GifDecoder::new(cursor)),
+        ImageFormat::WebP => read(WebPDecoder::new(cursor)),
+        ImageFormat::Tiff => read(TiffDecoder::new(cursor)),
+        ImageFormat::Bmp => read(BmpDecoder::new(cursor)),
+        _ => None,
+    }
+}
+
+/// Frames in an animation or pages in a TIFF, 1 for still images
+fn frame_count(processor: &ImageProcessor, image_data: &[u8], format: ImageFormat) -> u32 {
+    let count = match format {
+        ImageFormat::Gif => gif_frame_count(image_data),
+        ImageFormat::Png => apng_frame_count(image_data),
+        ImageFormat::WebP => webp_frame_count(image_data),
+        ImageFormat::Tiff => processor.get_tiff_page_count(image_data).ok(),
+        _ => None,
+    };
+    count.unwrap_or(1).max(1)
+}
+
+/// Walk the frame descriptors, skipping the LZW data
+fn gif_frame_count(image_data: &[u8]) -> Option<u32> {
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::Indexed);
+    let mut decoder = options.read_info(image_data).ok()?;
+
+    let mut count = 0;
+    while let Ok(Some(_)) = decoder.next_frame_info() {
+        count += 1;
+    }
+    Some(count)
+}
+
+/// Frame count from the `acTL` chunk; plain PNGs have none
+fn apng_frame_count(image_data: &[u8]) -> Option<u32> {
+    let reader = png::Decoder::new(image_data).read_info().ok()?;
+    reader.info().animation_control.map(|control| control.num_frames)
+}
+
+/// Count `ANMF` chunks of an animated WebP
+fn webp_frame_count(image_data: &[u8]) -> Option<u32> {
+    let count = riff::chunks(image_data)
+        .map_while(Result::ok)
+        .filter(|chunk| chunk.fourcc == b"ANMF")
+        .count() as u32;
+
+    (count > 0).then_some(count)
+}
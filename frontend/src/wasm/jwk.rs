@@ -0,0 +1,231 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use serde::Deserialize;
+use base64::{Engine as _, engine::general_purpose};
+use sha2::{Digest, Sha256};
+use crate::crypto::CryptoModule;
+
+/// The JWK members this module reads; others such as `alg` or `kid` are
+/// ignored
+#[derive(Deserialize)]
+struct Jwk {
+    kty: String,
+    crv: Option<String>,
+    x: Option<String>,
+    y: Option<String>,
+    d: Option<String>,
+    k: Option<String>,
+}
+
+#[wasm_bindgen]
+impl CryptoModule {
+    /// Convert a raw base64 key from this module into a JWK object
+    ///
+    /// `key_type` is `ed25519` (OKP), `p256` (EC) or `oct` (symmetric).
+    /// With `is_secret`, `key_base64` is a secret key and the JWK includes
+    /// `d` (or `k`) alongside the public members derived from it;
+    /// otherwise it is a public key. The result can be passed to
+    /// WebCrypto's `importKey("jwk", ...)`.
+    #[wasm_bindgen]
+    pub fn key_to_jwk(&self, key_type: &str, key_base64: &str, is_secret: bool) -> Result<JsValue, JsValue> {
+        let key = general_purpose::STANDARD
+            .decode(key_base64)
+            .map_err(|e| JsValue::from_str(&format!("Invalid key: {}", e)))?;
+        let url = |bytes: &[u8]| general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+
+        let jwk = match key_type.to_lowercase().as_str() {
+            "ed25519" if is_secret => {
+                let secret = ed25519_dalek::SigningKey::try_from(key.as_slice())
+                    .map_err(|e| JsValue::from_str(&format!("Invalid secret key format: {}", e)))?;
+                let public = secret.verifying_key();
+                serde_json::json!({ "kty": "OKP", "crv": "Ed25519", "x": url(public.as_bytes()), "d": url(&key) })
+            }
+            "ed25519" => {
+                let public = ed25519_dalek::VerifyingKey::try_from(key.as_slice())
+                    .map_err(|e| JsValue::from_str(&format!("Invalid public key format: {}", e)))?;
+                serde_json::json!({ "kty": "OKP", "crv": "Ed25519", "x": url(public.as_bytes()) })
+            }
+            "p256" => {
+                let public = if is_secret {
+                    p256::ecdsa::SigningKey::from_slice(&key)
+                        .map_err(|e| JsValue::from_str(&format!("Invalid secret key format: {}", e)))?
+                        .verifying_key()
+                        .to_encoded_point(false)
+                } else {
+                    p256::ecdsa::VerifyingKey::from_sec1_bytes(&key)
+                        .map_err(|e| JsValue::from_str(&format!("Invalid public key format: {}", e)))?
+                        .to_encoded_point(false)
+                };
+                // Uncompressed SEC1 is 0x04 followed by x and y
+                let point = public.as_bytes();
+                let mut jwk = serde_json::json!({ "kty": "EC", "crv": "P-256", "x": url(&point[1..33]), "y": url(&point[33..65]) });
+                if is_secret {
+                    jwk["d"] = url(&key).into();
+                }
+                jwk
+            }
+            "oct" if is_secret => serde_json::json!({ "kty": "oct", "k": url(&key) }),
+            "oct" => return Err(JsValue::from_str("Symmetric keys are always secret")),
+            other => return Err(JsValue::from_str(&format!("Unknown key type: {} (expected ed25519, p256 or oct)", other))),
+        };
+
+        JsValue::from_serde(&jwk)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Convert a JWK (object or JSON string) into this module's raw keys
+    ///
+    /// Supports OKP Ed25519, EC P-256 and oct keys. Returns
+    /// `{ keyType, publicKey, secretKey }` in base64 with `keyType` as in
+    /// `key_to_jwk`; `secretKey` is `null` for public JWKs and `publicKey`
+    /// for oct keys. P-256 public keys come out as uncompressed SEC1.
+    #[wasm_bindgen]
+    pub fn jwk_to_key(&self, jwk: JsValue) -> Result<JsValue, JsValue> {
+        let jwk = parse_jwk(&jwk)?;
+        let base64 = |bytes: &[u8]| general_purpose::STANDARD.encode(bytes);
+
+        let (key_type, public_key, secret_key) = match (jwk.kty.as_str(), jwk.crv.as_deref()) {
+            ("OKP", Some("Ed25519")) => {
+                let x = member(&jwk.x, "x", 32)?;
+                let secret = jwk.d.as_ref().map(|_| member(&jwk.d, "d", 32)).transpose()?;
+                if let Some(secret) = &secret {
+                    let secret_key = ed25519_dalek::SigningKey::try_from(secret.as_slice())
+                        .map_err(|e| JsValue::from_str(&format!("Invalid JWK: {}", e)))?;
+                    if secret_key.verifying_key().as_bytes()[..] != x[..] {
+                        return Err(JsValue::from_str("Invalid JWK: x does not match d"));
+                    }
+                }
+                ("ed25519", Some(base64(&x)), secret.map(|d| base64(&d)))
+            }
+            ("EC", Some("P-256")) => {
+                let mut point = vec![0x04];
+                point.extend(member(&jwk.x, "x", 32)?);
+                point.extend(member(&jwk.y, "y", 32)?);
+                let public = p256::ecdsa::VerifyingKey::from_sec1_bytes(&point)
+                    .map_err(|e| JsValue::from_str(&format!("Invalid JWK: {}", e)))?;
+
+                let secret = jwk.d.as_ref().map(|_| member(&jwk.d, "d", 32)).transpose()?;
+                if let Some(secret) = &secret {
+                    let signing_key = p256::ecdsa::SigningKey::from_slice(secret)
+                        .map_err(|e| JsValue::from_str(&format!("Invalid JWK: {}", e)))?;
+                    if *signing_key.verifying_key() != public {
+                        return Err(JsValue::from_str("Invalid JWK: x and y do not match d"));
+                    }
+                }
+                ("p256", Some(base64(&point)), secret.map(|d| base64(&d)))
+            }
+            ("oct", _) => {
+                let k = jwk.k.as_deref().ok_or_else(|| JsValue::from_str("Invalid JWK: missing k"))?;
+                let secret = general_purpose::URL_SAFE_NO_PAD
+                    .decode(k)
+                    .map_err(|e| JsValue::from_str(&format!("Invalid JWK k: {}", e)))?;
+                ("oct", None, Some(base64(&secret)))
+            }
+            (kty, crv) => {
+                return Err(JsValue::from_str(&format!(
+                    "Unsupported JWK: kty {} with crv {}",
+                    kty,
+                    crv.unwrap_or("none")
+                )))
+            }
+        };
+
+        let result = serde_json::json!({
+            "keyType": key_type,
+            "publicKey": public_key,
+            "secretKey": secret_key
+        });
+
+        JsValue::from_serde(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// RFC 7638 thumbprint of a JWK, usable as a stable `kid`
+    ///
+    /// Only the required public members are hashed, so a secret JWK and its
+    /// public half share a thumbprint. Returns base64url SHA-256 without
+    /// padding.
+    #[wasm_bindgen]
+    pub fn jwk_thumbprint(&self, jwk: JsValue) -> Result<String, JsValue> {
+        thumbprint(&parse_jwk(&jwk)?)
+    }
+}
+
+/// RFC 7638 thumbprint of a parsed JWK, see `jwk_thumbprint`
+fn thumbprint(jwk: &Jwk) -> Result<String, JsValue> {
+    let required = |value: &Option<String>, name: &str| {
+        value
+            .as_deref()
+            .map(|v| serde_json::Value::from(v).to_string())
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid JWK: missing {}", name)))
+    };
+
+    // Members in lexicographic order with no whitespace
+    let canonical = match jwk.kty.as_str() {
+        "OKP" => format!(r#"{{"crv":{},"kty":"OKP","x":{}}}"#, required(&jwk.crv, "crv")?, required(&jwk.x, "x")?),
+        "EC" => format!(
+            r#"{{"crv":{},"kty":"EC","x":{},"y":{}}}"#,
+            required(&jwk.crv, "crv")?,
+            required(&jwk.x, "x")?,
+            required(&jwk.y, "y")?
+        ),
+        "oct" => format!(r#"{{"k":{},"kty":"oct"}}"#, required(&jwk.k, "k")?),
+        other => return Err(JsValue::from_str(&format!("Unsupported JWK kty: {}", other))),
+    };
+
+    Ok(general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes())))
+}
+
+fn parse_jwk(jwk: &JsValue) -> Result<Jwk, JsValue> {
+    let parsed = match jwk.as_string() {
+        Some(text) => serde_json::from_str(&text).map_err(|e| e.to_string()),
+        None => jwk.into_serde().map_err(|e| e.to_string()),
+    };
+    parsed.map_err(|e| JsValue::from_str(&format!("Invalid JWK: {}", e)))
+}
+
+/// Decode a base64url JWK member of a fixed length
+fn member(value: &Option<String>, name: &str, length: usize) -> Result<Vec<u8>, JsValue> {
+    let value = value
+        .as_deref()
+        .ok_or_else(|| JsValue::from_str(&format!("Invalid JWK: missing {}", name)))?;
+    let bytes = general_purpose::URL_SAFE_NO_PAD
+        .decode(value)
+        .map_err(|e| JsValue::from_str(&format!("Invalid JWK {}: {}", name, e)))?;
+
+    if bytes.len() != length {
+        return Err(JsValue::from_str(&format!("Invalid JWK {}: expected {} bytes", name, length)));
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jwk(json: &str) -> Jwk {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn rfc8037_ed25519_thumbprint() {
+        // RFC 8037 appendix A.3
+        let public = jwk(r#"{"kty":"OKP","crv":"Ed25519","x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"}"#);
+        assert_eq!(thumbprint(&public).ok().unwrap(), "kPrK_qmxVWaYVA9wwBF6Iuo3vVzz7TxHCTwXBygrS4k");
+    }
+
+    #[test]
+    fn thumbprint_ignores_secret_and_optional_members() {
+        let secret = jwk(
+            r#"{"kid":"a","d":"nWGxne_9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A","x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo","crv":"Ed25519","kty":"OKP"}"#,
+        );
+        assert_eq!(thumbprint(&secret).ok().unwrap(), "kPrK_qmxVWaYVA9wwBF6Iuo3vVzz7TxHCTwXBygrS4k");
+    }
+
+    #[test]
+    fn oct_thumbprint_hashes_canonical_members() {
+        let key = jwk(r#"{"kty":"oct","k":"AAEC"}"#);
+        let expected = general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(br#"{"k":"AAEC","kty":"oct"}"#));
+        assert_eq!(thumbprint(&key).ok().unwrap(), expected);
+    }
+}
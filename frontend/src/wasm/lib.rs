@@ -1,6 +1,62 @@
 // Re-export modules
+pub mod adjustments;
+pub mod animation;
+pub mod ascii;
+pub mod barcode;
+pub mod buffer;
+pub mod cache;
+pub mod camera_raw;
+pub mod cancel;
+pub mod channels;
+pub mod color;
+pub mod compositing;
 pub mod crypto;
+pub mod crypto_stream;
+pub mod cube_lut;
+pub mod effects;
+pub mod encoding;
+pub mod ethereum;
+pub mod faces;
+pub mod filters;
+pub mod hd_keys;
+// libheif is a C library with no wasm32-unknown-unknown build, so HEIC
+// decoding is only available to native builds (`cargo build --features heic`
+// with libheif installed); the browser bundle rejects HEIC input
+#[cfg(feature = "heic")]
+pub mod heic;
+#[cfg(all(feature = "heic", target_arch = "wasm32"))]
+compile_error!("the `heic` feature links libheif and cannot be built for wasm32");
+#[cfg(all(feature = "libwebp", target_arch = "wasm32"))]
+compile_error!("the `libwebp` feature links the libwebp C library and cannot be built for wasm32");
+pub mod icc;
+pub mod icons;
+pub mod identicon;
 pub mod image_processor;
+pub mod inspect;
+pub mod jwk;
+pub mod limits;
+pub mod matting;
+pub mod metadata;
+pub mod mnemonic;
+pub mod multipage;
+pub mod p256_keys;
+pub mod panorama;
+pub mod parallel;
+pub mod pipeline;
+pub mod placeholder;
+pub mod qr;
+pub mod quantize;
+pub mod redeye;
+pub mod riff;
+pub mod rsa_keys;
+pub mod secp256k1_keys;
+pub mod similarity;
+pub mod streaming;
+pub mod text;
+pub mod thumbhash;
+pub mod tiles;
+pub mod transform;
+pub mod upscale;
 
 use wasm_bindgen::prelude::*;
 
@@ -0,0 +1,264 @@
+use std::cell::Cell;
+use std::io::Cursor;
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use js_sys::{Error, Reflect};
+use serde::{Deserialize, Serialize};
+use crate::image_processor::{ImageProcessor, parse_options};
+
+/// Resource limits applied to every decode and encode
+///
+/// Images come straight from users, so a tiny file declaring a huge canvas
+/// must be rejected from its header rather than by running out of memory.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase", default)]
+struct Limits {
+    max_width: u32,
+    max_height: u32,
+    /// Width times height, which bounds the decoded buffer size
+    max_pixels: u64,
+    /// Pixels summed over every frame of an animation or page of a TIFF
+    max_frame_pixels: u64,
+    /// Size of an encoded result in bytes
+    max_output_bytes: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_width: 32_768,
+            max_height: 32_768,
+            // About 400 MB as 8-bit RGBA
+            max_pixels: 100_000_000,
+            max_frame_pixels: 100_000_000,
+            max_output_bytes: 256 * 1024 * 1024,
+        }
+    }
+}
+
+thread_local! {
+    static LIMITS: Cell<Limits> = Cell::new(Limits::default());
+}
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Set resource limits from `{ maxWidth, maxHeight, maxPixels, maxFramePixels, maxOutputBytes }`
+    ///
+    /// Omitted fields fall back to the defaults. Limits apply to every
+    /// processor in this module instance. Violations throw an `Error` with
+    /// `code: "LIMIT_EXCEEDED"` plus `limit`, `value` and `max` properties.
+    #[wasm_bindgen]
+    pub fn set_limits(&self, options: JsValue) -> Result<(), JsValue> {
+        let limits: Limits = parse_options(&options)?;
+        LIMITS.with(|current| current.set(limits));
+        Ok(())
+    }
+
+    /// Current resource limits
+    #[wasm_bindgen]
+    pub fn get_limits(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&limits())
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+}
+
+fn limits() -> Limits {
+    LIMITS.with(|current| current.get())
+}
+
+/// Shrink the frame budget for the current test thread
+#[cfg(test)]
+pub(crate) fn set_max_frame_pixels(max: u64) {
+    LIMITS.with(|current| current.set(Limits { max_frame_pixels: max, ..current.get() }));
+}
+
+/// A configured limit was hit; converts into the JS `Error` described in
+/// `set_limits`, so `?` works in functions returning `JsValue` errors
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct LimitExceeded {
+    pub limit: &'static str,
+    pub value: u64,
+    pub max: u64,
+}
+
+impl From<LimitExceeded> for JsValue {
+    fn from(e: LimitExceeded) -> Self {
+        limit_error(e.limit, e.value, e.max)
+    }
+}
+
+/// Failure of a decoder that runs under the limits, kept apart from
+/// `JsValue` until the wasm boundary so the checks can be tested natively
+#[derive(Debug)]
+pub(crate) enum DecodeError {
+    Limit(LimitExceeded),
+    Invalid(String),
+}
+
+impl From<LimitExceeded> for DecodeError {
+    fn from(e: LimitExceeded) -> Self {
+        DecodeError::Limit(e)
+    }
+}
+
+impl From<DecodeError> for JsValue {
+    fn from(e: DecodeError) -> Self {
+        match e {
+            DecodeError::Limit(e) => e.into(),
+            DecodeError::Invalid(message) => JsValue::from_str(&message),
+        }
+    }
+}
+
+/// Reject dimensions beyond the configured limits
+pub(crate) fn check_dimensions(width: u32, height: u32) -> Result<(), LimitExceeded> {
+    let limits = limits();
+    if width > limits.max_width {
+        return Err(LimitExceeded { limit: "maxWidth", value: width as u64, max: limits.max_width as u64 });
+    }
+    if height > limits.max_height {
+        return Err(LimitExceeded { limit: "maxHeight", value: height as u64, max: limits.max_height as u64 });
+    }
+    let pixels = width as u64 * height as u64;
+    if pixels > limits.max_pixels {
+        return Err(LimitExceeded { limit: "maxPixels", value: pixels, max: limits.max_pixels });
+    }
+    Ok(())
+}
+
+/// Running pixel total for decoders that produce many frames or pages
+///
+/// Each frame is checked like a single image, and the sum is held to
+/// `maxFramePixels` so a long animation of small frames can't exhaust
+/// memory either.
+pub(crate) struct FrameBudget {
+    used: u64,
+    max: u64,
+}
+
+impl FrameBudget {
+    pub(crate) fn new() -> Self {
+        FrameBudget { used: 0, max: limits().max_frame_pixels }
+    }
+
+    /// Account for one more frame before keeping it
+    pub(crate) fn spend(&mut self, width: u32, height: u32) -> Result<(), LimitExceeded> {
+        check_dimensions(width, height)?;
+        self.used = self.used.saturating_add(width as u64 * height as u64);
+        if self.used > self.max {
+            return Err(LimitExceeded { limit: "maxFramePixels", value: self.used, max: self.max });
+        }
+        Ok(())
+    }
+}
+
+/// Check the size an encoded image declares in its header, without decoding it
+///
+/// Formats the header reader doesn't understand are let through; the
+/// decoder's allocation limit still applies to them.
+pub(crate) fn check_header(image_data: &[u8]) -> Result<(), LimitExceeded> {
+    let dimensions = image::io::Reader::new(Cursor::new(image_data))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok());
+
+    match dimensions {
+        Some((width, height)) => check_dimensions(width, height),
+        None => Ok(()),
+    }
+}
+
+/// Decoder limits matching the configured ones, as a backstop for formats
+/// whose header lies about or omits the size
+pub(crate) fn decoder_limits() -> image::io::Limits {
+    let limits = limits();
+    let mut decoder = image::io::Limits::default();
+    decoder.max_image_width = Some(limits.max_width);
+    decoder.max_image_height = Some(limits.max_height);
+    // Room for a 16-bit RGBA buffer plus a conversion copy
+    decoder.max_alloc = Some(limits.max_pixels.saturating_mul(16));
+    decoder
+}
+
+/// Decoder limits for the `tiff` crate, which multi-page reads use directly
+pub(crate) fn tiff_limits() -> tiff::decoder::Limits {
+    // One 16-bit RGBA page
+    let page_bytes = usize::try_from(limits().max_pixels.saturating_mul(8)).unwrap_or(usize::MAX);
+    let mut decoder = tiff::decoder::Limits::default();
+    decoder.decoding_buffer_size = page_bytes;
+    decoder.intermediate_buffer_size = decoder.intermediate_buffer_size.min(page_bytes);
+    decoder
+}
+
+/// Decoder limits for the `png` crate, used directly by the streaming thumbnailer
+pub(crate) fn png_limits() -> png::Limits {
+    let bytes = limits().max_pixels.saturating_mul(16);
+    png::Limits { bytes: usize::try_from(bytes).unwrap_or(usize::MAX) }
+}
+
+pub(crate) fn check_output_size(bytes: usize) -> Result<(), LimitExceeded> {
+    let max = limits().max_output_bytes;
+    if bytes as u64 > max {
+        return Err(LimitExceeded { limit: "maxOutputBytes", value: bytes as u64, max });
+    }
+    Ok(())
+}
+
+fn limit_error(limit: &str, value: u64, max: u64) -> JsValue {
+    let error = Error::new(&format!("Image exceeds {} ({} > {})", limit, value, max));
+    let _ = Reflect::set(&error, &"code".into(), &"LIMIT_EXCEEDED".into());
+    let _ = Reflect::set(&error, &"limit".into(), &limit.into());
+    let _ = Reflect::set(&error, &"value".into(), &(value as f64).into());
+    let _ = Reflect::set(&error, &"max".into(), &(max as f64).into());
+    error.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_dimensions_reports_the_limit_hit() {
+        assert_eq!(check_dimensions(32_768, 1), Ok(()));
+        assert_eq!(
+            check_dimensions(32_769, 1),
+            Err(LimitExceeded { limit: "maxWidth", value: 32_769, max: 32_768 })
+        );
+        assert_eq!(
+            check_dimensions(20_000, 20_000),
+            Err(LimitExceeded { limit: "maxPixels", value: 400_000_000, max: 100_000_000 })
+        );
+    }
+
+    #[test]
+    fn frame_budget_sums_frames() {
+        set_max_frame_pixels(250);
+        let mut budget = FrameBudget::new();
+
+        assert_eq!(budget.spend(10, 10), Ok(()));
+        assert_eq!(budget.spend(10, 10), Ok(()));
+        assert_eq!(
+            budget.spend(10, 10),
+            Err(LimitExceeded { limit: "maxFramePixels", value: 300, max: 250 })
+        );
+    }
+
+    #[test]
+    fn frame_budget_checks_each_frame() {
+        let mut budget = FrameBudget::new();
+        assert!(matches!(budget.spend(1, 40_000), Err(LimitExceeded { limit: "maxHeight", .. })));
+    }
+
+    #[test]
+    fn check_header_reads_declared_size() {
+        // A 40000x1 logical screen holding a single 1x1 frame
+        let mut gif = Vec::new();
+        {
+            let mut encoder = gif::Encoder::new(&mut gif, 40_000, 1, &[0, 0, 0, 255, 255, 255]).unwrap();
+            let frame = gif::Frame { width: 1, height: 1, buffer: vec![1].into(), ..gif::Frame::default() };
+            encoder.write_frame(&frame).unwrap();
+        }
+
+        assert!(matches!(check_header(&gif), Err(LimitExceeded { limit: "maxWidth", .. })));
+    }
+}
@@ -0,0 +1,146 @@
+use wasm_bindgen::prelude::*;
+use image::{DynamicImage, Rgba};
+use crate::adjustments::luma;
+use crate::filters::load_mask;
+use crate::image_processor::{ImageProcessor, decode, encode, parse_color};
+
+/// Guided filter window radius bounds, in pixels
+const MIN_GUIDE_RADIUS: u32 = 4;
+const MAX_GUIDE_RADIUS: u32 = 32;
+
+/// Guided filter regularization; small enough to follow hair-level detail
+const GUIDE_EPSILON: f32 = 1e-4;
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Key out a background color (green screen) to transparency
+    ///
+    /// Pixels are compared to `key_color` by chroma only, so shadows on the
+    /// screen key out too. Distances are in normalized CbCr units: pixels
+    /// within `tolerance` become transparent and alpha ramps back up over
+    /// the next `feather`. Colored spill from the screen is neutralized on
+    /// the pixels that remain.
+    #[wasm_bindgen]
+    pub fn chroma_key(&self, image_data: &[u8], key_color: &str, tolerance: f32, feather: f32) -> Result<Vec<u8>, JsValue> {
+        let key = parse_color(key_color)?;
+        let mut rgba = decode(image_data)?.to_rgba8();
+
+        let tolerance = tolerance.max(0.0);
+        let feather = feather.max(0.0);
+        let (key_cb, key_cr) = chroma(key);
+
+        // The channel the screen is made of, which spill shows up in
+        let dominant = (0..3).max_by_key(|&c| key[c]).unwrap_or(1);
+        let spill_reach = 2.0 * (tolerance + feather);
+
+        for px in rgba.pixels_mut() {
+            let (cb, cr) = chroma(*px);
+            let distance = ((cb - key_cb).powi(2) + (cr - key_cr).powi(2)).sqrt();
+
+            let alpha = if distance <= tolerance {
+                0.0
+            } else if distance < tolerance + feather {
+                (distance - tolerance) / feather
+            } else {
+                1.0
+            };
+            px[3] = (px[3] as f32 * alpha).round() as u8;
+
+            if px[3] > 0 && distance < spill_reach {
+                let others = (0..3).filter(|&c| c != dominant).map(|c| px[c]).max().unwrap_or(0);
+                px[dominant] = px[dominant].min(others);
+            }
+        }
+
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+
+    /// Cut out the subject using a coarse segmentation mask
+    ///
+    /// The mask (white = subject, any size) is refined against the image
+    /// with a guided filter so the alpha follows real edges such as hair,
+    /// then applied as transparency.
+    #[wasm_bindgen]
+    pub fn remove_background(&self, image_data: &[u8], mask_data: &[u8]) -> Result<Vec<u8>, JsValue> {
+        let mut rgba = decode(image_data)?.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        let mask = load_mask(mask_data, width, height)?;
+
+        let guide: Vec<f32> = rgba.pixels().map(|px| luma(px[0], px[1], px[2]) as f32 / 255.0).collect();
+        let coarse: Vec<f32> = mask.pixels().map(|m| m[0] as f32 / 255.0).collect();
+
+        // ML masks are usually upsampled from a few hundred pixels, so their
+        // edges can be off by around 1% of the image size
+        let radius = (width.min(height) / 100).max(MIN_GUIDE_RADIUS).min(MAX_GUIDE_RADIUS) as usize;
+        let refined = guided_filter(&guide, &coarse, width as usize, height as usize, radius, GUIDE_EPSILON);
+
+        for (px, alpha) in rgba.pixels_mut().zip(refined) {
+            // Snap near-solid values so the cutout has no faint haze
+            let alpha = if alpha < 0.02 { 0.0 } else if alpha > 0.98 { 1.0 } else { alpha };
+            px[3] = (px[3] as f32 * alpha).round() as u8;
+        }
+
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+}
+
+/// Edge-preserving filter of `input` steered by `guide` (He et al., 2010)
+///
+/// Both buffers hold values in 0..=1. `epsilon` is the regularization: the
+/// smaller it is, the more closely the output follows edges in the guide.
+fn guided_filter(guide: &[f32], input: &[f32], width: usize, height: usize, radius: usize, epsilon: f32) -> Vec<f32> {
+    let mean_i = box_mean(guide, width, height, radius);
+    let mean_p = box_mean(input, width, height, radius);
+    let corr_ii = box_mean(&guide.iter().map(|v| v * v).collect::<Vec<_>>(), width, height, radius);
+    let corr_ip = box_mean(&guide.iter().zip(input).map(|(i, p)| i * p).collect::<Vec<_>>(), width, height, radius);
+
+    let mut a = vec![0f32; guide.len()];
+    let mut b = vec![0f32; guide.len()];
+    for k in 0..guide.len() {
+        let variance = corr_ii[k] - mean_i[k] * mean_i[k];
+        let covariance = corr_ip[k] - mean_i[k] * mean_p[k];
+        a[k] = covariance / (variance + epsilon);
+        b[k] = mean_p[k] - a[k] * mean_i[k];
+    }
+
+    let mean_a = box_mean(&a, width, height, radius);
+    let mean_b = box_mean(&b, width, height, radius);
+
+    (0..guide.len())
+        .map(|k| (mean_a[k] * guide[k] + mean_b[k]).max(0.0).min(1.0))
+        .collect()
+}
+
+/// Mean over a (2r+1)^2 window, clipped at the borders, via a summed-area table
+fn box_mean(values: &[f32], width: usize, height: usize, radius: usize) -> Vec<f32> {
+    let stride = width + 1;
+    let mut integral = vec![0f64; stride * (height + 1)];
+    for y in 0..height {
+        let mut row = 0.0;
+        for x in 0..width {
+            row += values[y * width + x] as f64;
+            integral[(y + 1) * stride + x + 1] = integral[y * stride + x + 1] + row;
+        }
+    }
+
+    let mut out = vec![0f32; values.len()];
+    for y in 0..height {
+        let (y0, y1) = (y.saturating_sub(radius), (y + radius + 1).min(height));
+        for x in 0..width {
+            let (x0, x1) = (x.saturating_sub(radius), (x + radius + 1).min(width));
+            let sum = integral[y1 * stride + x1] - integral[y0 * stride + x1] - integral[y1 * stride + x0] + integral[y0 * stride + x0];
+            out[y * width + x] = (sum / ((x1 - x0) * (y1 - y0)) as f64) as f32;
+        }
+    }
+
+    out
+}
+
+/// BT.601 chroma of a pixel, each component in -0.5..=0.5
+fn chroma(px: Rgba<u8>) -> (f32, f32) {
+    let (r, g, b) = (px[0] as f32 / 255.0, px[1] as f32 / 255.0, px[2] as f32 / 255.0);
+    (
+        -0.168_736 * r - 0.331_264 * g + 0.5 * b,
+        0.5 * r - 0.418_688 * g - 0.081_312 * b,
+    )
+}
@@ -0,0 +1,301 @@
+use std::io::Cursor;
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use exif::{Exif, In, Tag, Value};
+use crate::image_processor::ImageProcessor;
+use crate::riff;
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Read EXIF/XMP metadata (camera, orientation, GPS, timestamps)
+    #[wasm_bindgen]
+    pub fn read_metadata(&self, image_data: &[u8]) -> Result<JsValue, JsValue> {
+        let format = image::guess_format(image_data)
+            .map_err(|e| JsValue::from_str(&format!("Failed to detect format: {}", e)))?;
+
+        let exif = match exif::Reader::new().read_from_container(&mut Cursor::new(image_data)) {
+            Ok(exif) => Some(exif),
+            Err(exif::Error::NotFound(_)) => None,
+            Err(e) => return Err(JsValue::from_str(&format!("Failed to parse EXIF: {}", e))),
+        };
+
+        let result = match exif {
+            Some(ref exif) => serde_json::json!({
+                "format": format!("{:?}", format).to_lowercase(),
+                "camera": {
+                    "make": ascii_field(exif, Tag::Make),
+                    "model": ascii_field(exif, Tag::Model),
+                    "lens": ascii_field(exif, Tag::LensModel),
+                    "software": ascii_field(exif, Tag::Software),
+                },
+                "orientation": uint_field(exif, Tag::Orientation),
+                "exposure": {
+                    "exposureTime": rational_field(exif, Tag::ExposureTime),
+                    "fNumber": rational_field(exif, Tag::FNumber),
+                    "iso": uint_field(exif, Tag::PhotographicSensitivity),
+                    "focalLength": rational_field(exif, Tag::FocalLength),
+                },
+                "gps": gps(exif),
+                "timestamps": {
+                    "original": ascii_field(exif, Tag::DateTimeOriginal),
+                    "digitized": ascii_field(exif, Tag::DateTimeDigitized),
+                    "modified": ascii_field(exif, Tag::DateTime),
+                },
+                "xmp": find_xmp(image_data),
+            }),
+            None => serde_json::json!({
+                "format": format!("{:?}", format).to_lowercase(),
+                "camera": null,
+                "orientation": null,
+                "exposure": null,
+                "gps": null,
+                "timestamps": null,
+                "xmp": find_xmp(image_data),
+            }),
+        };
+
+        JsValue::from_serde(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Remove EXIF, GPS, XMP and ICC data without re-encoding the pixels
+    #[wasm_bindgen]
+    pub fn strip_metadata(&self, image_data: &[u8]) -> Result<Vec<u8>, JsValue> {
+        match image::guess_format(image_data) {
+            Ok(image::ImageFormat::Jpeg) => strip_jpeg(image_data),
+            Ok(image::ImageFormat::Png) => strip_png(image_data),
+            Ok(image::ImageFormat::WebP) => strip_webp(image_data),
+            _ => Err("Metadata stripping supports JPEG, PNG and WebP only"),
+        }
+        .map_err(JsValue::from_str)
+    }
+}
+
+const TRUNCATED: &str = "Truncated image data";
+
+/// Copy JPEG segments, dropping APP1 (EXIF/XMP), APP2 (ICC), APP13 (IPTC) and comments
+fn strip_jpeg(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    let mut output = data[..2].to_vec();
+    let mut pos = 2;
+
+    while pos + 1 < data.len() {
+        if data[pos] != 0xFF {
+            return Err("Invalid JPEG segment marker");
+        }
+
+        let marker = data[pos + 1];
+
+        // Fill bytes may precede a marker
+        if marker == 0xFF {
+            pos += 1;
+            continue;
+        }
+
+        // Start of scan: the rest is entropy-coded image data
+        if marker == 0xDA {
+            output.extend_from_slice(&data[pos..]);
+            return Ok(output);
+        }
+
+        // Standalone markers carry no length
+        if marker == 0x01 || (0xD0..=0xD9).contains(&marker) {
+            output.extend_from_slice(&data[pos..pos + 2]);
+            pos += 2;
+            continue;
+        }
+
+        if pos + 4 > data.len() {
+            return Err(TRUNCATED);
+        }
+
+        let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let end = pos + 2 + length;
+        if end > data.len() {
+            return Err(TRUNCATED);
+        }
+
+        if !matches!(marker, 0xE1 | 0xE2 | 0xED | 0xFE) {
+            output.extend_from_slice(&data[pos..end]);
+        }
+
+        pos = end;
+    }
+
+    Ok(output)
+}
+
+/// Copy PNG chunks, dropping text, EXIF, ICC and timestamp chunks
+fn strip_png(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    let mut output = data[..8].to_vec();
+    let mut pos = 8;
+
+    while pos + 8 <= data.len() {
+        let length = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+        let chunk_type = &data[pos + 4..pos + 8];
+        // Length, type, payload and CRC; checked so a huge length can't wrap on wasm32
+        let end = match length.checked_add(12).and_then(|n| n.checked_add(pos)) {
+            Some(end) if end <= data.len() => end,
+            _ => return Err(TRUNCATED),
+        };
+
+        if !matches!(chunk_type, b"eXIf" | b"iCCP" | b"tEXt" | b"zTXt" | b"iTXt" | b"tIME") {
+            output.extend_from_slice(&data[pos..end]);
+        }
+
+        pos = end;
+    }
+
+    Ok(output)
+}
+
+/// Copy WebP RIFF chunks, dropping EXIF, XMP and ICC chunks and their VP8X flags
+fn strip_webp(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    if data.len() < 12 {
+        return Err(TRUNCATED);
+    }
+
+    let mut output = data[..12].to_vec();
+
+    for chunk in riff::chunks(data) {
+        let chunk = chunk.map_err(|_| TRUNCATED)?;
+        match chunk.fourcc {
+            b"EXIF" | b"XMP " | b"ICCP" => {}
+            // Flags, reserved bytes and the canvas size take 10 bytes
+            b"VP8X" if chunk.payload.len() < 10 => return Err("Invalid VP8X chunk"),
+            b"VP8X" => {
+                let start = output.len();
+                output.extend_from_slice(chunk.raw);
+                // Clear the ICC (0x20), EXIF (0x08) and XMP (0x04) flags
+                output[start + 8] &= !(0x20 | 0x08 | 0x04);
+            }
+            _ => output.extend_from_slice(chunk.raw),
+        }
+    }
+
+    let riff_size = (output.len() - 8) as u32;
+    output[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    Ok(output)
+}
+
+fn ascii_field(exif: &Exif, tag: Tag) -> Option<String> {
+    match exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(ref values) => values.first().map(|v| {
+            String::from_utf8_lossy(v).trim_end_matches('\0').trim().to_string()
+        }),
+        _ => None,
+    }
+}
+
+fn uint_field(exif: &Exif, tag: Tag) -> Option<u32> {
+    exif.get_field(tag, In::PRIMARY)?.value.get_uint(0)
+}
+
+fn rational_field(exif: &Exif, tag: Tag) -> Option<f64> {
+    match exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Rational(ref values) => values.first().map(|r| r.to_f64()),
+        _ => None,
+    }
+}
+
+/// Convert a degrees/minutes/seconds GPS field into signed decimal degrees
+fn gps_coordinate(exif: &Exif, tag: Tag, ref_tag: Tag) -> Option<f64> {
+    let dms = match exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Rational(ref values) if values.len() >= 3 => values.clone(),
+        _ => return None,
+    };
+
+    let degrees = dms[0].to_f64() + dms[1].to_f64() / 60.0 + dms[2].to_f64() / 3600.0;
+
+    match ascii_field(exif, ref_tag).as_deref() {
+        Some("S") | Some("W") => Some(-degrees),
+        _ => Some(degrees),
+    }
+}
+
+fn gps(exif: &Exif) -> serde_json::Value {
+    let latitude = gps_coordinate(exif, Tag::GPSLatitude, Tag::GPSLatitudeRef);
+    let longitude = gps_coordinate(exif, Tag::GPSLongitude, Tag::GPSLongitudeRef);
+
+    if latitude.is_none() || longitude.is_none() {
+        return serde_json::Value::Null;
+    }
+
+    // GPSAltitudeRef of 1 means the altitude is below sea level
+    let below_sea_level = uint_field(exif, Tag::GPSAltitudeRef) == Some(1);
+    let altitude = rational_field(exif, Tag::GPSAltitude)
+        .map(|a| if below_sea_level { -a } else { a });
+
+    serde_json::json!({
+        "latitude": latitude,
+        "longitude": longitude,
+        "altitude": altitude,
+    })
+}
+
+/// Locate the raw XMP packet, which is stored uncompressed in JPEG, PNG and WebP
+fn find_xmp(data: &[u8]) -> Option<String> {
+    let start = find_bytes(data, b"<x:xmpmeta")?;
+    let end = find_bytes(&data[start..], b"</x:xmpmeta>")? + start + b"</x:xmpmeta>".len();
+
+    Some(String::from_utf8_lossy(&data[start..end]).into_owned())
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn webp(chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut data = b"RIFF\0\0\0\0WEBP".to_vec();
+        for (fourcc, payload) in chunks {
+            data.extend_from_slice(*fourcc);
+            data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            data.extend_from_slice(payload);
+            if payload.len() % 2 == 1 {
+                data.push(0);
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn strip_webp_drops_metadata_and_flags() {
+        let vp8x = [0x20 | 0x10 | 0x08 | 0x04, 0, 0, 0, 9, 0, 0, 9, 0, 0];
+        let data = webp(&[(b"VP8X", &vp8x), (b"ICCP", b"icc"), (b"VP8L", b"pixels"), (b"EXIF", b"exif")]);
+
+        let stripped = strip_webp(&data).unwrap();
+
+        assert_eq!(stripped, {
+            let mut expected = webp(&[(b"VP8X", &[0x10, 0, 0, 0, 9, 0, 0, 9, 0, 0]), (b"VP8L", b"pixels")]);
+            let size = (expected.len() - 8) as u32;
+            expected[4..8].copy_from_slice(&size.to_le_bytes());
+            expected
+        });
+    }
+
+    #[test]
+    fn strip_webp_rejects_short_vp8x() {
+        assert_eq!(strip_webp(&webp(&[(b"VP8X", &[])])), Err("Invalid VP8X chunk"));
+        assert_eq!(strip_webp(&webp(&[(b"VP8X", &[0; 9])])), Err("Invalid VP8X chunk"));
+    }
+
+    #[test]
+    fn strip_webp_rejects_overflowing_length() {
+        let mut data = webp(&[(b"VP8L", b"pixels")]);
+        data.extend_from_slice(b"EXIF");
+        data.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(strip_webp(&data), Err(TRUNCATED));
+    }
+
+    #[test]
+    fn strip_png_rejects_overflowing_length() {
+        let mut data = b"\x89PNG\r\n\x1a\n".to_vec();
+        data.extend_from_slice(&u32::MAX.to_be_bytes());
+        data.extend_from_slice(b"tEXt");
+        assert_eq!(strip_png(&data), Err(TRUNCATED));
+    }
+}
@@ -0,0 +1,101 @@
+use wasm_bindgen::prelude::*;
+use base64::{Engine as _, engine::general_purpose};
+use bip39::{Language, Mnemonic};
+use crate::crypto::CryptoModule;
+
+#[wasm_bindgen]
+impl CryptoModule {
+    /// Generate a BIP-39 English recovery phrase
+    ///
+    /// `strength` is the entropy in bits: 128 (12 words, default), 160,
+    /// 192, 224 or 256 (24 words).
+    #[wasm_bindgen]
+    pub fn generate_mnemonic(strength: Option<u32>) -> Result<String, JsValue> {
+        let strength = strength.unwrap_or(128);
+        if !(128..=256).contains(&strength) || !strength.is_multiple_of(32) {
+            return Err(JsValue::from_str("Mnemonic strength must be 128, 160, 192, 224 or 256 bits"));
+        }
+
+        let mut entropy = vec![0u8; strength as usize / 8];
+        getrandom::getrandom(&mut entropy)
+            .map_err(|e| JsValue::from_str(&format!("Random generation failed: {}", e)))?;
+
+        Mnemonic::from_entropy_in(Language::English, &entropy)
+            .map(|mnemonic| mnemonic.to_string())
+            .map_err(|e| JsValue::from_str(&format!("Mnemonic generation failed: {}", e)))
+    }
+
+    /// Check that a phrase uses English BIP-39 words, has a valid length and
+    /// its checksum matches
+    #[wasm_bindgen]
+    pub fn validate_mnemonic(&self, phrase: &str) -> bool {
+        parse_mnemonic(phrase).is_ok()
+    }
+
+    /// Derive the 64-byte BIP-39 seed from a phrase and optional passphrase
+    ///
+    /// The phrase is validated first; case and extra whitespace are
+    /// ignored. Returns the seed in base64, ready for `derive_path`.
+    #[wasm_bindgen]
+    pub fn mnemonic_to_seed(&self, phrase: &str, passphrase: Option<String>) -> Result<String, JsValue> {
+        let mnemonic = parse_mnemonic(phrase)
+            .map_err(|e| JsValue::from_str(&format!("Invalid mnemonic: {}", e)))?;
+        let seed = mnemonic.to_seed(passphrase.as_deref().unwrap_or(""));
+        Ok(general_purpose::STANDARD.encode(seed))
+    }
+}
+
+/// Parse a phrase as typed by a user, ignoring case and spacing
+fn parse_mnemonic(phrase: &str) -> Result<Mnemonic, bip39::Error> {
+    let normalized = phrase.to_lowercase().split_whitespace().collect::<Vec<_>>().join(" ");
+    Mnemonic::parse_in(Language::English, &normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// BIP-39 reference vectors, whose seeds use the passphrase "TREZOR"
+    const VECTORS: [(&str, &str, &str); 2] = [
+        (
+            "00000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+        ),
+        (
+            "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+            "legal winner thank year wave sausage worth useful legal winner thank yellow",
+            "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607",
+        ),
+    ];
+
+    #[test]
+    fn bip39_reference_vectors() {
+        let crypto = CryptoModule {};
+        for (entropy, phrase, seed) in VECTORS {
+            let mnemonic = parse_mnemonic(phrase).unwrap();
+            assert_eq!(hex::encode(mnemonic.to_entropy()), entropy);
+
+            let seed_base64 = crypto.mnemonic_to_seed(phrase, Some("TREZOR".into())).ok().unwrap();
+            assert_eq!(hex::encode(general_purpose::STANDARD.decode(seed_base64).unwrap()), seed);
+        }
+    }
+
+    #[test]
+    fn phrase_case_and_spacing_are_ignored() {
+        let crypto = CryptoModule {};
+        let typed = "  Legal winner THANK year wave\tsausage worth useful legal winner thank yellow ";
+
+        assert!(crypto.validate_mnemonic(typed));
+        assert_eq!(crypto.mnemonic_to_seed(typed, None).ok(), crypto.mnemonic_to_seed(VECTORS[1].1, None).ok());
+    }
+
+    #[test]
+    fn bad_checksum_is_invalid() {
+        let crypto = CryptoModule {};
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon";
+
+        assert!(matches!(parse_mnemonic(phrase), Err(bip39::Error::InvalidChecksum)));
+        assert!(!crypto.validate_mnemonic(phrase));
+    }
+}
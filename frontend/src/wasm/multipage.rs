@@ -0,0 +1,247 @@
+use std::io::Cursor;
+use wasm_bindgen::prelude::*;
+use js_sys::{Array, Uint8Array};
+use image::{DynamicImage, GrayAlphaImage, GrayImage, ImageBuffer, Luma, Rgb, RgbImage, Rgba, RgbaImage};
+use tiff::ColorType;
+use tiff::decoder::{Decoder, DecodingResult};
+use crate::encoding::encode_as;
+use crate::image_processor::ImageProcessor;
+use crate::limits::{DecodeError, FrameBudget, tiff_limits};
+
+type TiffDecoder<'a> = Decoder<Cursor<&'a [u8]>>;
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Count the pages of a (multi-page) TIFF
+    #[wasm_bindgen]
+    pub fn get_tiff_page_count(&self, image_data: &[u8]) -> Result<u32, JsValue> {
+        let mut decoder = open_tiff(image_data)?;
+        let mut count = 1;
+
+        while decoder.more_images() {
+            decoder.next_image().map_err(tiff_error)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Extract one page of a TIFF, encoded in the requested format
+    #[wasm_bindgen]
+    pub fn extract_tiff_page(&self, image_data: &[u8], page: u32, format: &str) -> Result<Vec<u8>, JsValue> {
+        let mut decoder = open_tiff(image_data)?;
+
+        for _ in 0..page {
+            if !decoder.more_images() {
+                return Err(JsValue::from_str("Page index out of range"));
+            }
+            decoder.next_image().map_err(tiff_error)?;
+        }
+
+        let img = read_page(&mut decoder, &mut FrameBudget::new())?;
+        encode_as(&img, format, 85)
+    }
+
+    /// Convert every page of a TIFF, returning an array of encoded images
+    #[wasm_bindgen]
+    pub fn convert_tiff_pages(&self, image_data: &[u8], format: &str) -> Result<Array, JsValue> {
+        let mut decoder = open_tiff(image_data)?;
+        let pages = Array::new();
+        let mut budget = FrameBudget::new();
+
+        loop {
+            let img = read_page(&mut decoder, &mut budget)?;
+            let encoded = encode_as(&img, format, 85)?;
+            pages.push(&Uint8Array::from(encoded.as_slice()));
+
+            if !decoder.more_images() {
+                break;
+            }
+            decoder.next_image().map_err(tiff_error)?;
+        }
+
+        Ok(pages)
+    }
+}
+
+fn tiff_error(e: tiff::TiffError) -> DecodeError {
+    DecodeError::Invalid(format!("Failed to decode TIFF: {}", e))
+}
+
+fn open_tiff(image_data: &[u8]) -> Result<TiffDecoder<'_>, DecodeError> {
+    Ok(Decoder::new(Cursor::new(image_data)).map_err(tiff_error)?.with_limits(tiff_limits()))
+}
+
+/// Decode the decoder's current page into an image, charging it to `budget`
+fn read_page(decoder: &mut TiffDecoder<'_>, budget: &mut FrameBudget) -> Result<DynamicImage, DecodeError> {
+    let (width, height) = decoder.dimensions().map_err(tiff_error)?;
+    budget.spend(width, height)?;
+    let color = decoder.colortype().map_err(tiff_error)?;
+    let data = decoder.read_image().map_err(tiff_error)?;
+
+    let img = match (color, data) {
+        (ColorType::Gray(1), DecodingResult::U8(buf)) => {
+            GrayImage::from_raw(width, height, unpack_bilevel(&buf, width, height)?).map(DynamicImage::ImageLuma8)
+        }
+        (ColorType::Gray(8), DecodingResult::U8(buf)) => GrayImage::from_raw(width, height, buf).map(DynamicImage::ImageLuma8),
+        (ColorType::GrayA(8), DecodingResult::U8(buf)) => GrayAlphaImage::from_raw(width, height, buf).map(DynamicImage::ImageLumaA8),
+        (ColorType::RGB(8), DecodingResult::U8(buf)) => RgbImage::from_raw(width, height, buf).map(DynamicImage::ImageRgb8),
+        (ColorType::RGBA(8), DecodingResult::U8(buf)) => RgbaImage::from_raw(width, height, buf).map(DynamicImage::ImageRgba8),
+        (ColorType::CMYK(8), DecodingResult::U8(buf)) => RgbImage::from_raw(width, height, cmyk_to_rgb(&buf)).map(DynamicImage::ImageRgb8),
+        (ColorType::Gray(16), DecodingResult::U16(buf)) => {
+            ImageBuffer::<Luma<u16>, _>::from_raw(width, height, buf).map(DynamicImage::ImageLuma16)
+        }
+        (ColorType::RGB(16), DecodingResult::U16(buf)) => {
+            ImageBuffer::<Rgb<u16>, _>::from_raw(width, height, buf).map(DynamicImage::ImageRgb16)
+        }
+        (ColorType::RGBA(16), DecodingResult::U16(buf)) => {
+            ImageBuffer::<Rgba<u16>, _>::from_raw(width, height, buf).map(DynamicImage::ImageRgba16)
+        }
+        (color, _) => return Err(DecodeError::Invalid(format!("Unsupported TIFF color type: {:?}", color))),
+    };
+
+    img.ok_or_else(|| DecodeError::Invalid("Failed to decode TIFF: unexpected buffer size".into()))
+}
+
+/// Expand 1-bit rows (padded to whole bytes) into 8-bit grayscale
+///
+/// The tiff decoder has already complemented WhiteIsZero data byte by byte,
+/// as it does for every gray depth up to 8 bits, so a set bit is white for
+/// both photometric interpretations. The buffer length comes from the file,
+/// so it is checked before any row is indexed.
+fn unpack_bilevel(packed: &[u8], width: u32, height: u32) -> Result<Vec<u8>, DecodeError> {
+    let row_bytes = (width as usize).div_ceil(8);
+    let expected = row_bytes as u64 * height as u64;
+    if (packed.len() as u64) < expected {
+        return Err(DecodeError::Invalid(format!(
+            "Failed to decode TIFF: bilevel page has {} bytes, expected {}",
+            packed.len(),
+            expected
+        )));
+    }
+
+    let mut pixels = Vec::with_capacity(width as usize * height as usize);
+    for row in packed.chunks(row_bytes).take(height as usize) {
+        for x in 0..width as usize {
+            let bit = (row[x / 8] >> (7 - x % 8)) & 1;
+            pixels.push(if bit == 1 { 255 } else { 0 });
+        }
+    }
+
+    Ok(pixels)
+}
+
+fn cmyk_to_rgb(cmyk: &[u8]) -> Vec<u8> {
+    cmyk.chunks_exact(4)
+        .flat_map(|px| {
+            let k = 255 - px[3] as u16;
+            [
+                ((255 - px[0] as u16) * k / 255) as u8,
+                ((255 - px[1] as u16) * k / 255) as u8,
+                ((255 - px[2] as u16) * k / 255) as u8,
+            ]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiff::encoder::{TiffEncoder, colortype};
+    use crate::limits::{LimitExceeded, set_max_frame_pixels};
+
+    fn gray_tiff(pages: &[(u32, u32)]) -> Vec<u8> {
+        let mut data = Cursor::new(Vec::new());
+        {
+            let mut encoder = TiffEncoder::new(&mut data).unwrap();
+            for &(width, height) in pages {
+                let pixels = vec![128u8; (width * height) as usize];
+                encoder.write_image::<colortype::Gray8>(width, height, &pixels).unwrap();
+            }
+        }
+        data.into_inner()
+    }
+
+    /// Uncompressed 8-pixel-wide bilevel TIFF, one byte per row
+    ///
+    /// tiff 0.9 reads a whole byte per 1-bit sample, so the strip is padded
+    /// out to `width * height` bytes for the decoder to accept it.
+    fn bilevel_tiff(photometric: u16, rows: &[u8]) -> Vec<u8> {
+        let height = rows.len() as u32;
+        let strip_bytes = 8 * height;
+        let entries: [(u16, u16, u32); 9] = [
+            (256, 3, 8),
+            (257, 3, height),
+            (258, 3, 1),
+            (259, 3, 1),
+            (262, 3, photometric as u32),
+            (273, 4, 122),
+            (277, 3, 1),
+            (278, 3, height),
+            (279, 4, strip_bytes),
+        ];
+
+        let mut data = b"II*\0".to_vec();
+        data.extend_from_slice(&8u32.to_le_bytes());
+        data.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        for (tag, field_type, value) in entries {
+            data.extend_from_slice(&tag.to_le_bytes());
+            data.extend_from_slice(&field_type.to_le_bytes());
+            data.extend_from_slice(&1u32.to_le_bytes());
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(rows);
+        data.resize(122 + strip_bytes as usize, 0);
+        data
+    }
+
+    fn bilevel_pixels(photometric: u16) -> Vec<u8> {
+        let data = bilevel_tiff(photometric, &[0b1000_0001, 0b0000_0000]);
+        let mut decoder = open_tiff(&data).unwrap();
+        let img = read_page(&mut decoder, &mut FrameBudget::new()).unwrap();
+        img.into_luma8().into_raw()
+    }
+
+    #[test]
+    fn bilevel_black_is_zero() {
+        let pixels = bilevel_pixels(1);
+        assert_eq!(pixels[..8], [255, 0, 0, 0, 0, 0, 0, 255]);
+        assert!(pixels[8..].iter().all(|&p| p == 0));
+    }
+
+    #[test]
+    fn bilevel_white_is_zero() {
+        let pixels = bilevel_pixels(0);
+        assert_eq!(pixels[..8], [0, 255, 255, 255, 255, 255, 255, 0]);
+        assert!(pixels[8..].iter().all(|&p| p == 255));
+    }
+
+    #[test]
+    fn short_bilevel_buffer_is_an_error() {
+        assert!(unpack_bilevel(&[0xFF; 3], 9, 2).is_err());
+        assert_eq!(unpack_bilevel(&[0xFF, 0x80], 9, 1).unwrap(), vec![255; 9]);
+    }
+
+    #[test]
+    fn read_page_checks_dimensions() {
+        let data = gray_tiff(&[(40_000, 1)]);
+        let mut decoder = open_tiff(&data).unwrap();
+
+        let result = read_page(&mut decoder, &mut FrameBudget::new());
+        assert!(matches!(result, Err(DecodeError::Limit(LimitExceeded { limit: "maxWidth", .. }))));
+    }
+
+    #[test]
+    fn pages_share_frame_budget() {
+        set_max_frame_pixels(150);
+        let data = gray_tiff(&[(10, 10), (10, 10)]);
+        let mut decoder = open_tiff(&data).unwrap();
+        let mut budget = FrameBudget::new();
+
+        assert!(read_page(&mut decoder, &mut budget).is_ok());
+        decoder.next_image().unwrap();
+        let result = read_page(&mut decoder, &mut budget);
+        assert!(matches!(result, Err(DecodeError::Limit(LimitExceeded { limit: "maxFramePixels", .. }))));
+    }
+}
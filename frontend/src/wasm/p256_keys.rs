@@ -0,0 +1,139 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use base64::{Engine as _, engine::general_purpose};
+use p256::ecdsa::{Signature, SigningKey, VerifyingKey, signature::{Signer, Verifier}};
+use p256::elliptic_curve::rand_core::OsRng;
+use crate::crypto::CryptoModule;
+
+#[wasm_bindgen]
+impl CryptoModule {
+    /// Generate a NIST P-256 keypair for ECDSA
+    ///
+    /// Returns `{ publicKey, secretKey }` in base64. The public key is the
+    /// 65-byte uncompressed SEC1 point that WebCrypto imports as `raw`,
+    /// and the secret key the 32-byte scalar.
+    #[wasm_bindgen]
+    pub fn generate_p256_keypair() -> Result<JsValue, JsValue> {
+        let signing_key = SigningKey::random(&mut OsRng);
+        let public_key = signing_key.verifying_key().to_encoded_point(false);
+
+        let result = serde_json::json!({
+            "publicKey": general_purpose::STANDARD.encode(public_key.as_bytes()),
+            "secretKey": general_purpose::STANDARD.encode(signing_key.to_bytes())
+        });
+
+        JsValue::from_serde(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Sign a message with ECDSA P-256 and SHA-256 (JWT `ES256`)
+    ///
+    /// `format` is `p1363` (default), the 64-byte `r || s` that WebCrypto
+    /// and JWTs use, or `der` for Java, .NET and OpenSSL. Returns the
+    /// signature in base64.
+    #[wasm_bindgen]
+    pub fn sign_p256(&self, message: &str, secret_key_base64: &str, format: Option<String>) -> Result<String, JsValue> {
+        let signing_key = decode_secret_key(secret_key_base64).map_err(JsValue::from_str)?;
+        let signature: Signature = signing_key.sign(message.as_bytes());
+        let bytes = match format.as_deref().unwrap_or("p1363") {
+            "p1363" => signature.to_bytes().to_vec(),
+            "der" => signature.to_der().as_bytes().to_vec(),
+            other => return Err(JsValue::from_str(&format!("Unknown signature format: {} (expected p1363 or der)", other))),
+        };
+
+        Ok(general_purpose::STANDARD.encode(bytes))
+    }
+
+    /// Verify an ECDSA P-256 signature from `sign_p256` or another
+    /// implementation
+    ///
+    /// The signature may be in either P1363 or DER form, and the public key
+    /// compressed or uncompressed SEC1.
+    #[wasm_bindgen]
+    pub fn verify_p256(&self, message: &str, signature_base64: &str, public_key_base64: &str) -> Result<bool, JsValue> {
+        let signature_bytes = general_purpose::STANDARD
+            .decode(signature_base64)
+            .map_err(|e| JsValue::from_str(&format!("Invalid signature: {}", e)))?;
+
+        // A short DER signature can also be 64 bytes, so try both readings
+        let signatures: Vec<Signature> = [Signature::from_slice(&signature_bytes), Signature::from_der(&signature_bytes)]
+            .into_iter()
+            .flatten()
+            .collect();
+        if signatures.is_empty() {
+            return Err(JsValue::from_str("Invalid signature format: expected 64-byte P1363 or DER"));
+        }
+
+        let public_key = decode_public_key(public_key_base64).map_err(JsValue::from_str)?;
+
+        Ok(signatures.iter().any(|signature| public_key.verify(message.as_bytes(), signature).is_ok()))
+    }
+}
+
+/// Read a base64 32-byte secret scalar
+fn decode_secret_key(secret_key_base64: &str) -> Result<SigningKey, &'static str> {
+    let secret_bytes = general_purpose::STANDARD
+        .decode(secret_key_base64)
+        .map_err(|_| "Invalid secret key: expected base64")?;
+
+    SigningKey::from_slice(&secret_bytes).map_err(|_| "Invalid secret key format: expected a 32-byte P-256 scalar")
+}
+
+/// Read a base64 compressed or uncompressed SEC1 public key
+fn decode_public_key(public_key_base64: &str) -> Result<VerifyingKey, &'static str> {
+    let public_bytes = general_purpose::STANDARD
+        .decode(public_key_base64)
+        .map_err(|_| "Invalid public key: expected base64")?;
+
+    VerifyingKey::from_sec1_bytes(&public_bytes).map_err(|_| "Invalid public key format: expected a SEC1 P-256 point")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keypair() -> (String, String) {
+        let signing_key = SigningKey::from_slice(&[7; 32]).unwrap();
+        let public_key = signing_key.verifying_key().to_encoded_point(false);
+        (
+            general_purpose::STANDARD.encode(signing_key.to_bytes()),
+            general_purpose::STANDARD.encode(public_key.as_bytes()),
+        )
+    }
+
+    #[test]
+    fn sign_verify_round_trip() {
+        let crypto = CryptoModule {};
+        let (secret, public) = keypair();
+
+        for format in ["p1363", "der"] {
+            let signature = crypto.sign_p256("hello", &secret, Some(format.into())).ok().unwrap();
+            assert!(crypto.verify_p256("hello", &signature, &public).ok().unwrap());
+        }
+        let signature = crypto.sign_p256("hello", &secret, None).ok().unwrap();
+        assert_eq!(general_purpose::STANDARD.decode(signature).unwrap().len(), 64);
+    }
+
+    #[test]
+    fn tampered_message_fails() {
+        let crypto = CryptoModule {};
+        let (secret, public) = keypair();
+
+        let signature = crypto.sign_p256("hello", &secret, None).ok().unwrap();
+        assert!(!crypto.verify_p256("hellO", &signature, &public).ok().unwrap());
+    }
+
+    #[test]
+    fn bad_keys_are_rejected() {
+        assert_eq!(decode_secret_key("not base64!").err(), Some("Invalid secret key: expected base64"));
+        // Zero is not a valid scalar, and 33 bytes is too long
+        let zero = general_purpose::STANDARD.encode([0; 32]);
+        assert!(decode_secret_key(&zero).is_err());
+        assert!(decode_secret_key(&general_purpose::STANDARD.encode([1; 33])).is_err());
+
+        let (_, public) = keypair();
+        let mut point = general_purpose::STANDARD.decode(public).unwrap();
+        point[0] = 0x05;
+        assert!(decode_public_key(&general_purpose::STANDARD.encode(point)).is_err());
+    }
+}
@@ -0,0 +1,558 @@
+use std::cmp::Ordering;
+use wasm_bindgen::prelude::*;
+use js_sys::Array;
+use serde::Deserialize;
+use image::{DynamicImage, GrayImage, Rgba, RgbaImage, imageops::{self, FilterType}};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use crate::cancel::{run_cancellable, should_stop};
+use crate::filters::sobel_gradients;
+use crate::image_processor::{ImageProcessor, decode_array, encode, parse_options};
+use crate::limits::check_dimensions;
+use crate::transform::sample_bilinear;
+
+/// Images accepted by `stitch`; longer sequences drift too far from the
+/// reference plane to stay presentable
+const MAX_IMAGES: usize = 8;
+
+/// Longest side of the grayscale copies keypoints are detected on
+const FEATURE_SIZE: u32 = 800;
+
+/// Keypoints per image, spread over a grid so one busy region can't
+/// take them all
+const MAX_KEYPOINTS: usize = 1536;
+const KEYPOINT_GRID: usize = 16;
+
+/// Half side of the square patch a descriptor compares pixels in
+const PATCH_RADIUS: i32 = 15;
+
+/// Largest descriptor distance (of 256 bits) for a match, and how much
+/// closer the best candidate must be than the runner-up
+const MAX_HAMMING: u32 = 64;
+const MATCH_RATIO: f32 = 0.8;
+
+/// RANSAC settings; distances are in feature-image pixels
+const RANSAC_ITERATIONS: usize = 1000;
+const INLIER_DISTANCE: f64 = 3.0;
+const MIN_INLIERS: usize = 12;
+
+/// Gaussian sigma, in output pixels, splitting the low frequencies that are
+/// blended smoothly from the detail that is cut at the seam
+const BAND_SIGMA: f32 = 8.0;
+
+/// Fixed seeds keep results identical between runs
+const PATTERN_SEED: u64 = 0x0b21ef;
+const RANSAC_SEED: u64 = 0x5eed;
+
+const TRANSPARENT: Rgba<u8> = Rgba([0, 0, 0, 0]);
+
+/// Projective transform acting on column vectors `(x, y, 1)`
+type Matrix = [[f64; 3]; 3];
+
+const IDENTITY: Matrix = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// 256-bit BRIEF descriptor
+type Descriptor = [u64; 4];
+
+/// Point in one image and the matching point in another
+type Correspondence = ((f64, f64), (f64, f64));
+
+/// Settings for `stitch`
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct StitchOptions {
+    /// Longest side of the panorama (default 4096)
+    max_size: Option<u32>,
+}
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Stitch overlapping photos into a panorama PNG
+    ///
+    /// `images` must be in order, each overlapping the one before it by a
+    /// good margin (a third is comfortable), e.g. a left-to-right sweep of
+    /// a room. Corners are matched between neighbors, aligned with a
+    /// homography onto the middle photo's plane and blended in two bands:
+    /// brightness transitions smoothly while detail switches sharply at
+    /// the seam, avoiding ghosting. Areas no photo covers are transparent.
+    /// `options` accepts `{ maxSize }`. The flat projection suits up to
+    /// roughly 120 degrees of view.
+    #[wasm_bindgen]
+    pub fn stitch(&self, images: &Array, options: JsValue) -> Result<Vec<u8>, JsValue> {
+        let options: StitchOptions = parse_options(&options)?;
+        let images = decode_array(images)?;
+        if images.len() < 2 || images.len() > MAX_IMAGES {
+            return Err(JsValue::from_str(&format!("Stitching needs between 2 and {} images", MAX_IMAGES)));
+        }
+
+        let max_size = options.max_size.unwrap_or(4096).max(1);
+        let panorama = run_cancellable(self.cancel_token.as_ref(), || {
+            let to_reference = align(&images)?;
+            composite(&images, &to_reference, max_size)
+        })?;
+
+        encode(&DynamicImage::ImageRgba8(panorama), image::ImageOutputFormat::Png)
+    }
+}
+
+/// Keypoints and descriptors of one image
+struct Features {
+    /// Feature image size over full size, per axis
+    scale: (f64, f64),
+    /// Keypoint positions in feature-image pixels
+    points: Vec<(f64, f64)>,
+    descriptors: Vec<Descriptor>,
+}
+
+impl Features {
+    fn detect(img: &DynamicImage, pattern: &[[(i32, i32); 2]]) -> Features {
+        let gray = if img.width().max(img.height()) > FEATURE_SIZE {
+            img.thumbnail(FEATURE_SIZE, FEATURE_SIZE).to_luma8()
+        } else {
+            img.to_luma8()
+        };
+        let scale = (gray.width() as f64 / img.width() as f64, gray.height() as f64 / img.height() as f64);
+
+        let keypoints = harris_corners(&gray);
+        // Pixel comparisons are noise sensitive, so compare a smoothed copy
+        let smooth = imageops::blur(&gray, 2.0);
+
+        Features {
+            scale,
+            points: keypoints.iter().map(|&(x, y)| (x as f64, y as f64)).collect(),
+            descriptors: keypoints.iter().map(|&(x, y)| describe(&smooth, x, y, pattern)).collect(),
+        }
+    }
+}
+
+/// Homographies taking each image onto the middle one, in full resolution
+/// pixels
+fn align(images: &[DynamicImage]) -> Result<Vec<Matrix>, JsValue> {
+    let pattern = brief_pattern();
+    let features: Vec<Features> = images.iter().map(|img| Features::detect(img, &pattern)).collect();
+
+    // steps[i] takes image i onto image i - 1
+    let mut steps = vec![IDENTITY];
+    for (i, pair) in features.windows(2).enumerate() {
+        let step = register(&pair[1], &pair[0]).ok_or_else(|| {
+            JsValue::from_str(&format!("Image {} does not overlap image {} enough to align them", i + 1, i))
+        })?;
+        steps.push(step);
+    }
+
+    // Chain the steps outward from the reference, inverting them on its left
+    let reference = images.len() / 2;
+    let mut to_reference = Vec::with_capacity(images.len());
+    let mut current = IDENTITY;
+    for step in steps[1..=reference].iter().rev() {
+        let to_next = invert(step).ok_or_else(|| JsValue::from_str("Failed to align images"))?;
+        current = multiply(&current, &to_next);
+        to_reference.push(current);
+    }
+    to_reference.reverse();
+    to_reference.push(IDENTITY);
+
+    let mut current = IDENTITY;
+    for step in &steps[reference + 1..] {
+        current = multiply(&current, step);
+        to_reference.push(current);
+    }
+
+    Ok(to_reference)
+}
+
+/// Homography taking image `a` onto image `b`, if enough matches agree
+fn register(a: &Features, b: &Features) -> Option<Matrix> {
+    let h = ransac(&match_features(a, b))?;
+
+    let scale_a = [[a.scale.0, 0.0, 0.0], [0.0, a.scale.1, 0.0], [0.0, 0.0, 1.0]];
+    let scale_b = [[b.scale.0, 0.0, 0.0], [0.0, b.scale.1, 0.0], [0.0, 0.0, 1.0]];
+    Some(multiply(&invert(&scale_b)?, &multiply(&h, &scale_a)))
+}
+
+/// Strongest Harris corners, at most a few per grid cell, away from the
+/// border so descriptors fit
+fn harris_corners(gray: &GrayImage) -> Vec<(u32, u32)> {
+    let (width, height) = (gray.width() as usize, gray.height() as usize);
+    let margin = PATCH_RADIUS as usize + 1;
+    if width <= 2 * margin || height <= 2 * margin {
+        return Vec::new();
+    }
+
+    // Structure tensor entries summed over a 5x5 window
+    let (gx, gy) = sobel_gradients(gray);
+    let xx = box_sum(&gx.iter().map(|g| g * g).collect::<Vec<_>>(), width, height, 2);
+    let xy = box_sum(&gx.iter().zip(&gy).map(|(a, b)| a * b).collect::<Vec<_>>(), width, height, 2);
+    let yy = box_sum(&gy.iter().map(|g| g * g).collect::<Vec<_>>(), width, height, 2);
+    let response: Vec<f32> = xx
+        .iter()
+        .zip(&xy)
+        .zip(&yy)
+        .map(|((a, b), c)| a * c - b * b - 0.04 * (a + c) * (a + c))
+        .collect();
+
+    let threshold = response.iter().cloned().fold(0.0, f32::max) * 0.001;
+    let cell_width = width.div_ceil(KEYPOINT_GRID);
+    let cell_height = height.div_ceil(KEYPOINT_GRID);
+    let mut cells: Vec<Vec<(f32, u32, u32)>> = vec![Vec::new(); KEYPOINT_GRID * KEYPOINT_GRID];
+
+    for y in margin..height - margin {
+        for x in margin..width - margin {
+            let r = response[y * width + x];
+            if r <= threshold {
+                continue;
+            }
+            // 3x3 non-maximum suppression
+            let is_peak = (y - 1..=y + 1).all(|ny| (x - 1..=x + 1).all(|nx| (nx == x && ny == y) || response[ny * width + nx] < r));
+            if is_peak {
+                cells[(y / cell_height) * KEYPOINT_GRID + x / cell_width].push((r, x as u32, y as u32));
+            }
+        }
+    }
+
+    let per_cell = MAX_KEYPOINTS / (KEYPOINT_GRID * KEYPOINT_GRID);
+    cells
+        .into_iter()
+        .flat_map(|mut cell| {
+            cell.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+            cell.into_iter().take(per_cell).map(|(_, x, y)| (x, y))
+        })
+        .collect()
+}
+
+/// Sum over a (2 * radius + 1) square window, clipped at the edges
+fn box_sum(values: &[f32], width: usize, height: usize, radius: usize) -> Vec<f32> {
+    let mut horizontal = vec![0f32; values.len()];
+    for (row, out) in values.chunks_exact(width).zip(horizontal.chunks_exact_mut(width)) {
+        for (x, sum) in out.iter_mut().enumerate() {
+            *sum = row[x.saturating_sub(radius)..(x + radius + 1).min(width)].iter().sum();
+        }
+    }
+
+    let mut out = vec![0f32; values.len()];
+    for y in 0..height {
+        let rows = y.saturating_sub(radius)..(y + radius + 1).min(height);
+        for x in 0..width {
+            out[y * width + x] = rows.clone().map(|ny| horizontal[ny * width + x]).sum();
+        }
+    }
+    out
+}
+
+/// Point pairs a BRIEF descriptor compares, the same for every image
+fn brief_pattern() -> Vec<[(i32, i32); 2]> {
+    let mut rng = StdRng::seed_from_u64(PATTERN_SEED);
+    let side = (2 * PATCH_RADIUS + 1) as u32;
+    let mut offset = || ((rng.gen::<u32>() % side) as i32 - PATCH_RADIUS, (rng.gen::<u32>() % side) as i32 - PATCH_RADIUS);
+    (0..256).map(|_| [offset(), offset()]).collect()
+}
+
+fn describe(smooth: &GrayImage, x: u32, y: u32, pattern: &[[(i32, i32); 2]]) -> Descriptor {
+    let at = |(dx, dy): (i32, i32)| smooth.get_pixel((x as i32 + dx) as u32, (y as i32 + dy) as u32)[0];
+
+    let mut descriptor = [0u64; 4];
+    for (bit, &[a, b]) in pattern.iter().enumerate() {
+        if at(a) < at(b) {
+            descriptor[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+    descriptor
+}
+
+/// Nearest-neighbor matches from `a` to `b` that pass the ratio test
+fn match_features(a: &Features, b: &Features) -> Vec<Correspondence> {
+    let distance = |x: &Descriptor, y: &Descriptor| x.iter().zip(y).map(|(p, q)| (p ^ q).count_ones()).sum::<u32>();
+
+    a.descriptors
+        .iter()
+        .zip(&a.points)
+        .filter_map(|(descriptor, &point)| {
+            let (mut best, mut second, mut best_index) = (u32::MAX, u32::MAX, 0);
+            for (i, candidate) in b.descriptors.iter().enumerate() {
+                let d = distance(descriptor, candidate);
+                if d < best {
+                    second = best;
+                    best = d;
+                    best_index = i;
+                } else if d < second {
+                    second = d;
+                }
+            }
+
+            let distinct = (best as f32) < MATCH_RATIO * second as f32;
+            (best <= MAX_HAMMING && distinct).then(|| (point, b.points[best_index]))
+        })
+        .collect()
+}
+
+/// Homography supported by the most matches, refit on all of them
+fn ransac(matches: &[Correspondence]) -> Option<Matrix> {
+    if matches.len() < MIN_INLIERS {
+        return None;
+    }
+
+    let mut rng = StdRng::seed_from_u64(RANSAC_SEED);
+    let mut best: Vec<usize> = Vec::new();
+    for _ in 0..RANSAC_ITERATIONS {
+        let mut sample = Vec::with_capacity(4);
+        while sample.len() < 4 {
+            let candidate = rng.gen::<u32>() as usize % matches.len();
+            if !sample.contains(&candidate) {
+                sample.push(candidate);
+            }
+        }
+
+        let points: Vec<Correspondence> = sample.iter().map(|&i| matches[i]).collect();
+        if let Some(h) = fit_homography(&points).filter(plausible) {
+            let inliers = inliers(&h, matches);
+            if inliers.len() > best.len() {
+                best = inliers;
+            }
+        }
+    }
+
+    if best.len() < MIN_INLIERS {
+        return None;
+    }
+    let points: Vec<Correspondence> = best.iter().map(|&i| matches[i]).collect();
+    fit_homography(&points).filter(plausible)
+}
+
+fn inliers(h: &Matrix, matches: &[Correspondence]) -> Vec<usize> {
+    matches
+        .iter()
+        .enumerate()
+        .filter(|(_, (a, b))| match project(h, *a) {
+            Some((x, y)) => (x - b.0).powi(2) + (y - b.1).powi(2) < INLIER_DISTANCE * INLIER_DISTANCE,
+            None => false,
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Reject homographies that flip, squash or tilt an image more than
+/// overlapping handheld photos can
+fn plausible(h: &Matrix) -> bool {
+    let det = h[0][0] * h[1][1] - h[0][1] * h[1][0];
+    det > 0.2 && det < 5.0 && h[2][0].abs() < 0.002 && h[2][1].abs() < 0.002
+}
+
+/// Least-squares homography through the correspondences (at least 4),
+/// using Hartley normalization for numerical stability
+fn fit_homography(points: &[Correspondence]) -> Option<Matrix> {
+    let from = normalizer(points.iter().map(|p| p.0));
+    let to = normalizer(points.iter().map(|p| p.1));
+
+    // Normal equations of the direct linear transform with h33 fixed to 1
+    let mut system = [[0f64; 9]; 8];
+    for &(a, b) in points {
+        let (x, y) = project(&from, a)?;
+        let (u, v) = project(&to, b)?;
+        let rows = [
+            [x, y, 1.0, 0.0, 0.0, 0.0, -u * x, -u * y, u],
+            [0.0, 0.0, 0.0, x, y, 1.0, -v * x, -v * y, v],
+        ];
+        for row in &rows {
+            for (equation, &ri) in system.iter_mut().zip(row) {
+                for (entry, &rj) in equation.iter_mut().zip(row) {
+                    *entry += ri * rj;
+                }
+            }
+        }
+    }
+
+    let h = solve(system)?;
+    let normalized = [[h[0], h[1], h[2]], [h[3], h[4], h[5]], [h[6], h[7], 1.0]];
+    let h = multiply(&invert(&to)?, &multiply(&normalized, &from));
+    if h[2][2].abs() < 1e-12 {
+        return None;
+    }
+    Some(h.map(|row| row.map(|v| v / h[2][2])))
+}
+
+/// Similarity moving the points' centroid to the origin at a mean distance
+/// of sqrt(2)
+fn normalizer<I: Iterator<Item = (f64, f64)> + Clone>(points: I) -> Matrix {
+    let count = points.clone().count().max(1) as f64;
+    let (sum_x, sum_y) = points.clone().fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x, sy + y));
+    let (mean_x, mean_y) = (sum_x / count, sum_y / count);
+    let spread = points.map(|(x, y)| ((x - mean_x).powi(2) + (y - mean_y).powi(2)).sqrt()).sum::<f64>() / count;
+
+    let s = if spread > 0.0 { std::f64::consts::SQRT_2 / spread } else { 1.0 };
+    [[s, 0.0, -s * mean_x], [0.0, s, -s * mean_y], [0.0, 0.0, 1.0]]
+}
+
+/// Solve an 8x8 system given as rows of coefficients plus right-hand side
+fn solve(mut system: [[f64; 9]; 8]) -> Option<[f64; 8]> {
+    for col in 0..8 {
+        let pivot = (col..8).max_by(|&a, &b| system[a][col].abs().partial_cmp(&system[b][col].abs()).unwrap_or(Ordering::Equal))?;
+        if system[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        system.swap(col, pivot);
+
+        let pivot_row = system[col];
+        for (row, equation) in system.iter_mut().enumerate() {
+            if row != col {
+                let factor = equation[col] / pivot_row[col];
+                for (entry, p) in equation.iter_mut().zip(pivot_row) {
+                    *entry -= factor * p;
+                }
+            }
+        }
+    }
+
+    let mut solution = [0f64; 8];
+    for (i, value) in solution.iter_mut().enumerate() {
+        *value = system[i][8] / system[i][i];
+    }
+    Some(solution)
+}
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut out = [[0f64; 3]; 3];
+    for (out_row, a_row) in out.iter_mut().zip(a) {
+        for (j, entry) in out_row.iter_mut().enumerate() {
+            *entry = a_row.iter().zip(b).map(|(x, b_row)| x * b_row[j]).sum();
+        }
+    }
+    out
+}
+
+fn invert(m: &Matrix) -> Option<Matrix> {
+    let minor = |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    let adjugate = [
+        [minor(1, 2, 1, 2), -minor(0, 2, 1, 2), minor(0, 1, 1, 2)],
+        [-minor(1, 2, 0, 2), minor(0, 2, 0, 2), -minor(0, 1, 0, 2)],
+        [minor(1, 2, 0, 1), -minor(0, 2, 0, 1), minor(0, 1, 0, 1)],
+    ];
+    let det = m[0][0] * adjugate[0][0] + m[0][1] * adjugate[1][0] + m[0][2] * adjugate[2][0];
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    Some(adjugate.map(|row| row.map(|v| v / det)))
+}
+
+/// Apply a homography; `None` for points sent to infinity or behind the
+/// viewer
+fn project(h: &Matrix, (x, y): (f64, f64)) -> Option<(f64, f64)> {
+    let w = h[2][0] * x + h[2][1] * y + h[2][2];
+    if w < 1e-9 {
+        return None;
+    }
+    Some(((h[0][0] * x + h[0][1] * y + h[0][2]) / w, (h[1][0] * x + h[1][1] * y + h[1][2]) / w))
+}
+
+/// Warp every image onto a shared canvas and blend them in two bands
+fn composite(images: &[DynamicImage], to_reference: &[Matrix], max_size: u32) -> Result<RgbaImage, JsValue> {
+    let too_wide = || JsValue::from_str("Images cover too wide a view for a flat panorama");
+    let corners = |img: &DynamicImage| {
+        let (w, h) = (img.width() as f64, img.height() as f64);
+        [(0.0, 0.0), (w, 0.0), (0.0, h), (w, h)]
+    };
+
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+    for (img, h) in images.iter().zip(to_reference) {
+        for corner in corners(img) {
+            let (x, y) = project(h, corner).ok_or_else(too_wide)?;
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+    }
+
+    let scale = (max_size as f64 / (max_x - min_x).max(max_y - min_y)).min(1.0);
+    let width = (((max_x - min_x) * scale).ceil() as u32).max(1);
+    let height = (((max_y - min_y) * scale).ceil() as u32).max(1);
+    check_dimensions(width, height)?;
+    let placement = [[scale, 0.0, -min_x * scale], [0.0, scale, -min_y * scale], [0.0, 0.0, 1.0]];
+
+    // Low band: feather-weighted average. Detail: taken from the image with
+    // the highest weight, which places the seam midway between image edges
+    let pixels = width as usize * height as usize;
+    let mut low = vec![0f32; pixels * 3];
+    let mut weights = vec![0f32; pixels];
+    let mut best = vec![0f32; pixels];
+    let mut detail = vec![0f32; pixels * 3];
+
+    for (img, h) in images.iter().zip(to_reference) {
+        if should_stop() {
+            break;
+        }
+
+        // Resample at about output resolution so the band split has a fixed size
+        let to_canvas = multiply(&placement, h);
+        let src = if scale < 1.0 {
+            let resized_width = ((img.width() as f64 * scale).round() as u32).max(1);
+            let resized_height = ((img.height() as f64 * scale).round() as u32).max(1);
+            img.resize_exact(resized_width, resized_height, FilterType::Triangle).to_rgba8()
+        } else {
+            img.to_rgba8()
+        };
+        let src_scale = [
+            [src.width() as f64 / img.width() as f64, 0.0, 0.0],
+            [0.0, src.height() as f64 / img.height() as f64, 0.0],
+            [0.0, 0.0, 1.0],
+        ];
+        let from_canvas = multiply(&src_scale, &invert(&to_canvas).ok_or_else(too_wide)?);
+        let smooth = imageops::blur(&src, BAND_SIGMA);
+
+        let (mut x0, mut y0, mut x1, mut y1) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+        for corner in corners(img) {
+            let (x, y) = project(&to_canvas, corner).ok_or_else(too_wide)?;
+            x0 = x0.min(x);
+            y0 = y0.min(y);
+            x1 = x1.max(x);
+            y1 = y1.max(y);
+        }
+        let (x0, y0) = (x0.floor().max(0.0) as u32, y0.floor().max(0.0) as u32);
+        let (x1, y1) = (x1.ceil().min(width as f64) as u32, y1.ceil().min(height as f64) as u32);
+
+        let (src_width, src_height) = (src.width() as f64, src.height() as f64);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let (u, v) = match project(&from_canvas, (x as f64 + 0.5, y as f64 + 0.5)) {
+                    Some(point) => point,
+                    None => continue,
+                };
+                if u <= 0.0 || v <= 0.0 || u >= src_width || v >= src_height {
+                    continue;
+                }
+
+                // Highest in the middle of the image, fading to 0 at its edges
+                let feather = (u.min(src_width - u) / src_width * v.min(src_height - v) / src_height) as f32;
+                let px = sample_bilinear(&src, (u - 0.5) as f32, (v - 0.5) as f32, TRANSPARENT);
+                let weight = feather * px[3] as f32 / 255.0;
+                if weight <= 0.0 {
+                    continue;
+                }
+                let smooth_px = sample_bilinear(&smooth, (u - 0.5) as f32, (v - 0.5) as f32, TRANSPARENT);
+
+                let i = y as usize * width as usize + x as usize;
+                weights[i] += weight;
+                for (sum, &value) in low[i * 3..i * 3 + 3].iter_mut().zip(&smooth_px.0) {
+                    *sum += value as f32 * weight;
+                }
+                if weight > best[i] {
+                    best[i] = weight;
+                    for ((d, &value), &base) in detail[i * 3..i * 3 + 3].iter_mut().zip(&px.0).zip(&smooth_px.0) {
+                        *d = value as f32 - base as f32;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut output = RgbaImage::new(width, height);
+    let bands = low.chunks_exact(3).zip(detail.chunks_exact(3)).zip(&weights);
+    for (px, ((low, detail), &weight)) in output.pixels_mut().zip(bands) {
+        if weight > 0.0 {
+            for ((channel, l), d) in px.0.iter_mut().zip(low).zip(detail) {
+                *channel = (l / weight + d).round().max(0.0).min(255.0) as u8;
+            }
+            px[3] = 255;
+        }
+    }
+
+    Ok(output)
+}
@@ -0,0 +1,249 @@
+use wasm_bindgen::prelude::*;
+use image::{DynamicImage, Rgba32FImage, imageops::FilterType};
+use crate::cancel::{cancellation_active, should_stop};
+use crate::image_processor::into_color_type;
+#[cfg(feature = "parallel")]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Rows processed between cancellation checks
+const ROWS_PER_CHECK: usize = 64;
+
+/// Set once the worker pool is running; until then all work stays on the
+/// calling thread
+#[cfg(feature = "parallel")]
+static THREADS_READY: AtomicBool = AtomicBool::new(false);
+
+/// Start `num_threads` web workers for resize, blur and convolution
+///
+/// Requires the `parallel` feature and a cross-origin isolated page, since
+/// the workers share memory through a `SharedArrayBuffer`. Processing runs
+/// single-threaded until the returned promise resolves, so callers that
+/// can't use threads simply never call this.
+#[cfg(feature = "parallel")]
+#[wasm_bindgen]
+pub fn init_threads(num_threads: usize) -> js_sys::Promise {
+    let ready = Closure::once(|_: JsValue| THREADS_READY.store(true, Ordering::Release));
+    let promise = wasm_bindgen_rayon::init_thread_pool(num_threads).then(&ready);
+    ready.forget();
+    promise
+}
+
+/// Whether work is currently spread across the worker pool
+#[wasm_bindgen]
+pub fn threads_enabled() -> bool {
+    threads_ready()
+}
+
+#[cfg(feature = "parallel")]
+fn threads_ready() -> bool {
+    THREADS_READY.load(Ordering::Acquire)
+}
+
+#[cfg(not(feature = "parallel"))]
+fn threads_ready() -> bool {
+    false
+}
+
+/// Run `f` over each `row_len`-sized row of `buf`, with the row index,
+/// across the worker pool when it is available. Stops early once the
+/// active cancellation token fires.
+pub(crate) fn for_each_row<T: Send, F: Fn(usize, &mut [T]) + Send + Sync>(buf: &mut [T], row_len: usize, f: F) {
+    for (band, rows) in buf.chunks_mut(row_len * ROWS_PER_CHECK).enumerate() {
+        if should_stop() {
+            return;
+        }
+        process_rows(rows, row_len, band * ROWS_PER_CHECK, &f);
+    }
+}
+
+#[cfg(feature = "parallel")]
+fn process_rows<T: Send, F: Fn(usize, &mut [T]) + Send + Sync>(rows: &mut [T], row_len: usize, first: usize, f: &F) {
+    use rayon::prelude::*;
+
+    if threads_ready() {
+        rows.par_chunks_mut(row_len).enumerate().for_each(|(y, row)| f(first + y, row));
+    } else {
+        rows.chunks_mut(row_len).enumerate().for_each(|(y, row)| f(first + y, row));
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+fn process_rows<T: Send, F: Fn(usize, &mut [T]) + Send + Sync>(rows: &mut [T], row_len: usize, first: usize, f: &F) {
+    rows.chunks_mut(row_len).enumerate().for_each(|(y, row)| f(first + y, row));
+}
+
+/// Resize to exactly `width` x `height`
+///
+/// Without worker threads or a cancellation token this is
+/// `DynamicImage::resize_exact`; otherwise the equivalent separable filter
+/// runs one output row per task. Either way the result keeps the source's
+/// color type, so setting a token doesn't change the output format.
+pub(crate) fn resize_exact(img: &DynamicImage, width: u32, height: u32, filter: FilterType) -> DynamicImage {
+    if !threads_ready() && !cancellation_active() {
+        return img.resize_exact(width, height, filter);
+    }
+
+    let (kernel, support): (fn(f32) -> f32, f32) = match filter {
+        FilterType::Nearest => (|x| if (-0.5..0.5).contains(&x) { 1.0 } else { 0.0 }, 0.5),
+        FilterType::Triangle => (|x| (1.0 - x.abs()).max(0.0), 1.0),
+        FilterType::CatmullRom => (catmull_rom, 2.0),
+        FilterType::Gaussian => (|x| gaussian(x, 0.5), 3.0),
+        FilterType::Lanczos3 => (lanczos3, 3.0),
+    };
+
+    let src = img.to_rgba32f();
+    let xs = contributions(src.width(), width, &kernel, support);
+    let ys = contributions(src.height(), height, &kernel, support);
+    restore_color_type(img, separable(&src, &xs, &ys))
+}
+
+/// Resize to fit within `width` x `height`, keeping the aspect ratio
+pub(crate) fn resize(img: &DynamicImage, width: u32, height: u32, filter: FilterType) -> DynamicImage {
+    let ratio = (width as f64 / img.width() as f64).min(height as f64 / img.height() as f64);
+    let width = ((img.width() as f64 * ratio).round() as u32).max(1);
+    let height = ((img.height() as f64 * ratio).round() as u32).max(1);
+    resize_exact(img, width, height, filter)
+}
+
+/// Gaussian blur, matching `DynamicImage::blur`
+pub(crate) fn blur(img: &DynamicImage, sigma: f32) -> DynamicImage {
+    if !threads_ready() && !cancellation_active() {
+        return img.blur(sigma);
+    }
+
+    let sigma = if sigma <= 0.0 { 1.0 } else { sigma };
+    let kernel = |x: f32| gaussian(x, sigma);
+
+    let src = img.to_rgba32f();
+    let xs = contributions(src.width(), src.width(), &kernel, 2.0 * sigma);
+    let ys = contributions(src.height(), src.height(), &kernel, 2.0 * sigma);
+    restore_color_type(img, separable(&src, &xs, &ys))
+}
+
+fn gaussian(x: f32, sigma: f32) -> f32 {
+    (-(x * x) / (2.0 * sigma * sigma)).exp() / ((2.0 * std::f32::consts::PI).sqrt() * sigma)
+}
+
+fn sinc(x: f32) -> f32 {
+    if x == 0.0 {
+        1.0
+    } else {
+        let a = x * std::f32::consts::PI;
+        a.sin() / a
+    }
+}
+
+fn lanczos3(x: f32) -> f32 {
+    if x.abs() < 3.0 { sinc(x) * sinc(x / 3.0) } else { 0.0 }
+}
+
+fn catmull_rom(x: f32) -> f32 {
+    let x = x.abs();
+    if x < 1.0 {
+        1.5 * x * x * x - 2.5 * x * x + 1.0
+    } else if x < 2.0 {
+        -0.5 * x * x * x + 2.5 * x * x - 4.0 * x + 2.0
+    } else {
+        0.0
+    }
+}
+
+/// First source index and normalized weights for each destination pixel
+/// along one axis. The kernel is widened when downscaling so every source
+/// pixel contributes.
+fn contributions(src_len: u32, dst_len: u32, kernel: &dyn Fn(f32) -> f32, support: f32) -> Vec<(usize, Vec<f32>)> {
+    let ratio = src_len as f32 / dst_len as f32;
+    let scale = ratio.max(1.0);
+    let radius = support * scale;
+
+    (0..dst_len)
+        .map(|i| {
+            let center = (i as f32 + 0.5) * ratio;
+            let start = ((center - radius).floor().max(0.0) as usize).min(src_len as usize - 1);
+            let end = ((center + radius).ceil() as usize).min(src_len as usize).max(start + 1);
+
+            let mut weights: Vec<f32> = (start..end).map(|j| kernel((j as f32 + 0.5 - center) / scale)).collect();
+            let sum: f32 = weights.iter().sum();
+            if sum != 0.0 {
+                weights.iter_mut().for_each(|w| *w /= sum);
+            }
+            (start, weights)
+        })
+        .collect()
+}
+
+/// Horizontal then vertical pass, each parallelized over output rows
+fn separable(src: &Rgba32FImage, xs: &[(usize, Vec<f32>)], ys: &[(usize, Vec<f32>)]) -> Rgba32FImage {
+    let src_width = src.width() as usize;
+    let (width, height) = (xs.len(), ys.len());
+    let pixels: &[f32] = src.as_raw();
+
+    let mut horizontal = vec![0f32; width * src.height() as usize * 4];
+    for_each_row(&mut horizontal, width * 4, |y, row| {
+        let line = &pixels[y * src_width * 4..(y + 1) * src_width * 4];
+        for (x, (start, weights)) in xs.iter().enumerate() {
+            for (k, w) in weights.iter().enumerate() {
+                let px = &line[(start + k) * 4..(start + k) * 4 + 4];
+                for (value, source) in row[x * 4..x * 4 + 4].iter_mut().zip(px) {
+                    *value += source * w;
+                }
+            }
+        }
+    });
+
+    let mut out = vec![0f32; width * height * 4];
+    for_each_row(&mut out, width * 4, |y, row| {
+        let (start, weights) = &ys[y];
+        for (k, w) in weights.iter().enumerate() {
+            let line = &horizontal[(start + k) * width * 4..(start + k + 1) * width * 4];
+            for (value, source) in row.iter_mut().zip(line) {
+                *value += source * w;
+            }
+        }
+    });
+
+    Rgba32FImage::from_raw(width as u32, height as u32, out).unwrap_or_else(|| Rgba32FImage::new(width as u32, height as u32))
+}
+
+/// Convert a float result back to the color type and bit depth of the
+/// source, as the single-threaded `image` operations return
+fn restore_color_type(original: &DynamicImage, resampled: Rgba32FImage) -> DynamicImage {
+    into_color_type(DynamicImage::ImageRgba32F(resampled), original.color())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ColorType, ImageBuffer, Luma, LumaA, Rgb};
+    use crate::cancel::{CancellationToken, run_cancellable};
+
+    #[test]
+    fn restore_color_type_keeps_variant() {
+        let gray16 = DynamicImage::ImageLuma16(ImageBuffer::from_pixel(2, 2, Luma([40_000u16])));
+        let restored = restore_color_type(&gray16, gray16.to_rgba32f());
+        assert_eq!(restored.color(), ColorType::L16);
+        assert_eq!(restored.as_luma16().unwrap().get_pixel(0, 0), &Luma([40_000]));
+
+        let gray_alpha = DynamicImage::ImageLumaA8(ImageBuffer::from_pixel(2, 2, LumaA([10u8, 20])));
+        let restored = restore_color_type(&gray_alpha, gray_alpha.to_rgba32f());
+        assert_eq!(restored.color(), ColorType::La8);
+        assert_eq!(restored.as_luma_alpha8().unwrap().get_pixel(1, 1), &LumaA([10, 20]));
+    }
+
+    #[test]
+    fn cancellable_resize_matches_plain_resize_format() {
+        let img = DynamicImage::ImageRgb16(ImageBuffer::from_fn(16, 8, |x, y| Rgb([x as u16 * 4000, y as u16 * 8000, 1234])));
+        let plain = resize_exact(&img, 8, 4, FilterType::Triangle);
+
+        let token = CancellationToken::new();
+        let cancellable = run_cancellable(Some(&token), || Ok(resize_exact(&img, 8, 4, FilterType::Triangle)))
+            .ok()
+            .unwrap();
+        let blurred = run_cancellable(Some(&token), || Ok(blur(&img, 1.0))).ok().unwrap();
+
+        assert_eq!(plain.color(), ColorType::Rgb16);
+        assert_eq!(cancellable.color(), ColorType::Rgb16);
+        assert_eq!((cancellable.width(), cancellable.height()), (plain.width(), plain.height()));
+        assert_eq!(blurred.color(), ColorType::Rgb16);
+    }
+}
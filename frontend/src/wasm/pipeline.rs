@@ -0,0 +1,285 @@
+use wasm_bindgen::prelude::*;
+use web_sys::ImageData;
+use js_sys::{Array, Object, Reflect, Uint8Array};
+use serde::Deserialize;
+use image::{DynamicImage, imageops::FilterType};
+use crate::buffer::OutputBuffer;
+use crate::cancel::{CancellationToken, run_cancellable};
+use crate::encoding::{EncodeOptions, encode_as, encode_with_options};
+use crate::image_processor::{ImageProcessor, decode, parse_options, from_image_data, from_rgba, to_image_data};
+use crate::limits::check_dimensions;
+use crate::parallel;
+
+/// A decoded image kept in WASM memory so several operations can be chained
+/// without decoding and re-encoding between each step.
+///
+/// Operations consume the handle and return a new one, which allows
+/// `image.crop(...).blur(...).encode(...)` style chaining from JS.
+/// `resize` and `rotate` can fail, so they work in place instead and a
+/// rejected size or angle leaves the handle usable.
+#[wasm_bindgen]
+pub struct LoadedImage {
+    image: DynamicImage,
+}
+
+#[wasm_bindgen]
+impl LoadedImage {
+    #[wasm_bindgen(constructor)]
+    pub fn new(image_data: &[u8]) -> Result<LoadedImage, JsValue> {
+        Ok(LoadedImage { image: decode(image_data)? })
+    }
+
+    /// Load pixels from canvas `ImageData`
+    #[wasm_bindgen]
+    pub fn from_image_data(image_data: &ImageData) -> Result<LoadedImage, JsValue> {
+        Ok(LoadedImage { image: from_image_data(image_data)? })
+    }
+
+    /// Load pixels from a raw RGBA buffer
+    #[wasm_bindgen]
+    pub fn from_rgba(rgba: Vec<u8>, width: u32, height: u32) -> Result<LoadedImage, JsValue> {
+        Ok(LoadedImage { image: from_rgba(rgba, width, height)? })
+    }
+
+    /// Current width in pixels
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.image.width()
+    }
+
+    /// Current height in pixels
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.image.height()
+    }
+
+    /// Resize in place to the specified dimensions, which are held to
+    /// `set_limits`
+    #[wasm_bindgen]
+    pub fn resize(&mut self, width: u32, height: u32, maintain_aspect: bool) -> Result<(), JsValue> {
+        check_dimensions(width, height)?;
+        self.image = if maintain_aspect {
+            parallel::resize(&self.image, width, height, FilterType::Lanczos3)
+        } else {
+            parallel::resize_exact(&self.image, width, height, FilterType::Lanczos3)
+        };
+
+        Ok(())
+    }
+
+    /// Scale down to fit within the given bounds
+    #[wasm_bindgen]
+    pub fn thumbnail(self, max_width: u32, max_height: u32) -> LoadedImage {
+        LoadedImage { image: self.image.thumbnail(max_width, max_height) }
+    }
+
+    /// Crop to the given rectangle
+    #[wasm_bindgen]
+    pub fn crop(mut self, x: u32, y: u32, width: u32, height: u32) -> LoadedImage {
+        LoadedImage { image: self.image.crop(x, y, width, height) }
+    }
+
+    /// Apply blur filter
+    #[wasm_bindgen]
+    pub fn blur(self, sigma: f32) -> LoadedImage {
+        LoadedImage { image: parallel::blur(&self.image, sigma) }
+    }
+
+    /// Apply grayscale filter
+    #[wasm_bindgen]
+    pub fn grayscale(self) -> LoadedImage {
+        LoadedImage { image: self.image.grayscale() }
+    }
+
+    /// Adjust brightness
+    #[wasm_bindgen]
+    pub fn brighten(self, value: i32) -> LoadedImage {
+        LoadedImage { image: self.image.brighten(value) }
+    }
+
+    /// Adjust contrast
+    #[wasm_bindgen]
+    pub fn contrast(self, contrast: f32) -> LoadedImage {
+        LoadedImage { image: self.image.adjust_contrast(contrast) }
+    }
+
+    /// Rotate in place by 90, 180 or 270 degrees
+    #[wasm_bindgen]
+    pub fn rotate(&mut self, degrees: u32) -> Result<(), JsValue> {
+        self.image = match degrees {
+            90 => self.image.rotate90(),
+            180 => self.image.rotate180(),
+            270 => self.image.rotate270(),
+            _ => return Err(JsValue::from_str("Only 90, 180, 270 degree rotations supported")),
+        };
+
+        Ok(())
+    }
+
+    /// Flip horizontally or vertically
+    #[wasm_bindgen]
+    pub fn flip(self, horizontal: bool) -> LoadedImage {
+        let image = if horizontal {
+            self.image.fliph()
+        } else {
+            self.image.flipv()
+        };
+
+        LoadedImage { image }
+    }
+
+    /// Duplicate the handle so a branch of the chain can be encoded separately
+    #[wasm_bindgen(js_name = clone)]
+    pub fn duplicate(&self) -> LoadedImage {
+        LoadedImage { image: self.image.clone() }
+    }
+
+    /// Export the current pixels as canvas `ImageData`
+    #[wasm_bindgen]
+    pub fn to_image_data(&self) -> Result<ImageData, JsValue> {
+        to_image_data(&self.image)
+    }
+
+    /// Export the current pixels as a raw RGBA buffer
+    #[wasm_bindgen]
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.image.to_rgba8().into_raw()
+    }
+
+    /// Export the current pixels as raw RGBA kept in WASM memory, avoiding
+    /// the copy `to_rgba` makes
+    #[wasm_bindgen]
+    pub fn to_rgba_buffer(&self) -> OutputBuffer {
+        self.image.to_rgba8().into_raw().into()
+    }
+
+    /// Encode as AVIF with quality (1-100) and speed (1-10)
+    #[cfg(feature = "avif")]
+    #[wasm_bindgen]
+    pub fn encode_avif(&self, quality: u8, speed: u8) -> Result<Vec<u8>, JsValue> {
+        crate::image_processor::encode_avif(&self.image, quality, speed)
+    }
+
+    /// Encode the current image; `quality` is used by lossy formats only
+    #[wasm_bindgen]
+    pub fn encode(&self, format: &str, quality: u8) -> Result<Vec<u8>, JsValue> {
+        encode_as(&self.image, format, quality)
+    }
+
+    /// Encode the current image with format-specific encoder options
+    #[wasm_bindgen]
+    pub fn encode_with_options(&self, format: &str, options: JsValue) -> Result<Vec<u8>, JsValue> {
+        let options: EncodeOptions = parse_options(&options)?;
+        encode_with_options(&self.image, format, &options)
+    }
+
+    /// Encode like `encode_with_options`, keeping the result in WASM memory
+    #[wasm_bindgen]
+    pub fn encode_to_buffer(&self, format: &str, options: JsValue) -> Result<OutputBuffer, JsValue> {
+        Ok(self.encode_with_options(format, options)?.into())
+    }
+}
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Apply one operation chain to many images in a single call
+    ///
+    /// `operations_json` is `{ "operations": [...], "format": "png",
+    /// "options": { ... } }`. Each operation names a `LoadedImage` method
+    /// in `op` and passes its parameters in camelCase, e.g.
+    /// `{ "op": "resize", "width": 800, "height": 600, "maintainAspect": true }`.
+    /// `format` and `options` select the output encoding as in
+    /// `convert_format`.
+    ///
+    /// Returns one object per input, in order: `{ data }` with the encoded
+    /// image, or `{ error }` if that image failed. One bad file doesn't
+    /// stop the rest of the batch, but cancelling does: the call then fails
+    /// with "Operation cancelled" without decoding the remaining images.
+    #[wasm_bindgen]
+    pub fn process_batch(&self, images: &Array, operations_json: &str) -> Result<Array, JsValue> {
+        let batch: Batch = serde_json::from_str(operations_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid operations: {}", e)))?;
+
+        let results = Array::new();
+        for value in images.iter() {
+            if self.cancel_token.as_ref().is_some_and(CancellationToken::is_cancelled) {
+                return Err(JsValue::from_str("Operation cancelled"));
+            }
+            let bytes = Uint8Array::new(&value).to_vec();
+            let result = Object::new();
+
+            match run_cancellable(self.cancel_token.as_ref(), || batch.process(&bytes)) {
+                Ok(data) => Reflect::set(&result, &"data".into(), &Uint8Array::from(data.as_slice()))?,
+                Err(error) => Reflect::set(&result, &"error".into(), &error)?,
+            };
+            results.push(&result);
+        }
+
+        Ok(results)
+    }
+}
+
+/// Operation chain and output encoding for `process_batch`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Batch {
+    operations: Vec<Operation>,
+    #[serde(default = "default_batch_format")]
+    format: String,
+    #[serde(default)]
+    options: EncodeOptions,
+}
+
+fn default_batch_format() -> String {
+    "png".to_string()
+}
+
+impl Batch {
+    fn process(&self, image_data: &[u8]) -> Result<Vec<u8>, JsValue> {
+        let mut image = LoadedImage::new(image_data)?;
+        for operation in &self.operations {
+            image = operation.apply(image)?;
+        }
+
+        encode_with_options(&image.image, &self.format, &self.options)
+    }
+}
+
+/// One step of a `process_batch` chain, mirroring the `LoadedImage` methods
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Operation {
+    #[serde(rename_all = "camelCase")]
+    Resize { width: u32, height: u32, #[serde(default)] maintain_aspect: bool },
+    #[serde(rename_all = "camelCase")]
+    Thumbnail { max_width: u32, max_height: u32 },
+    Crop { x: u32, y: u32, width: u32, height: u32 },
+    Blur { sigma: f32 },
+    Grayscale,
+    Brighten { value: i32 },
+    Contrast { contrast: f32 },
+    Rotate { degrees: u32 },
+    Flip { horizontal: bool },
+}
+
+impl Operation {
+    fn apply(&self, mut image: LoadedImage) -> Result<LoadedImage, JsValue> {
+        Ok(match *self {
+            Operation::Resize { width, height, maintain_aspect } => {
+                image.resize(width, height, maintain_aspect)?;
+                image
+            }
+            Operation::Thumbnail { max_width, max_height } => image.thumbnail(max_width, max_height),
+            Operation::Crop { x, y, width, height } => image.crop(x, y, width, height),
+            Operation::Blur { sigma } => image.blur(sigma),
+            Operation::Grayscale => image.grayscale(),
+            Operation::Brighten { value } => image.brighten(value),
+            Operation::Contrast { contrast } => image.contrast(contrast),
+            Operation::Rotate { degrees } => {
+                image.rotate(degrees)?;
+                image
+            }
+            Operation::Flip { horizontal } => image.flip(horizontal),
+        })
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use wasm_bindgen::prelude::*;
+use serde::Deserialize;
+use image::{DynamicImage, Rgba, RgbaImage};
+use crate::adjustments::luma;
+use crate::image_processor::{ImageProcessor, encode, parse_color, parse_options};
+use crate::limits::check_dimensions;
+use crate::text::{TextAlign, draw_text, load_font, measure_text};
+
+/// Slate tones used when no colors are given
+const DEFAULT_COLORS: [&str; 2] = ["#e2e8f0", "#94a3b8"];
+
+/// Cells across the longer side of the `geometric` style
+const GEOMETRIC_CELLS: u32 = 8;
+
+/// Settings for `generate_placeholder`
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct PlaceholderOptions {
+    /// Colors for the style; gradients use them as evenly spaced stops
+    colors: Vec<String>,
+    /// Linear gradient direction in degrees, 0 pointing right
+    angle: Option<f32>,
+    /// Varies the `geometric` pattern, e.g. an item id
+    seed: String,
+    /// Centered label such as "800 × 600"
+    text: Option<String>,
+    text_color: Option<String>,
+    text_size: Option<f32>,
+}
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Render a placeholder PNG locally
+    ///
+    /// `style` is `solid`, `gradient` (linear), `radial` or `geometric`
+    /// (a seeded mosaic of triangles). `options` accepts `{ colors, angle,
+    /// seed, text, textColor, textSize }`; drawing `text` needs `font_data`.
+    /// Without a `textColor` the label is black or white, whichever stands
+    /// out more against the background.
+    #[wasm_bindgen]
+    pub fn generate_placeholder(&self, width: u32, height: u32, style: &str, options: JsValue, font_data: Option<Vec<u8>>) -> Result<Vec<u8>, JsValue> {
+        if width == 0 || height == 0 {
+            return Err(JsValue::from_str("Placeholder dimensions must be non-zero"));
+        }
+        check_dimensions(width, height)?;
+
+        let options: PlaceholderOptions = parse_options(&options)?;
+        let names: Vec<&str> = if options.colors.is_empty() {
+            DEFAULT_COLORS.to_vec()
+        } else {
+            options.colors.iter().map(String::as_str).collect()
+        };
+        let colors = names.into_iter().map(parse_color).collect::<Result<Vec<_>, _>>()?;
+
+        let mut canvas = match style.to_lowercase().as_str() {
+            "solid" => RgbaImage::from_pixel(width, height, colors[0]),
+            "gradient" => linear_gradient(width, height, &colors, options.angle.unwrap_or(135.0)),
+            "radial" => radial_gradient(width, height, &colors),
+            "geometric" => geometric(width, height, &colors, &options.seed),
+            _ => return Err(JsValue::from_str("Placeholder style must be solid, gradient, radial or geometric")),
+        };
+
+        if let Some(text) = options.text.as_deref().filter(|t| !t.is_empty()) {
+            let font_data = font_data.ok_or_else(|| JsValue::from_str("Placeholder text requires font_data"))?;
+            let font = load_font(&font_data)?;
+            let size = options.text_size.unwrap_or(width.min(height) as f32 / 6.0).max(1.0);
+            let color = match options.text_color.as_deref() {
+                Some(color) => parse_color(color)?,
+                None => contrasting_text(&canvas),
+            };
+
+            let (_, text_height) = measure_text(&font, text, size);
+            let y = (height as f32 - text_height as f32) / 2.0;
+            draw_text(&mut canvas, &font, text, width as f32 / 2.0, y, size, color, TextAlign::Center);
+        }
+
+        encode(&DynamicImage::ImageRgba8(canvas), image::ImageOutputFormat::Png)
+    }
+}
+
+/// Color at `t` (0-1) along evenly spaced stops
+fn gradient_at(colors: &[Rgba<u8>], t: f32) -> Rgba<u8> {
+    if colors.len() == 1 {
+        return colors[0];
+    }
+
+    let position = t.max(0.0).min(1.0) * (colors.len() - 1) as f32;
+    let index = (position.floor() as usize).min(colors.len() - 2);
+    let local = position - index as f32;
+    let (a, b) = (colors[index], colors[index + 1]);
+
+    let mut out = [0u8; 4];
+    for (c, value) in out.iter_mut().enumerate() {
+        *value = (a[c] as f32 + (b[c] as f32 - a[c] as f32) * local).round() as u8;
+    }
+    Rgba(out)
+}
+
+fn linear_gradient(width: u32, height: u32, colors: &[Rgba<u8>], angle: f32) -> RgbaImage {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    // Project the corners on the direction so the stops span the whole canvas
+    let project = |x: f32, y: f32| x * cos + y * sin;
+    let corners = [project(0.0, 0.0), project(width as f32, 0.0), project(0.0, height as f32), project(width as f32, height as f32)];
+    let min = corners.iter().cloned().fold(f32::MAX, f32::min);
+    let max = corners.iter().cloned().fold(f32::MIN, f32::max);
+    let span = (max - min).max(1.0);
+
+    RgbaImage::from_fn(width, height, |x, y| {
+        gradient_at(colors, (project(x as f32 + 0.5, y as f32 + 0.5) - min) / span)
+    })
+}
+
+fn radial_gradient(width: u32, height: u32, colors: &[Rgba<u8>]) -> RgbaImage {
+    let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+    let radius = (cx * cx + cy * cy).sqrt().max(1.0);
+
+    RgbaImage::from_fn(width, height, |x, y| {
+        let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+        gradient_at(colors, (dx * dx + dy * dy).sqrt() / radius)
+    })
+}
+
+/// Square cells split along a diagonal into two triangles, each shaded
+/// from the palette by a hash of the seed and its position
+fn geometric(width: u32, height: u32, colors: &[Rgba<u8>], seed: &str) -> RgbaImage {
+    let cell = (width.max(height) / GEOMETRIC_CELLS).max(1);
+    let hash = |cx: u32, cy: u32, part: u32| {
+        let mut hasher = DefaultHasher::new();
+        (seed, cx, cy, part).hash(&mut hasher);
+        hasher.finish()
+    };
+    // Shades between the first and last color, so two colors still give variety
+    let shade = |h: u64| gradient_at(colors, (h % 1000) as f32 / 999.0);
+
+    let columns = width.div_ceil(cell);
+    let rows = height.div_ceil(cell);
+    let cells: Vec<(bool, Rgba<u8>, Rgba<u8>)> = (0..rows)
+        .flat_map(|cy| (0..columns).map(move |cx| (cx, cy)))
+        .map(|(cx, cy)| (hash(cx, cy, 0) % 2 == 0, shade(hash(cx, cy, 1)), shade(hash(cx, cy, 2))))
+        .collect();
+
+    RgbaImage::from_fn(width, height, |x, y| {
+        let (flipped, upper, lower) = cells[((y / cell) * columns + x / cell) as usize];
+        let (u, v) = ((x % cell) as f32 / cell as f32, (y % cell) as f32 / cell as f32);
+        let is_upper = if flipped { u > v } else { u + v < 1.0 };
+        if is_upper { upper } else { lower }
+    })
+}
+
+/// Black or white, whichever contrasts more with the average background
+fn contrasting_text(canvas: &RgbaImage) -> Rgba<u8> {
+    let total: u64 = canvas.pixels().map(|px| luma(px[0], px[1], px[2]) as u64).sum();
+    let mean = total / (canvas.width() as u64 * canvas.height() as u64).max(1);
+    if mean > 140 {
+        Rgba([17, 24, 39, 255])
+    } else {
+        Rgba([255, 255, 255, 255])
+    }
+}
@@ -0,0 +1,142 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use image::{DynamicImage, Rgba, RgbaImage};
+use qrcode::{Color, EcLevel, QrCode};
+use crate::image_processor::{ImageProcessor, decode, encode, parse_color};
+
+/// Quiet zone around the symbol, in modules, as required by the spec
+const QUIET_ZONE: u32 = 4;
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Render `text` as a QR code PNG of roughly `size` x `size` pixels
+    ///
+    /// `error_correction` is `L`, `M`, `Q` or `H` (7-30% recoverable).
+    /// Modules are whole pixels, so the symbol is centered with a little
+    /// extra margin when `size` is not an exact multiple; if `size` is too
+    /// small the image grows to one pixel per module.
+    #[wasm_bindgen]
+    pub fn generate_qr(&self, text: &str, size: u32, error_correction: &str, fg_color: &str, bg_color: &str) -> Result<Vec<u8>, JsValue> {
+        let code = build_qr(text, error_correction)?;
+        let (fg, bg) = (parse_color(fg_color)?, parse_color(bg_color)?);
+
+        let modules = code.width() as u32 + 2 * QUIET_ZONE;
+        let module_px = (size / modules).max(1);
+        let side = size.max(modules * module_px);
+        let offset = (side - modules * module_px) / 2 + QUIET_ZONE * module_px;
+
+        let mut canvas = RgbaImage::from_pixel(side, side, bg);
+        for (i, color) in code.to_colors().iter().enumerate() {
+            if *color != Color::Dark {
+                continue;
+            }
+            let mx = (i % code.width()) as u32;
+            let my = (i / code.width()) as u32;
+            for y in 0..module_px {
+                for x in 0..module_px {
+                    canvas.put_pixel(offset + mx * module_px + x, offset + my * module_px + y, fg);
+                }
+            }
+        }
+
+        encode(&DynamicImage::ImageRgba8(canvas), image::ImageOutputFormat::Png)
+    }
+
+    /// Render `text` as a scalable QR code SVG, one user unit per module
+    #[wasm_bindgen]
+    pub fn generate_qr_svg(&self, text: &str, error_correction: &str, fg_color: &str, bg_color: &str) -> Result<String, JsValue> {
+        let code = build_qr(text, error_correction)?;
+        let (fg, bg) = (parse_color(fg_color)?, parse_color(bg_color)?);
+        let width = code.width();
+        let modules = width as u32 + 2 * QUIET_ZONE;
+
+        // One horizontal run per path segment keeps the markup small
+        let mut path = String::new();
+        for (y, row) in code.to_colors().chunks(width).enumerate() {
+            let mut x = 0;
+            while x < width {
+                if row[x] != Color::Dark {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < width && row[x] == Color::Dark {
+                    x += 1;
+                }
+                path.push_str(&format!(
+                    "M{} {}h{}v1h-{}z",
+                    start as u32 + QUIET_ZONE,
+                    y as u32 + QUIET_ZONE,
+                    x - start,
+                    x - start
+                ));
+            }
+        }
+
+        Ok(format!(
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {m} {m}" shape-rendering="crispEdges">"#,
+                r#"<rect width="{m}" height="{m}" {bg}/><path d="{path}" {fg}/></svg>"#
+            ),
+            m = modules,
+            bg = svg_fill(bg),
+            fg = svg_fill(fg),
+            path = path
+        ))
+    }
+
+    /// Find and decode every QR code in a photo or screenshot
+    ///
+    /// Returns `[{ text, corners: [{ x, y }; 4] }]`; the corners run
+    /// clockwise from the symbol's top-left finder pattern. Symbols that are
+    /// located but fail to decode are skipped.
+    #[wasm_bindgen]
+    pub fn decode_qr(&self, image_data: &[u8]) -> Result<JsValue, JsValue> {
+        let gray = decode(image_data)?.to_luma8();
+        let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+            gray.width() as usize,
+            gray.height() as usize,
+            |x, y| gray.get_pixel(x as u32, y as u32)[0],
+        );
+
+        let codes: Vec<_> = prepared
+            .detect_grids()
+            .into_iter()
+            .filter_map(|grid| {
+                let (_, text) = grid.decode().ok()?;
+                let corners: Vec<_> = grid
+                    .bounds
+                    .iter()
+                    .map(|p| serde_json::json!({ "x": p.x, "y": p.y }))
+                    .collect();
+                Some(serde_json::json!({ "text": text, "corners": corners }))
+            })
+            .collect();
+
+        JsValue::from_serde(&codes)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+}
+
+fn build_qr(text: &str, error_correction: &str) -> Result<QrCode, JsValue> {
+    let level = match error_correction.to_uppercase().as_str() {
+        "L" => EcLevel::L,
+        "M" => EcLevel::M,
+        "Q" => EcLevel::Q,
+        "H" => EcLevel::H,
+        _ => return Err(JsValue::from_str("Error correction must be L, M, Q or H")),
+    };
+
+    QrCode::with_error_correction_level(text.as_bytes(), level)
+        .map_err(|e| JsValue::from_str(&format!("Failed to encode QR code: {}", e)))
+}
+
+/// SVG `fill` (and `fill-opacity` when translucent) attributes for a color
+pub(crate) fn svg_fill(color: Rgba<u8>) -> String {
+    let fill = format!(r##"fill="#{:02x}{:02x}{:02x}""##, color[0], color[1], color[2]);
+    if color[3] == 255 {
+        fill
+    } else {
+        format!(r#"{} fill-opacity="{:.3}""#, fill, color[3] as f32 / 255.0)
+    }
+}
@@ -0,0 +1,277 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use image::RgbaImage;
+use color_quant::NeuQuant;
+use crate::encoding::encode_indexed_png;
+use crate::image_processor::{ImageProcessor, decode};
+
+/// Longest side of the copy sampled by `dominant_colors`
+const DOMINANT_SAMPLE_SIZE: u32 = 128;
+
+/// 8x8 Bayer threshold matrix for ordered dithering
+const BAYER_8X8: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Reduce to `max_colors` (2-256) with dithering, returning an indexed PNG
+    ///
+    /// `method` is `floyd-steinberg` or `bayer`. With `grayscale` set the
+    /// palette is evenly spaced gray levels (e.g. for e-ink previews),
+    /// otherwise it is built adaptively from the image.
+    #[wasm_bindgen]
+    pub fn dither(&self, image_data: &[u8], max_colors: u16, method: &str, grayscale: bool) -> Result<Vec<u8>, JsValue> {
+        let rgba = decode(image_data)?.to_rgba8();
+        let max_colors = max_colors.max(2).min(256) as usize;
+
+        let palette = if grayscale {
+            Palette::grayscale(max_colors)
+        } else {
+            Palette::adaptive(&rgba, max_colors)
+        };
+
+        let indices = match method.to_lowercase().as_str() {
+            "floyd-steinberg" | "floyd_steinberg" => floyd_steinberg(&rgba, &palette),
+            "bayer" | "ordered" => ordered_bayer(&rgba, &palette),
+            _ => return Err(JsValue::from_str("Dither method must be floyd-steinberg or bayer")),
+        };
+
+        encode_indexed_png(rgba.width(), rgba.height(), &palette, &indices)
+    }
+
+    /// Reduce to an adaptive palette of `max_colors` (2-256), returning an
+    /// indexed PNG
+    ///
+    /// With `dither` set, Floyd-Steinberg error diffusion hides banding at
+    /// the cost of slightly larger files.
+    #[wasm_bindgen]
+    pub fn quantize(&self, image_data: &[u8], max_colors: u16, dither: bool) -> Result<Vec<u8>, JsValue> {
+        let rgba = decode(image_data)?.to_rgba8();
+        let palette = Palette::adaptive(&rgba, max_colors.max(2).min(256) as usize);
+
+        let indices = if dither {
+            floyd_steinberg(&rgba, &palette)
+        } else {
+            map_nearest(&rgba, &palette)
+        };
+
+        encode_indexed_png(rgba.width(), rgba.height(), &palette, &indices)
+    }
+
+    /// Extract the `count` most dominant colors with median cut
+    ///
+    /// Returns `[{ color: "#rrggbb", percentage }]` sorted by coverage.
+    /// Pixels that are mostly transparent are ignored.
+    #[wasm_bindgen]
+    pub fn dominant_colors(&self, image_data: &[u8], count: u32) -> Result<JsValue, JsValue> {
+        let count = count.max(1).min(256) as usize;
+        // Colors barely change with resolution, so work on a small copy
+        let rgba = decode(image_data)?.thumbnail(DOMINANT_SAMPLE_SIZE, DOMINANT_SAMPLE_SIZE).to_rgba8();
+
+        let pixels: Vec<[u8; 3]> = rgba
+            .pixels()
+            .filter(|px| px[3] >= 128)
+            .map(|px| [px[0], px[1], px[2]])
+            .collect();
+        let total = pixels.len().max(1) as f64;
+
+        let mut boxes = median_cut(pixels, count);
+        boxes.sort_by_key(|b| std::cmp::Reverse(b.len()));
+
+        let colors: Vec<_> = boxes
+            .iter()
+            .filter(|b| !b.is_empty())
+            .map(|b| {
+                let mut sum = [0u64; 3];
+                for px in b {
+                    for c in 0..3 {
+                        sum[c] += px[c] as u64;
+                    }
+                }
+                let n = b.len() as u64;
+                serde_json::json!({
+                    "color": format!("#{:02x}{:02x}{:02x}", sum[0] / n, sum[1] / n, sum[2] / n),
+                    "percentage": (b.len() as f64 / total * 1000.0).round() / 10.0
+                })
+            })
+            .collect();
+
+        JsValue::from_serde(&colors)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+}
+
+/// Split pixels into up to `count` boxes, always cutting the box with the
+/// widest channel range at its median
+fn median_cut(pixels: Vec<[u8; 3]>, count: usize) -> Vec<Vec<[u8; 3]>> {
+    let range = |pixels: &[[u8; 3]]| -> (usize, u8) {
+        let mut widest = (0, 0);
+        for c in 0..3 {
+            let min = pixels.iter().map(|px| px[c]).min().unwrap_or(0);
+            let max = pixels.iter().map(|px| px[c]).max().unwrap_or(0);
+            if max - min > widest.1 {
+                widest = (c, max - min);
+            }
+        }
+        widest
+    };
+
+    let mut boxes = vec![pixels];
+    while boxes.len() < count {
+        let candidate = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(i, b)| (i, range(b)))
+            .filter(|(_, (_, width))| *width > 0)
+            .max_by_key(|(i, (_, width))| *width as usize * boxes[*i].len());
+
+        let (index, (channel, _)) = match candidate {
+            Some(found) => found,
+            None => break,
+        };
+
+        let mut split = boxes.swap_remove(index);
+        split.sort_unstable_by_key(|px| px[channel]);
+        let upper = split.split_off(split.len() / 2);
+        boxes.push(split);
+        boxes.push(upper);
+    }
+
+    boxes
+}
+
+/// A reduced color palette with nearest-color lookup
+pub(crate) struct Palette {
+    colors: Vec<[u8; 4]>,
+    quantizer: Option<NeuQuant>,
+}
+
+impl Palette {
+    /// Build a palette of at most `max_colors` entries with NeuQuant
+    pub(crate) fn adaptive(rgba: &RgbaImage, max_colors: usize) -> Self {
+        // Sampling factor 10 balances palette quality against speed
+        let quantizer = NeuQuant::new(10, max_colors, rgba.as_raw());
+        let colors = quantizer
+            .color_map_rgba()
+            .chunks_exact(4)
+            .map(|c| [c[0], c[1], c[2], c[3]])
+            .collect();
+
+        Palette { colors, quantizer: Some(quantizer) }
+    }
+
+    /// Evenly spaced opaque gray levels from black to white
+    pub(crate) fn grayscale(levels: usize) -> Self {
+        let levels = levels.max(2);
+        let colors = (0..levels)
+            .map(|i| {
+                let v = (i * 255 / (levels - 1)) as u8;
+                [v, v, v, 255]
+            })
+            .collect();
+
+        Palette { colors, quantizer: None }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    pub(crate) fn color(&self, index: u8) -> [u8; 4] {
+        self.colors[index as usize]
+    }
+
+    pub(crate) fn rgb(&self) -> Vec<u8> {
+        self.colors.iter().flat_map(|c| [c[0], c[1], c[2]]).collect()
+    }
+
+    pub(crate) fn alpha(&self) -> Vec<u8> {
+        self.colors.iter().map(|c| c[3]).collect()
+    }
+
+    pub(crate) fn nearest(&self, px: [u8; 4]) -> u8 {
+        if let Some(ref quantizer) = self.quantizer {
+            return quantizer.index_of(&px) as u8;
+        }
+
+        let distance = |c: &[u8; 4]| -> i32 {
+            (0..4).map(|i| (c[i] as i32 - px[i] as i32).pow(2)).sum()
+        };
+        self.colors
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, c)| distance(c))
+            .map(|(i, _)| i as u8)
+            .unwrap_or(0)
+    }
+}
+
+/// Map every pixel to its nearest palette entry without dithering
+pub(crate) fn map_nearest(rgba: &RgbaImage, palette: &Palette) -> Vec<u8> {
+    rgba.pixels().map(|px| palette.nearest(px.0)).collect()
+}
+
+/// Error diffusion with the classic 7/16, 3/16, 5/16, 1/16 weights
+pub(crate) fn floyd_steinberg(rgba: &RgbaImage, palette: &Palette) -> Vec<u8> {
+    let (width, height) = (rgba.width() as usize, rgba.height() as usize);
+    let mut indices = vec![0u8; width * height];
+
+    // Accumulated error for the current and next row, RGB only
+    let mut current = vec![[0f32; 3]; width + 2];
+    let mut next = vec![[0f32; 3]; width + 2];
+
+    for y in 0..height {
+        for x in 0..width {
+            let px = rgba.get_pixel(x as u32, y as u32);
+            let mut wanted = [0u8; 4];
+            for c in 0..3 {
+                wanted[c] = (px[c] as f32 + current[x + 1][c]).round().max(0.0).min(255.0) as u8;
+            }
+            wanted[3] = px[3];
+
+            let index = palette.nearest(wanted);
+            indices[y * width + x] = index;
+
+            let chosen = palette.color(index);
+            for c in 0..3 {
+                let error = px[c] as f32 + current[x + 1][c] - chosen[c] as f32;
+                current[x + 2][c] += error * 7.0 / 16.0;
+                next[x][c] += error * 3.0 / 16.0;
+                next[x + 1][c] += error * 5.0 / 16.0;
+                next[x + 2][c] += error * 1.0 / 16.0;
+            }
+        }
+
+        std::mem::swap(&mut current, &mut next);
+        next.iter_mut().for_each(|e| *e = [0.0; 3]);
+    }
+
+    indices
+}
+
+/// Ordered dithering with an 8x8 Bayer matrix
+pub(crate) fn ordered_bayer(rgba: &RgbaImage, palette: &Palette) -> Vec<u8> {
+    // Spread the threshold over roughly one palette step per channel
+    let steps_per_channel = (palette.len() as f32).cbrt().max(2.0);
+    let spread = 255.0 / (steps_per_channel - 1.0);
+
+    rgba.enumerate_pixels()
+        .map(|(x, y, px)| {
+            let threshold = BAYER_8X8[(y % 8) as usize][(x % 8) as usize] as f32 / 64.0 - 0.5;
+            let mut wanted = px.0;
+            for c in 0..3 {
+                wanted[c] = (px[c] as f32 + threshold * spread).round().max(0.0).min(255.0) as u8;
+            }
+            palette.nearest(wanted)
+        })
+        .collect()
+}
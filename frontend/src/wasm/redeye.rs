@@ -0,0 +1,139 @@
+use wasm_bindgen::prelude::*;
+use serde::Deserialize;
+use image::{DynamicImage, RgbaImage};
+use crate::image_processor::{ImageProcessor, decode, encode, parse_options};
+
+/// Red to green-blue ratio where correction starts, and where it is full;
+/// skin stays below the lower bound
+const REDNESS_START: f32 = 1.6;
+const REDNESS_FULL: f32 = 2.2;
+
+/// Pupils are dark to begin with; red below this level is left alone
+const MIN_RED: u8 = 60;
+
+/// Largest auto-detected pupil diameter as a fraction of the shorter side
+const MAX_PUPIL_FRACTION: f32 = 0.04;
+
+/// A rectangle around one eye
+#[derive(Deserialize, Clone, Copy)]
+struct EyeRegion {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Neutralize red pupils caused by on-camera flash
+    ///
+    /// `regions` is an array of `{ x, y, width, height }` boxes around the
+    /// eyes. Without regions, small round patches of strong red are found
+    /// automatically, which works for typical portraits but may also touch
+    /// small red objects. Inside each region, red pixels take the level of
+    /// their green and blue channels, blending smoothly so the iris and
+    /// skin are kept.
+    #[wasm_bindgen]
+    pub fn remove_red_eye(&self, image_data: &[u8], regions: JsValue) -> Result<Vec<u8>, JsValue> {
+        let mut regions: Vec<EyeRegion> = parse_options(&regions)?;
+        let mut rgba = decode(image_data)?.to_rgba8();
+
+        if regions.is_empty() {
+            regions = detect_red_pupils(&rgba);
+        }
+        for region in &regions {
+            correct_region(&mut rgba, *region);
+        }
+
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+}
+
+/// How red a pixel is, from 0 (not at all) to 1 (certainly a red pupil)
+fn redness(px: &[u8]) -> f32 {
+    if px[0] < MIN_RED {
+        return 0.0;
+    }
+
+    let others = (px[1] as f32 + px[2] as f32) / 2.0;
+    let ratio = px[0] as f32 / others.max(1.0);
+    ((ratio - REDNESS_START) / (REDNESS_FULL - REDNESS_START)).max(0.0).min(1.0)
+}
+
+fn correct_region(rgba: &mut RgbaImage, region: EyeRegion) {
+    let x_end = region.x.saturating_add(region.width).min(rgba.width());
+    let y_end = region.y.saturating_add(region.height).min(rgba.height());
+
+    for y in region.y..y_end {
+        for x in region.x..x_end {
+            let px = rgba.get_pixel_mut(x, y);
+            let weight = redness(&px.0);
+            if weight > 0.0 {
+                // A dark pupil keeps the highlights of the other channels
+                let target = (px[1] as f32 + px[2] as f32) / 2.0;
+                px[0] = (px[0] as f32 + (target - px[0] as f32) * weight).round() as u8;
+            }
+        }
+    }
+}
+
+/// Bounding boxes, with some margin, of compact round blobs of strong red
+fn detect_red_pupils(rgba: &RgbaImage) -> Vec<EyeRegion> {
+    let (width, height) = (rgba.width() as usize, rgba.height() as usize);
+    let max_diameter = (width.min(height) as f32 * MAX_PUPIL_FRACTION).max(4.0);
+    let mut red: Vec<bool> = rgba.pixels().map(|px| redness(&px.0) >= 0.5).collect();
+
+    let mut regions = Vec::new();
+    let mut stack = Vec::new();
+    let mut cursor = 0;
+    while let Some(offset) = red[cursor..].iter().position(|&is_red| is_red) {
+        let start = cursor + offset;
+        cursor = start + 1;
+
+        // Flood fill the blob, clearing it from the mask as we go
+        red[start] = false;
+        stack.push(start);
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (width, height, 0, 0);
+        let mut area = 0usize;
+        while let Some(i) = stack.pop() {
+            let (x, y) = (i % width, i / width);
+            area += 1;
+            min_x = min_x.min(x);
+            max_x = max_x.max(x);
+            min_y = min_y.min(y);
+            max_y = max_y.max(y);
+
+            let neighbors = [
+                (x > 0).then(|| i - 1),
+                (x + 1 < width).then(|| i + 1),
+                (y > 0).then(|| i - width),
+                (y + 1 < height).then(|| i + width),
+            ];
+            for n in neighbors.into_iter().flatten() {
+                if red[n] {
+                    red[n] = false;
+                    stack.push(n);
+                }
+            }
+        }
+
+        let (blob_width, blob_height) = ((max_x - min_x + 1) as f32, (max_y - min_y + 1) as f32);
+        let aspect = blob_width / blob_height;
+        // A filled circle covers about 79% of its bounding box
+        let fill = area as f32 / (blob_width * blob_height);
+        if area >= 4 && blob_width.max(blob_height) <= max_diameter && (0.5..=2.0).contains(&aspect) && fill >= 0.5 {
+            let margin_x = (blob_width / 2.0).ceil() as usize;
+            let margin_y = (blob_height / 2.0).ceil() as usize;
+            let x = min_x.saturating_sub(margin_x);
+            let y = min_y.saturating_sub(margin_y);
+            regions.push(EyeRegion {
+                x: x as u32,
+                y: y as u32,
+                width: (max_x + margin_x + 1).min(width).saturating_sub(x) as u32,
+                height: (max_y + margin_y + 1).min(height).saturating_sub(y) as u32,
+            });
+        }
+    }
+
+    regions
+}
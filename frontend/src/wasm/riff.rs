@@ -0,0 +1,131 @@
+/// A RIFF chunk header declared more payload than the data holds
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Truncated;
+
+/// One chunk of a RIFF container such as WebP
+pub(crate) struct Chunk<'a> {
+    pub fourcc: &'a [u8],
+    pub payload: &'a [u8],
+    /// Header, payload and padding byte, for copying the chunk unchanged
+    pub raw: &'a [u8],
+}
+
+/// Walk the chunks following the 12-byte `RIFF....WEBP` header
+///
+/// Lengths come straight from the file, so every offset is computed with
+/// checked arithmetic; on wasm32 a 4 GiB length would otherwise wrap `pos`
+/// back to the start and loop forever. A chunk running past the end yields
+/// `Err(Truncated)` once and ends the walk. A missing padding byte after the
+/// final chunk is tolerated, as browsers do.
+pub(crate) fn chunks(data: &[u8]) -> Chunks<'_> {
+    Chunks { data, pos: 12, done: data.len() < 12 }
+}
+
+pub(crate) struct Chunks<'a> {
+    data: &'a [u8],
+    pos: usize,
+    done: bool,
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = Result<Chunk<'a>, Truncated>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.data.len() - self.pos < 8 {
+            return None;
+        }
+
+        let header = &self.data[self.pos..self.pos + 8];
+        let length = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let payload_start = self.pos + 8;
+        let payload_end = match payload_start.checked_add(length) {
+            Some(end) if end <= self.data.len() => end,
+            _ => {
+                self.done = true;
+                return Some(Err(Truncated));
+            }
+        };
+        // Chunks are padded to an even size
+        let end = (payload_end + (length & 1)).min(self.data.len());
+
+        let chunk = Chunk {
+            fourcc: &header[..4],
+            payload: &self.data[payload_start..payload_end],
+            raw: &self.data[self.pos..end],
+        };
+        self.pos = end;
+        Some(Ok(chunk))
+    }
+}
+
+/// Append a chunk to a RIFF body, padding the payload to an even size
+pub(crate) fn write_chunk(out: &mut Vec<u8>, fourcc: &[u8; 4], payload: &[u8]) {
+    out.extend_from_slice(fourcc);
+    out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    out.extend_from_slice(payload);
+    if payload.len() % 2 == 1 {
+        out.push(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn webp(chunks: &[(&[u8; 4], u32, &[u8])]) -> Vec<u8> {
+        let mut data = b"RIFF\0\0\0\0WEBP".to_vec();
+        for (fourcc, length, payload) in chunks {
+            data.extend_from_slice(*fourcc);
+            data.extend_from_slice(&length.to_le_bytes());
+            data.extend_from_slice(payload);
+        }
+        data
+    }
+
+    #[test]
+    fn walks_padded_chunks() {
+        let data = webp(&[(b"VP8X", 3, &[1, 2, 3, 0]), (b"ANMF", 2, &[4, 5])]);
+        let chunks: Vec<_> = chunks(&data).collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].fourcc, b"VP8X");
+        assert_eq!(chunks[0].payload, &[1, 2, 3]);
+        assert_eq!(chunks[0].raw.len(), 12);
+        assert_eq!(chunks[1].fourcc, b"ANMF");
+        assert_eq!(chunks[1].payload, &[4, 5]);
+    }
+
+    #[test]
+    fn tolerates_missing_final_padding() {
+        let data = webp(&[(b"EXIF", 1, &[9])]);
+        let chunks: Vec<_> = chunks(&data).collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(chunks[0].payload, &[9]);
+        assert_eq!(chunks[0].raw.len(), 9);
+    }
+
+    #[test]
+    fn reports_truncated_chunk_once() {
+        let data = webp(&[(b"ICCP", 100, &[0; 10])]);
+        let results: Vec<_> = chunks(&data).collect();
+
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0], Err(Truncated)));
+    }
+
+    #[test]
+    fn maximal_length_does_not_wrap() {
+        let data = webp(&[(b"VP8 ", 4, &[0; 4]), (b"ANMF", u32::MAX, &[])]);
+        let results: Vec<_> = chunks(&data).collect();
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(Truncated)));
+    }
+
+    #[test]
+    fn short_input_has_no_chunks() {
+        assert_eq!(chunks(b"RIFF").count(), 0);
+        assert_eq!(chunks(b"RIFF\0\0\0\0WEBPVP8").count(), 0);
+    }
+}
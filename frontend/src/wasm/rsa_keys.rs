@@ -0,0 +1,192 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use base64::{Engine as _, engine::general_purpose};
+use sha2::Sha256;
+use rsa::{Oaep, RsaPrivateKey, RsaPublicKey};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey};
+use rsa::rand_core::OsRng;
+use rsa::signature::{RandomizedSigner, SignatureEncoding, Verifier};
+use crate::crypto::CryptoModule;
+
+/// Padding schemes for RSA signatures
+enum RsaScheme {
+    Pss,
+    Pkcs1v15,
+}
+
+impl RsaScheme {
+    fn parse(name: Option<&str>) -> Result<Self, JsValue> {
+        match name.map(str::to_lowercase).as_deref() {
+            None | Some("pss") => Ok(RsaScheme::Pss),
+            Some("pkcs1v15") => Ok(RsaScheme::Pkcs1v15),
+            Some(other) => Err(JsValue::from_str(&format!(
+                "Unknown RSA signature scheme: {} (expected pss or pkcs1v15)",
+                other
+            ))),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl CryptoModule {
+    /// Generate an RSA keypair of 2048, 3072 or 4096 bits
+    ///
+    /// Returns `{ publicKey, secretKey }` as base64 DER, SPKI for the public
+    /// key and PKCS#8 for the secret key, the formats WebCrypto, Java and
+    /// .NET import. Generation takes a few seconds for 3072 bits and more,
+    /// so call it from a worker.
+    #[wasm_bindgen]
+    pub fn generate_rsa_keypair(bits: usize) -> Result<JsValue, JsValue> {
+        check_key_size(bits).map_err(JsValue::from_str)?;
+
+        let secret_key = RsaPrivateKey::new(&mut OsRng, bits)
+            .map_err(|e| JsValue::from_str(&format!("Key generation failed: {}", e)))?;
+        let public_der = RsaPublicKey::from(&secret_key)
+            .to_public_key_der()
+            .map_err(|e| JsValue::from_str(&format!("Key encoding failed: {}", e)))?;
+        let secret_der = secret_key
+            .to_pkcs8_der()
+            .map_err(|e| JsValue::from_str(&format!("Key encoding failed: {}", e)))?;
+
+        let result = serde_json::json!({
+            "publicKey": general_purpose::STANDARD.encode(public_der.as_bytes()),
+            "secretKey": general_purpose::STANDARD.encode(secret_der.as_bytes())
+        });
+
+        JsValue::from_serde(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Sign a message with RSA and SHA-256
+    ///
+    /// `scheme` is `pss` (default, `PS256` in JWTs) or `pkcs1v15` (`RS256`)
+    /// for systems that only accept the older padding. Returns the
+    /// signature in base64.
+    #[wasm_bindgen]
+    pub fn sign_rsa(&self, message: &str, secret_key_base64: &str, scheme: Option<String>) -> Result<String, JsValue> {
+        let secret_key = decode_secret_key(secret_key_base64)?;
+
+        let signature = match RsaScheme::parse(scheme.as_deref())? {
+            RsaScheme::Pss => rsa::pss::SigningKey::<Sha256>::new(secret_key)
+                .sign_with_rng(&mut OsRng, message.as_bytes())
+                .to_vec(),
+            RsaScheme::Pkcs1v15 => rsa::pkcs1v15::SigningKey::<Sha256>::new(secret_key)
+                .sign_with_rng(&mut OsRng, message.as_bytes())
+                .to_vec(),
+        };
+
+        Ok(general_purpose::STANDARD.encode(signature))
+    }
+
+    /// Verify an RSA SHA-256 signature made with the given `scheme`
+    #[wasm_bindgen]
+    pub fn verify_rsa(&self, message: &str, signature_base64: &str, public_key_base64: &str, scheme: Option<String>) -> Result<bool, JsValue> {
+        let signature_bytes = general_purpose::STANDARD
+            .decode(signature_base64)
+            .map_err(|e| JsValue::from_str(&format!("Invalid signature: {}", e)))?;
+        let public_key = decode_public_key(public_key_base64)?;
+
+        let valid = match RsaScheme::parse(scheme.as_deref())? {
+            RsaScheme::Pss => rsa::pss::Signature::try_from(signature_bytes.as_slice())
+                .map(|signature| rsa::pss::VerifyingKey::<Sha256>::new(public_key).verify(message.as_bytes(), &signature).is_ok()),
+            RsaScheme::Pkcs1v15 => rsa::pkcs1v15::Signature::try_from(signature_bytes.as_slice())
+                .map(|signature| rsa::pkcs1v15::VerifyingKey::<Sha256>::new(public_key).verify(message.as_bytes(), &signature).is_ok()),
+        }
+        .map_err(|e| JsValue::from_str(&format!("Invalid signature format: {}", e)))?;
+
+        Ok(valid)
+    }
+
+    /// Encrypt a short message, such as an AES key, with RSA-OAEP and SHA-256
+    ///
+    /// This matches WebCrypto's `RSA-OAEP` with `hash: "SHA-256"`. The
+    /// plaintext can be at most 190 bytes for a 2048-bit key; encrypt larger
+    /// data with `encrypt_bytes` and wrap its key instead.
+    #[wasm_bindgen]
+    pub fn encrypt_rsa_oaep(&self, plaintext: &[u8], public_key_base64: &str) -> Result<Vec<u8>, JsValue> {
+        decode_public_key(public_key_base64)?
+            .encrypt(&mut OsRng, Oaep::new::<Sha256>(), plaintext)
+            .map_err(|e| JsValue::from_str(&format!("Encryption failed: {}", e)))
+    }
+
+    /// Decrypt data from `encrypt_rsa_oaep`
+    ///
+    /// The private-key operation is blinded with a fresh random factor so its
+    /// timing does not depend on the ciphertext.
+    #[wasm_bindgen]
+    pub fn decrypt_rsa_oaep(&self, ciphertext: &[u8], secret_key_base64: &str) -> Result<Vec<u8>, JsValue> {
+        decode_secret_key(secret_key_base64)?
+            .decrypt_blinded(&mut OsRng, Oaep::new::<Sha256>(), ciphertext)
+            .map_err(|e| JsValue::from_str(&format!("Decryption failed: {}", e)))
+    }
+}
+
+/// Key sizes WebCrypto and common JWT libraries all accept
+fn check_key_size(bits: usize) -> Result<(), &'static str> {
+    if matches!(bits, 2048 | 3072 | 4096) {
+        Ok(())
+    } else {
+        Err("RSA key size must be 2048, 3072 or 4096 bits")
+    }
+}
+
+/// Read a base64 PKCS#8 DER secret key
+fn decode_secret_key(secret_key_base64: &str) -> Result<RsaPrivateKey, JsValue> {
+    let der = general_purpose::STANDARD
+        .decode(secret_key_base64)
+        .map_err(|e| JsValue::from_str(&format!("Invalid secret key: {}", e)))?;
+
+    RsaPrivateKey::from_pkcs8_der(&der)
+        .map_err(|e| JsValue::from_str(&format!("Invalid secret key format: {}", e)))
+}
+
+/// Read a base64 SPKI DER public key
+fn decode_public_key(public_key_base64: &str) -> Result<RsaPublicKey, JsValue> {
+    let der = general_purpose::STANDARD
+        .decode(public_key_base64)
+        .map_err(|e| JsValue::from_str(&format!("Invalid public key: {}", e)))?;
+
+    RsaPublicKey::from_public_key_der(&der)
+        .map_err(|e| JsValue::from_str(&format!("Invalid public key format: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keypair() -> (String, String) {
+        let secret_key = RsaPrivateKey::new(&mut OsRng, 2048).unwrap();
+        let public_der = RsaPublicKey::from(&secret_key).to_public_key_der().unwrap();
+        let secret_der = secret_key.to_pkcs8_der().unwrap();
+        (
+            general_purpose::STANDARD.encode(secret_der.as_bytes()),
+            general_purpose::STANDARD.encode(public_der.as_bytes()),
+        )
+    }
+
+    #[test]
+    fn oaep_and_pss_round_trip() {
+        let crypto = CryptoModule {};
+        let (secret, public) = keypair();
+
+        let ciphertext = crypto.encrypt_rsa_oaep(b"wrapped key", &public).ok().unwrap();
+        assert_eq!(ciphertext.len(), 256);
+        assert_eq!(crypto.decrypt_rsa_oaep(&ciphertext, &secret).ok().unwrap(), b"wrapped key");
+
+        for scheme in [None, Some("pkcs1v15".to_string())] {
+            let signature = crypto.sign_rsa("hello", &secret, scheme.clone()).ok().unwrap();
+            assert!(crypto.verify_rsa("hello", &signature, &public, scheme.clone()).ok().unwrap());
+            assert!(!crypto.verify_rsa("hellO", &signature, &public, scheme).ok().unwrap());
+        }
+    }
+
+    #[test]
+    fn only_standard_key_sizes() {
+        for bits in [2048, 3072, 4096] {
+            assert!(check_key_size(bits).is_ok());
+        }
+        for bits in [0, 1024, 2047, 8192] {
+            assert_eq!(check_key_size(bits), Err("RSA key size must be 2048, 3072 or 4096 bits"));
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use base64::{Engine as _, engine::general_purpose};
+use k256::ecdsa::{RecoveryId, Signature, SigningKey, VerifyingKey, signature::hazmat::PrehashVerifier};
+use k256::elliptic_curve::rand_core::OsRng;
+use k256::schnorr;
+use crate::crypto::CryptoModule;
+
+#[wasm_bindgen]
+impl CryptoModule {
+    /// Generate a secp256k1 keypair
+    ///
+    /// Returns `{ publicKey, xOnlyPublicKey, secretKey }` in base64: the
+    /// 33-byte compressed SEC1 key used with ECDSA, the 32-byte x-only key
+    /// BIP-340 Schnorr signatures verify against, and the 32-byte scalar.
+    #[wasm_bindgen]
+    pub fn generate_secp256k1_keypair() -> Result<JsValue, JsValue> {
+        let signing_key = SigningKey::random(&mut OsRng);
+        let public_key = signing_key.verifying_key().to_encoded_point(true);
+        let x_only = schnorr::SigningKey::from_bytes(&signing_key.to_bytes())
+            .map_err(|e| JsValue::from_str(&format!("Key generation failed: {}", e)))?
+            .verifying_key()
+            .to_bytes();
+
+        let result = serde_json::json!({
+            "publicKey": general_purpose::STANDARD.encode(public_key.as_bytes()),
+            "xOnlyPublicKey": general_purpose::STANDARD.encode(x_only),
+            "secretKey": general_purpose::STANDARD.encode(signing_key.to_bytes())
+        });
+
+        JsValue::from_serde(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Sign a 32-byte message hash with secp256k1 ECDSA
+    ///
+    /// Hash the message first with whatever the chain expects (SHA-256,
+    /// double SHA-256, Keccak-256). Returns 65 bytes in base64: the low-S
+    /// `r || s` followed by the recovery id (0 or 1), so the signer's public
+    /// key can be recovered with `recover_secp256k1`.
+    #[wasm_bindgen]
+    pub fn sign_secp256k1(&self, message_hash: &[u8], secret_key_base64: &str) -> Result<String, JsValue> {
+        let signature = sign_recoverable(&decode_secret_key(secret_key_base64)?, message_hash)?;
+        Ok(general_purpose::STANDARD.encode(signature))
+    }
+
+    /// Verify a secp256k1 ECDSA signature over a 32-byte message hash
+    ///
+    /// Takes the 64-byte `r || s` or the 65-byte recoverable form, and a
+    /// compressed or uncompressed public key.
+    #[wasm_bindgen]
+    pub fn verify_secp256k1(&self, message_hash: &[u8], signature_base64: &str, public_key_base64: &str) -> Result<bool, JsValue> {
+        let signature_bytes = general_purpose::STANDARD
+            .decode(signature_base64)
+            .map_err(|e| JsValue::from_str(&format!("Invalid signature: {}", e)))?;
+
+        let public_bytes = general_purpose::STANDARD
+            .decode(public_key_base64)
+            .map_err(|e| JsValue::from_str(&format!("Invalid public key: {}", e)))?;
+
+        check_message_hash(message_hash).map_err(JsValue::from_str)?;
+        let (signature, _) = parse_signature(&signature_bytes)?;
+        let public_key = VerifyingKey::from_sec1_bytes(&public_bytes)
+            .map_err(|e| JsValue::from_str(&format!("Invalid public key format: {}", e)))?;
+
+        Ok(public_key.verify_prehash(message_hash, &signature).is_ok())
+    }
+
+    /// Recover the compressed public key that made a 65-byte recoverable
+    /// signature over `message_hash`, in base64
+    #[wasm_bindgen]
+    pub fn recover_secp256k1(&self, message_hash: &[u8], signature_base64: &str) -> Result<String, JsValue> {
+        let signature_bytes = general_purpose::STANDARD
+            .decode(signature_base64)
+            .map_err(|e| JsValue::from_str(&format!("Invalid signature: {}", e)))?;
+
+        let public_key = recover_public_key(message_hash, &signature_bytes)?;
+        Ok(general_purpose::STANDARD.encode(public_key.to_encoded_point(true).as_bytes()))
+    }
+
+    /// Sign a message with a BIP-340 Schnorr signature
+    ///
+    /// Bitcoin signs a 32-byte tagged hash, but any length is accepted.
+    /// Fresh auxiliary randomness is mixed into the nonce. Returns the
+    /// 64-byte signature in base64.
+    #[wasm_bindgen]
+    pub fn sign_schnorr(&self, message: &[u8], secret_key_base64: &str) -> Result<String, JsValue> {
+        let signing_key = schnorr::SigningKey::from_bytes(&decode_secret_key(secret_key_base64)?.to_bytes())
+            .map_err(|e| JsValue::from_str(&format!("Invalid secret key format: {}", e)))?;
+        let aux_rand = rand::random::<[u8; 32]>();
+
+        let signature = signing_key
+            .sign_raw(message, &aux_rand)
+            .map_err(|e| JsValue::from_str(&format!("Signing failed: {}", e)))?;
+        Ok(general_purpose::STANDARD.encode(signature.to_bytes()))
+    }
+
+    /// Verify a BIP-340 Schnorr signature against a 32-byte x-only public
+    /// key (a 33-byte compressed key is also accepted)
+    #[wasm_bindgen]
+    pub fn verify_schnorr(&self, message: &[u8], signature_base64: &str, public_key_base64: &str) -> Result<bool, JsValue> {
+        let signature_bytes = general_purpose::STANDARD
+            .decode(signature_base64)
+            .map_err(|e| JsValue::from_str(&format!("Invalid signature: {}", e)))?;
+
+        let public_bytes = general_purpose::STANDARD
+            .decode(public_key_base64)
+            .map_err(|e| JsValue::from_str(&format!("Invalid public key: {}", e)))?;
+
+        let signature = schnorr::Signature::try_from(signature_bytes.as_slice())
+            .map_err(|e| JsValue::from_str(&format!("Invalid signature format: {}", e)))?;
+
+        // The x-only key is the compressed key without its parity byte
+        let x_only = if public_bytes.len() == 33 { &public_bytes[1..] } else { &public_bytes[..] };
+        let public_key = schnorr::VerifyingKey::from_bytes(x_only)
+            .map_err(|e| JsValue::from_str(&format!("Invalid public key format: {}", e)))?;
+
+        Ok(public_key.verify_raw(message, &signature).is_ok())
+    }
+}
+
+/// Read a base64 32-byte secp256k1 secret scalar
+pub(crate) fn decode_secret_key(secret_key_base64: &str) -> Result<SigningKey, JsValue> {
+    let secret_bytes = general_purpose::STANDARD
+        .decode(secret_key_base64)
+        .map_err(|e| JsValue::from_str(&format!("Invalid secret key: {}", e)))?;
+
+    SigningKey::from_slice(&secret_bytes)
+        .map_err(|e| JsValue::from_str(&format!("Invalid secret key format: {}", e)))
+}
+
+/// Low-S `r || s || v` signature over a 32-byte hash, `v` being 0 or 1
+pub(crate) fn sign_recoverable(signing_key: &SigningKey, message_hash: &[u8]) -> Result<[u8; 65], JsValue> {
+    check_message_hash(message_hash).map_err(JsValue::from_str)?;
+
+    let (signature, recovery_id) = signing_key
+        .sign_prehash_recoverable(message_hash)
+        .map_err(|e| JsValue::from_str(&format!("Signing failed: {}", e)))?;
+
+    let mut bytes = [0u8; 65];
+    bytes[..64].copy_from_slice(&signature.to_bytes());
+    bytes[64] = recovery_id.to_byte();
+    Ok(bytes)
+}
+
+/// Split a 64- or 65-byte signature into `r || s` and the recovery id, if
+/// present; Ethereum's 27/28 recovery ids are accepted too
+fn parse_signature(bytes: &[u8]) -> Result<(Signature, Option<RecoveryId>), JsValue> {
+    let (rs, recovery_id) = match bytes.len() {
+        64 => (bytes, None),
+        65 => {
+            let v = if bytes[64] >= 27 { bytes[64] - 27 } else { bytes[64] };
+            let recovery_id = RecoveryId::from_byte(v).ok_or_else(|| JsValue::from_str("Invalid signature recovery id"))?;
+            (&bytes[..64], Some(recovery_id))
+        }
+        _ => return Err(JsValue::from_str("Invalid signature length: expected 64 or 65 bytes")),
+    };
+
+    let signature = Signature::from_slice(rs)
+        .map_err(|e| JsValue::from_str(&format!("Invalid signature format: {}", e)))?;
+    Ok((signature, recovery_id))
+}
+
+/// ECDSA over secp256k1 signs a 32-byte digest; k256 would otherwise
+/// truncate a longer input or pad a shorter one instead of failing
+fn check_message_hash(message_hash: &[u8]) -> Result<(), &'static str> {
+    if message_hash.len() != 32 {
+        return Err("Message hash must be 32 bytes");
+    }
+    Ok(())
+}
+
+pub(crate) fn recover_public_key(message_hash: &[u8], signature_bytes: &[u8]) -> Result<VerifyingKey, JsValue> {
+    check_message_hash(message_hash).map_err(JsValue::from_str)?;
+    let (signature, recovery_id) = parse_signature(signature_bytes)?;
+    let recovery_id = recovery_id.ok_or_else(|| JsValue::from_str("Recovery needs a 65-byte signature"))?;
+
+    VerifyingKey::recover_from_prehash(message_hash, &signature, recovery_id)
+        .map_err(|e| JsValue::from_str(&format!("Public key recovery failed: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ecdsa_sign_verify_recover() {
+        let crypto = CryptoModule {};
+        let signing_key = SigningKey::from_slice(&[7; 32]).unwrap();
+        let secret = general_purpose::STANDARD.encode(signing_key.to_bytes());
+        let public = general_purpose::STANDARD.encode(signing_key.verifying_key().to_encoded_point(true).as_bytes());
+        let hash = [0x42; 32];
+
+        let signature = crypto.sign_secp256k1(&hash, &secret).ok().unwrap();
+        assert!(crypto.verify_secp256k1(&hash, &signature, &public).ok().unwrap());
+        assert!(!crypto.verify_secp256k1(&[0x43; 32], &signature, &public).ok().unwrap());
+        assert_eq!(crypto.recover_secp256k1(&hash, &signature).ok().unwrap(), public);
+
+        // Ethereum's 27/28 recovery ids recover the same key
+        let mut bytes = general_purpose::STANDARD.decode(&signature).unwrap();
+        bytes[64] += 27;
+        assert_eq!(recover_public_key(&hash, &bytes).ok().unwrap(), *signing_key.verifying_key());
+    }
+
+    #[test]
+    fn schnorr_bip340_vector() {
+        // BIP-340 test vector 0: secret key 3, zero aux_rand, zero message
+        let crypto = CryptoModule {};
+        let public = general_purpose::STANDARD.encode(hex::decode("F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9").unwrap());
+        let signature = hex::decode(
+            "E907831F80848D1069A5371B402410364BDF1C5F8307B0084C55F1CE2DCA821525F66A4A85EA8B71E482A74F382D2CE5EBEEE8FDB2172F477DF4900D310536C0",
+        )
+        .unwrap();
+
+        let encoded = general_purpose::STANDARD.encode(&signature);
+        assert!(crypto.verify_schnorr(&[0; 32], &encoded, &public).ok().unwrap());
+        assert!(!crypto.verify_schnorr(&[1; 32], &encoded, &public).ok().unwrap());
+
+        // Signing mixes in fresh aux_rand, so check what it makes verifies
+        let secret = general_purpose::STANDARD.encode([[0; 31].as_slice(), &[3]].concat());
+        let fresh = crypto.sign_schnorr(&[0; 32], &secret).ok().unwrap();
+        assert_ne!(fresh, encoded);
+        assert!(crypto.verify_schnorr(&[0; 32], &fresh, &public).ok().unwrap());
+    }
+
+    #[test]
+    fn message_hash_must_be_32_bytes() {
+        assert!(check_message_hash(&[0; 32]).is_ok());
+        for len in [0, 31, 33, 64] {
+            assert_eq!(check_message_hash(&vec![0; len]), Err("Message hash must be 32 bytes"));
+        }
+    }
+}
@@ -0,0 +1,349 @@
+use std::f32::consts::PI;
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use js_sys::{Array, Object, Reflect, Uint8Array};
+use image::{DynamicImage, GrayImage, Rgba, RgbaImage, imageops::FilterType};
+use crate::cancel::CancellationToken;
+use crate::image_processor::{ImageProcessor, decode, encode};
+
+/// Side of the downscaled image the pHash DCT runs on
+const PHASH_SIZE: u32 = 32;
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Compute a 64-bit perceptual hash as 16 hex digits
+    ///
+    /// `method` is `phash` (DCT based, most robust), `dhash` (gradient) or
+    /// `ahash` (average). Only hashes from the same method are comparable.
+    #[wasm_bindgen]
+    pub fn perceptual_hash(&self, image_data: &[u8], method: &str) -> Result<String, JsValue> {
+        let img = decode(image_data)?;
+        let hash = match method.to_lowercase().as_str() {
+            "phash" => phash(&img),
+            "dhash" => dhash(&img),
+            "ahash" => ahash(&img),
+            _ => return Err(JsValue::from_str("Hash method must be phash, dhash or ahash")),
+        };
+
+        Ok(format!("{:016x}", hash))
+    }
+
+    /// Hamming distance between two hex hashes; 0 means identical and values
+    /// up to about 10 usually indicate the same picture
+    #[wasm_bindgen]
+    pub fn hash_distance(&self, hash_a: &str, hash_b: &str) -> Result<u32, JsValue> {
+        hamming_distance(hash_a, hash_b)
+    }
+
+    /// Group near-duplicate images by pHash
+    ///
+    /// Images whose hashes are within `threshold` bits of each other (about
+    /// 10 for resized or recompressed copies, 0 for exact matches) end up in
+    /// the same cluster, transitively. Returns `{ clusters, hashes }`:
+    /// `clusters` lists the indices of each group of two or more images and
+    /// `hashes` holds every image's hash, or `null` where decoding failed.
+    #[wasm_bindgen]
+    pub fn find_duplicates(&self, images: &Array, threshold: u32) -> Result<JsValue, JsValue> {
+        let mut hashes: Vec<Option<u64>> = Vec::with_capacity(images.length() as usize);
+        for value in images.iter() {
+            if self.cancel_token.as_ref().is_some_and(CancellationToken::is_cancelled) {
+                return Err(JsValue::from_str("Operation cancelled"));
+            }
+            let bytes = Uint8Array::new(&value).to_vec();
+            hashes.push(decode(&bytes).ok().map(|img| phash(&img)));
+        }
+
+        // Union-find over every pair within the threshold
+        let mut parent: Vec<usize> = (0..hashes.len()).collect();
+        for (i, a) in hashes.iter().enumerate() {
+            for (j, b) in hashes.iter().enumerate().skip(i + 1) {
+                if let (Some(a), Some(b)) = (a, b) {
+                    if (a ^ b).count_ones() <= threshold {
+                        let (ri, rj) = (find_root(&mut parent, i), find_root(&mut parent, j));
+                        parent[rj] = ri;
+                    }
+                }
+            }
+        }
+
+        let mut groups: Vec<Vec<usize>> = vec![Vec::new(); hashes.len()];
+        for i in 0..hashes.len() {
+            let r = find_root(&mut parent, i);
+            groups[r].push(i);
+        }
+        let clusters: Vec<Vec<usize>> = groups.into_iter().filter(|group| group.len() > 1).collect();
+
+        let result = serde_json::json!({
+            "clusters": clusters,
+            "hashes": hashes.iter().map(|hash| hash.map(|h| format!("{:016x}", h))).collect::<Vec<_>>()
+        });
+
+        JsValue::from_serde(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Compare two images of the same size, returning `{ ssim, psnr }`
+    ///
+    /// SSIM (0-1, on luminance) uses the standard 11x11 Gaussian window;
+    /// PSNR is in dB over the RGB channels and is `null` for identical
+    /// images. SSIM above about 0.98 is generally visually lossless.
+    #[wasm_bindgen]
+    pub fn compare(&self, image_a: &[u8], image_b: &[u8]) -> Result<JsValue, JsValue> {
+        let a = decode(image_a)?.to_rgba8();
+        let b = decode(image_b)?.to_rgba8();
+        if a.dimensions() != b.dimensions() {
+            return Err(JsValue::from_str("Images must have the same dimensions"));
+        }
+
+        let squared_error: f64 = a
+            .pixels()
+            .zip(b.pixels())
+            .map(|(pa, pb)| (0..3).map(|c| (pa[c] as f64 - pb[c] as f64).powi(2)).sum::<f64>())
+            .sum();
+        let mse = squared_error / (a.width() as f64 * a.height() as f64 * 3.0);
+        let psnr = if mse > 0.0 { Some(10.0 * (255.0 * 255.0 / mse).log10()) } else { None };
+
+        let luma_a = DynamicImage::ImageRgba8(a).to_luma8();
+        let luma_b = DynamicImage::ImageRgba8(b).to_luma8();
+
+        let result = serde_json::json!({
+            "ssim": ssim(&luma_a, &luma_b),
+            "psnr": psnr
+        });
+
+        JsValue::from_serde(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Highlight the pixels that differ between two same-sized images
+    ///
+    /// Works like pixelmatch: differences are measured in YIQ space and
+    /// `threshold` (0-1, 0.1 is a good default) sets the sensitivity.
+    /// Returns `{ image, changedPixels, totalPixels }` where `image` is a PNG
+    /// of a faded copy of `image_a` with changed pixels in red.
+    #[wasm_bindgen]
+    pub fn diff_images(&self, image_a: &[u8], image_b: &[u8], threshold: f32) -> Result<Object, JsValue> {
+        let a = decode(image_a)?.to_rgba8();
+        let b = decode(image_b)?.to_rgba8();
+        if a.dimensions() != b.dimensions() {
+            return Err(JsValue::from_str("Images must have the same dimensions"));
+        }
+
+        // 35215 is the largest possible YIQ delta
+        let max_delta = 35215.0 * threshold.max(0.0).min(1.0).powi(2);
+        let mut changed = 0u64;
+        let mut output = RgbaImage::new(a.width(), a.height());
+
+        for ((pa, pb), out) in a.pixels().zip(b.pixels()).zip(output.pixels_mut()) {
+            if yiq_delta(*pa, *pb) > max_delta {
+                changed += 1;
+                *out = Rgba([255, 0, 0, 255]);
+            } else {
+                let (y, _, _) = yiq(*pa);
+                let faded = (255.0 + (y - 255.0) * 0.1).round() as u8;
+                *out = Rgba([faded, faded, faded, 255]);
+            }
+        }
+
+        let png = encode(&DynamicImage::ImageRgba8(output), image::ImageOutputFormat::Png)?;
+        let result = Object::new();
+        Reflect::set(&result, &"image".into(), &Uint8Array::from(png.as_slice()))?;
+        // Counts go out as JS numbers; u64 would become a BigInt
+        let total = a.width() as u64 * a.height() as u64;
+        Reflect::set(&result, &"changedPixels".into(), &(changed as f64).into())?;
+        Reflect::set(&result, &"totalPixels".into(), &(total as f64).into())?;
+
+        Ok(result)
+    }
+}
+
+/// Pixel as YIQ after blending over white by its alpha
+fn yiq(px: Rgba<u8>) -> (f32, f32, f32) {
+    let alpha = px[3] as f32 / 255.0;
+    let blend = |v: u8| 255.0 + (v as f32 - 255.0) * alpha;
+    let (r, g, b) = (blend(px[0]), blend(px[1]), blend(px[2]));
+
+    (
+        r * 0.298_895_3 + g * 0.586_622_5 + b * 0.114_482_23,
+        r * 0.595_978 - g * 0.274_176_1 - b * 0.321_801_9,
+        r * 0.211_470_17 - g * 0.522_617_1 + b * 0.311_146_9,
+    )
+}
+
+/// Perceptual color difference as used by pixelmatch
+fn yiq_delta(a: Rgba<u8>, b: Rgba<u8>) -> f32 {
+    if a == b {
+        return 0.0;
+    }
+
+    let (ya, ia, qa) = yiq(a);
+    let (yb, ib, qb) = yiq(b);
+    0.5053 * (ya - yb).powi(2) + 0.299 * (ia - ib).powi(2) + 0.1957 * (qa - qb).powi(2)
+}
+
+/// Mean structural similarity of two equally sized grayscale images
+fn ssim(a: &GrayImage, b: &GrayImage) -> f64 {
+    // Stabilizing constants for 8-bit data: (0.01 * 255)^2 and (0.03 * 255)^2
+    const C1: f32 = 6.5025;
+    const C2: f32 = 58.5225;
+
+    let (width, height) = (a.width() as usize, a.height() as usize);
+    let xa: Vec<f32> = a.pixels().map(|px| px[0] as f32).collect();
+    let xb: Vec<f32> = b.pixels().map(|px| px[0] as f32).collect();
+
+    let mu_a = gaussian_window(&xa, width, height);
+    let mu_b = gaussian_window(&xb, width, height);
+    let aa = gaussian_window(&xa.iter().map(|v| v * v).collect::<Vec<_>>(), width, height);
+    let bb = gaussian_window(&xb.iter().map(|v| v * v).collect::<Vec<_>>(), width, height);
+    let ab = gaussian_window(&xa.iter().zip(&xb).map(|(x, y)| x * y).collect::<Vec<_>>(), width, height);
+
+    let total: f64 = (0..width * height)
+        .map(|i| {
+            let (ma, mb) = (mu_a[i], mu_b[i]);
+            let var_a = aa[i] - ma * ma;
+            let var_b = bb[i] - mb * mb;
+            let covariance = ab[i] - ma * mb;
+            (((2.0 * ma * mb + C1) * (2.0 * covariance + C2))
+                / ((ma * ma + mb * mb + C1) * (var_a + var_b + C2))) as f64
+        })
+        .sum();
+
+    total / (width * height).max(1) as f64
+}
+
+/// Separable 11-tap Gaussian (sigma 1.5) with clamped edges
+fn gaussian_window(values: &[f32], width: usize, height: usize) -> Vec<f32> {
+    const RADIUS: isize = 5;
+    let weights: Vec<f32> = {
+        let raw: Vec<f32> = (-RADIUS..=RADIUS).map(|i| (-(i * i) as f32 / (2.0 * 1.5 * 1.5)).exp()).collect();
+        let sum: f32 = raw.iter().sum();
+        raw.iter().map(|w| w / sum).collect()
+    };
+    let clamp = |v: isize, max: usize| v.max(0).min(max as isize - 1) as usize;
+
+    let mut horizontal = vec![0f32; values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            horizontal[y * width + x] = (-RADIUS..=RADIUS)
+                .map(|k| values[y * width + clamp(x as isize + k, width)] * weights[(k + RADIUS) as usize])
+                .sum();
+        }
+    }
+
+    let mut out = vec![0f32; values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            out[y * width + x] = (-RADIUS..=RADIUS)
+                .map(|k| horizontal[clamp(y as isize + k, height) * width + x] * weights[(k + RADIUS) as usize])
+                .sum();
+        }
+    }
+
+    out
+}
+
+/// Grayscale copy resized to exactly `width` x `height`
+fn small_gray(img: &DynamicImage, width: u32, height: u32) -> GrayImage {
+    img.resize_exact(width, height, FilterType::Triangle).to_luma8()
+}
+
+/// Bit set where the pixel is brighter than the mean of an 8x8 thumbnail
+fn ahash(img: &DynamicImage) -> u64 {
+    let gray = small_gray(img, 8, 8);
+    let mean = gray.pixels().map(|px| px[0] as u32).sum::<u32>() / 64;
+
+    gray.pixels()
+        .enumerate()
+        .fold(0, |hash, (i, px)| if px[0] as u32 > mean { hash | 1 << i } else { hash })
+}
+
+/// Bit set where brightness increases from left to right in a 9x8 thumbnail
+fn dhash(img: &DynamicImage) -> u64 {
+    let gray = small_gray(img, 9, 8);
+    let mut hash = 0u64;
+
+    for y in 0..8 {
+        for x in 0..8 {
+            if gray.get_pixel(x, y)[0] < gray.get_pixel(x + 1, y)[0] {
+                hash |= 1 << (y * 8 + x);
+            }
+        }
+    }
+
+    hash
+}
+
+/// Bit set where a low-frequency AC coefficient of the DCT exceeds their median
+fn phash(img: &DynamicImage) -> u64 {
+    let n = PHASH_SIZE as usize;
+    let gray = small_gray(img, PHASH_SIZE, PHASH_SIZE);
+    let pixels: Vec<f32> = gray.pixels().map(|px| px[0] as f32).collect();
+
+    // Only the top-left 8x8 block of the 2D DCT-II is needed
+    let cosines: Vec<f32> = (0..8)
+        .flat_map(|k| (0..n).map(move |i| (PI / n as f32 * (i as f32 + 0.5) * k as f32).cos()))
+        .collect();
+    let mut rows = vec![0f32; 8 * n];
+    for y in 0..n {
+        for u in 0..8 {
+            rows[u * n + y] = (0..n).map(|x| pixels[y * n + x] * cosines[u * n + x]).sum();
+        }
+    }
+    let mut coefficients = Vec::with_capacity(64);
+    for v in 0..8 {
+        for u in 0..8 {
+            coefficients.push((0..n).map(|y| rows[u * n + y] * cosines[v * n + y]).sum::<f32>());
+        }
+    }
+
+    // The DC term only reflects overall brightness, so it is left out of
+    // both the median and the hash; bit 0 is always clear
+    let mut sorted = coefficients[1..].to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let median = sorted[sorted.len() / 2];
+
+    coefficients
+        .iter()
+        .enumerate()
+        .skip(1)
+        .fold(0, |hash, (i, &c)| if c > median { hash | 1 << i } else { hash })
+}
+
+fn hamming_distance(hash_a: &str, hash_b: &str) -> Result<u32, JsValue> {
+    let parse = |hash: &str| {
+        u64::from_str_radix(hash.trim(), 16).map_err(|_| JsValue::from_str("Invalid hash: expected 16 hex digits"))
+    };
+    if hash_a.trim().len() != hash_b.trim().len() {
+        return Err(JsValue::from_str("Hashes have different lengths"));
+    }
+
+    Ok((parse(hash_a)? ^ parse(hash_b)?).count_ones())
+}
+
+/// Representative of `i`'s set in a union-find forest, halving paths on the way
+fn find_root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(offset: u8) -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(PHASH_SIZE, PHASH_SIZE, |x, y| {
+            image::Luma([offset + ((x * 3 + y * 2) % 97) as u8])
+        }))
+    }
+
+    #[test]
+    fn phash_ignores_overall_brightness() {
+        let hash = phash(&gradient(40));
+
+        assert_eq!(hash & 1, 0);
+        assert_eq!(phash(&gradient(90)), hash);
+    }
+}
@@ -0,0 +1,179 @@
+use std::io::Cursor;
+use wasm_bindgen::prelude::*;
+use image::{DynamicImage, GrayImage, RgbImage, RgbaImage};
+use crate::encoding::encode_as;
+use crate::icc::convert_to_srgb;
+use crate::image_processor::{ImageProcessor, decode};
+use crate::limits::{check_dimensions, png_limits};
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Thumbnail an image too large to decode in full
+    ///
+    /// JPEGs are decoded at the smallest DCT scale (1/2, 1/4 or 1/8) that
+    /// still covers the target size, so a 100 megapixel baseline panorama
+    /// never exists at full resolution (progressive JPEGs still buffer their
+    /// coefficients at full size). Non-interlaced PNGs are streamed row by
+    /// row into a box filter. Other formats fall back to a regular decode
+    /// and are subject to the usual limits.
+    #[wasm_bindgen]
+    pub fn thumbnail_large(&self, image_data: &[u8], max_width: u32, max_height: u32, format: &str, quality: u8) -> Result<Vec<u8>, JsValue> {
+        if max_width == 0 || max_height == 0 {
+            return Err(JsValue::from_str("Thumbnail bounds must be non-zero"));
+        }
+
+        let thumbnail = match image::guess_format(image_data) {
+            Ok(image::ImageFormat::Jpeg) => jpeg_thumbnail(image_data, max_width, max_height)?,
+            Ok(image::ImageFormat::Png) => match png_thumbnail(image_data, max_width, max_height)? {
+                Some(thumbnail) => thumbnail,
+                None => decode(image_data)?.thumbnail(max_width, max_height),
+            },
+            _ => decode(image_data)?.thumbnail(max_width, max_height),
+        };
+
+        encode_as(&convert_to_srgb(image_data, thumbnail), format, quality)
+    }
+}
+
+/// Size of `width` x `height` scaled to fit the bounds, never enlarged
+fn fit(width: u32, height: u32, max_width: u32, max_height: u32) -> (u32, u32) {
+    let ratio = (max_width as f64 / width as f64).min(max_height as f64 / height as f64).min(1.0);
+    (
+        ((width as f64 * ratio).round() as u32).max(1),
+        ((height as f64 * ratio).round() as u32).max(1),
+    )
+}
+
+fn jpeg_thumbnail(image_data: &[u8], max_width: u32, max_height: u32) -> Result<DynamicImage, JsValue> {
+    let to_js = |e: jpeg_decoder::Error| JsValue::from_str(&format!("Failed to load image: {}", e));
+
+    let mut decoder = jpeg_decoder::Decoder::new(Cursor::new(image_data));
+    decoder.read_info().map_err(to_js)?;
+    let info = decoder.info().ok_or_else(|| JsValue::from_str("Failed to load image: missing JPEG header"))?;
+
+    let (target_width, target_height) = fit(info.width as u32, info.height as u32, max_width, max_height);
+    let (width, height) = decoder.scale(target_width as u16, target_height as u16).map_err(to_js)?;
+    let (width, height) = (width as u32, height as u32);
+    check_dimensions(width, height)?;
+
+    let pixels = decoder.decode().map_err(to_js)?;
+    let format = decoder.info().map(|info| info.pixel_format);
+    let scaled = match format {
+        Some(jpeg_decoder::PixelFormat::L8) => GrayImage::from_raw(width, height, pixels).map(DynamicImage::ImageLuma8),
+        Some(jpeg_decoder::PixelFormat::RGB24) => RgbImage::from_raw(width, height, pixels).map(DynamicImage::ImageRgb8),
+        Some(jpeg_decoder::PixelFormat::CMYK32) => {
+            // Adobe CMYK JPEGs store inverted values, so this is a plain product
+            let rgb = pixels
+                .chunks_exact(4)
+                .flat_map(|p| {
+                    let k = p[3] as u32;
+                    [(p[0] as u32 * k / 255) as u8, (p[1] as u32 * k / 255) as u8, (p[2] as u32 * k / 255) as u8]
+                })
+                .collect();
+            RgbImage::from_raw(width, height, rgb).map(DynamicImage::ImageRgb8)
+        }
+        Some(jpeg_decoder::PixelFormat::L16) => {
+            let gray = pixels.chunks_exact(2).map(|p| p[0]).collect();
+            GrayImage::from_raw(width, height, gray).map(DynamicImage::ImageLuma8)
+        }
+        None => None,
+    }
+    .ok_or_else(|| JsValue::from_str("Failed to load image: unexpected JPEG buffer size"))?;
+
+    // DCT scaling only goes down in powers of two; finish the last step
+    Ok(scaled.thumbnail(max_width, max_height))
+}
+
+/// Box-filter a PNG down while decoding, holding one output row of sums
+///
+/// Returns `None` for interlaced files, whose rows don't arrive in order.
+fn png_thumbnail(image_data: &[u8], max_width: u32, max_height: u32) -> Result<Option<DynamicImage>, JsValue> {
+    let to_js = |e: png::DecodingError| JsValue::from_str(&format!("Failed to load image: {}", e));
+
+    let mut decoder = png::Decoder::new_with_limits(image_data, png_limits());
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(to_js)?;
+    if reader.info().interlaced {
+        return Ok(None);
+    }
+
+    let (width, height) = (reader.info().width, reader.info().height);
+    let (out_width, out_height) = fit(width, height, max_width, max_height);
+    let samples = reader.output_color_type().0.samples();
+
+    // Output column of every source column, and how many map to each
+    let columns: Vec<usize> = (0..width as u64).map(|x| (x * out_width as u64 / width as u64) as usize).collect();
+    let mut column_counts = vec![0u32; out_width as usize];
+    for &column in &columns {
+        column_counts[column] += 1;
+    }
+
+    let mut out = RgbaImage::new(out_width, out_height);
+    // A band can cover the whole source image, too many pixels for 32-bit sums
+    let mut sums = vec![0u64; out_width as usize * 4];
+    let mut band_rows = 0;
+    let mut out_y = 0;
+
+    let mut y = 0u64;
+    while let Some(row) = reader.next_row().map_err(to_js)? {
+        let target = (y * out_height as u64 / height as u64) as u32;
+        if target != out_y {
+            flush_band(&mut out, out_y, &mut sums, &column_counts, band_rows);
+            band_rows = 0;
+            out_y = target;
+        }
+
+        for (px, &column) in row.data().chunks_exact(samples).zip(&columns) {
+            let rgba = match samples {
+                1 => [px[0], px[0], px[0], 255],
+                2 => [px[0], px[0], px[0], px[1]],
+                3 => [px[0], px[1], px[2], 255],
+                _ => [px[0], px[1], px[2], px[3]],
+            };
+            for (sum, v) in sums[column * 4..column * 4 + 4].iter_mut().zip(rgba) {
+                *sum += v as u64;
+            }
+        }
+
+        band_rows += 1;
+        y += 1;
+    }
+    flush_band(&mut out, out_y, &mut sums, &column_counts, band_rows);
+
+    Ok(Some(DynamicImage::ImageRgba8(out)))
+}
+
+/// Write the averaged sums of one band of source rows as output row `y`
+fn flush_band(out: &mut RgbaImage, y: u32, sums: &mut [u64], column_counts: &[u32], band_rows: u32) {
+    if band_rows == 0 || y >= out.height() {
+        return;
+    }
+
+    for (x, count) in column_counts.iter().enumerate() {
+        let total = (*count as u64 * band_rows as u64).max(1);
+        let px = out.get_pixel_mut(x as u32, y);
+        for (channel, sum) in px.0.iter_mut().zip(&sums[x * 4..x * 4 + 4]) {
+            *channel = ((sum + total / 2) / total) as u8;
+        }
+    }
+    sums.iter_mut().for_each(|sum| *sum = 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flush_band_averages_huge_bands() {
+        // 100k columns by 100k rows of one value overflows 32-bit totals
+        let (count, rows) = (100_000u32, 100_000u32);
+        let total = count as u64 * rows as u64;
+        let mut sums = vec![200 * total, 10 * total, 0, 255 * total];
+        let mut out = RgbaImage::new(1, 1);
+
+        flush_band(&mut out, 0, &mut sums, &[count], rows);
+
+        assert_eq!(out.get_pixel(0, 0).0, [200, 10, 0, 255]);
+        assert!(sums.iter().all(|&sum| sum == 0));
+    }
+}
@@ -0,0 +1,141 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use image::{DynamicImage, Rgba, RgbaImage};
+use ab_glyph::{Font, FontVec, PxScale, ScaleFont, point};
+use crate::compositing::alpha_blend;
+use crate::image_processor::{ImageProcessor, decode, encode, parse_color};
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Draw text onto an image using a TTF/OTF font
+    ///
+    /// `x`/`y` give the anchor of the first line's top edge; `align` is
+    /// `left`, `center` or `right` relative to `x`. Newlines start new lines.
+    #[wasm_bindgen]
+    pub fn draw_text(&self, image_data: &[u8], text: &str, font_data: &[u8], x: f32, y: f32, size: f32, color: &str, align: &str) -> Result<Vec<u8>, JsValue> {
+        let mut img = decode(image_data)?.to_rgba8();
+        let font = load_font(font_data)?;
+        let color = parse_color(color)?;
+        let align = TextAlign::parse(align)?;
+
+        draw_text(&mut img, &font, text, x, y, size, color, align);
+
+        encode(&DynamicImage::ImageRgba8(img), image::ImageOutputFormat::Png)
+    }
+
+    /// Measure the bounding box of text rendered at the given size
+    #[wasm_bindgen]
+    pub fn measure_text(&self, text: &str, font_data: &[u8], size: f32) -> Result<JsValue, JsValue> {
+        let font = load_font(font_data)?;
+        let (width, height) = measure_text(&font, text, size);
+
+        let result = serde_json::json!({
+            "width": width,
+            "height": height
+        });
+
+        JsValue::from_serde(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+impl TextAlign {
+    pub(crate) fn parse(align: &str) -> Result<Self, JsValue> {
+        match align.to_lowercase().as_str() {
+            "left" | "" => Ok(TextAlign::Left),
+            "center" => Ok(TextAlign::Center),
+            "right" => Ok(TextAlign::Right),
+            _ => Err(JsValue::from_str("Alignment must be left, center or right")),
+        }
+    }
+}
+
+pub(crate) fn load_font(font_data: &[u8]) -> Result<FontVec, JsValue> {
+    FontVec::try_from_vec(font_data.to_vec())
+        .map_err(|e| JsValue::from_str(&format!("Invalid font: {}", e)))
+}
+
+/// Width of a single line of text in pixels, including kerning
+fn line_width(font: &FontVec, line: &str, size: f32) -> f32 {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let mut width = 0.0;
+    let mut previous = None;
+
+    for c in line.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(prev) = previous {
+            width += scaled.kern(prev, id);
+        }
+        width += scaled.h_advance(id);
+        previous = Some(id);
+    }
+
+    width
+}
+
+/// Bounding box of (possibly multi-line) text
+pub(crate) fn measure_text(font: &FontVec, text: &str, size: f32) -> (u32, u32) {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let line_height = scaled.height() + scaled.line_gap();
+    let lines: Vec<&str> = text.lines().collect();
+
+    let width = lines.iter().map(|line| line_width(font, line, size)).fold(0.0, f32::max);
+    let height = line_height * lines.len().max(1) as f32;
+
+    (width.ceil() as u32, height.ceil() as u32)
+}
+
+/// Rasterize text onto an RGBA buffer with anti-aliased coverage blending
+pub(crate) fn draw_text(img: &mut RgbaImage, font: &FontVec, text: &str, x: f32, y: f32, size: f32, color: Rgba<u8>, align: TextAlign) {
+    let scale = PxScale::from(size);
+    let scaled = font.as_scaled(scale);
+    let line_height = scaled.height() + scaled.line_gap();
+    let (width, height) = img.dimensions();
+
+    for (index, line) in text.lines().enumerate() {
+        let origin_x = match align {
+            TextAlign::Left => x,
+            TextAlign::Center => x - line_width(font, line, size) / 2.0,
+            TextAlign::Right => x - line_width(font, line, size),
+        };
+        let baseline = y + scaled.ascent() + line_height * index as f32;
+
+        let mut caret = origin_x;
+        let mut previous = None;
+
+        for c in line.chars() {
+            let id = scaled.glyph_id(c);
+            if let Some(prev) = previous {
+                caret += scaled.kern(prev, id);
+            }
+
+            let glyph = id.with_scale_and_position(scale, point(caret, baseline));
+            caret += scaled.h_advance(id);
+            previous = Some(id);
+
+            let outlined = match font.outline_glyph(glyph) {
+                Some(outlined) => outlined,
+                None => continue,
+            };
+
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i64 + gx as i64;
+                let py = bounds.min.y as i64 + gy as i64;
+                if px < 0 || py < 0 || px >= width as i64 || py >= height as i64 {
+                    return;
+                }
+
+                let dst = img.get_pixel_mut(px as u32, py as u32);
+                *dst = alpha_blend(*dst, color, coverage);
+            });
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use std::f32::consts::PI;
+use wasm_bindgen::prelude::*;
+use crate::image_processor::{ImageProcessor, decode, encode, from_rgba};
+
+/// ThumbHash only looks at a tiny version of the image
+const MAX_ENCODE_SIZE: u32 = 100;
+
+/// Longest side of decoded previews
+const PREVIEW_SIZE: f32 = 32.0;
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Compute the ThumbHash of an image (about 25 bytes, alpha-aware)
+    #[wasm_bindgen]
+    pub fn thumbhash_encode(&self, image_data: &[u8]) -> Result<Vec<u8>, JsValue> {
+        let rgba = decode(image_data)?.thumbnail(MAX_ENCODE_SIZE, MAX_ENCODE_SIZE).to_rgba8();
+        Ok(rgba_to_thumbhash(rgba.width() as usize, rgba.height() as usize, rgba.as_raw()))
+    }
+
+    /// Render a ThumbHash as a small PNG preview (32px on the long side)
+    #[wasm_bindgen]
+    pub fn thumbhash_decode(&self, hash: &[u8]) -> Result<Vec<u8>, JsValue> {
+        let (width, height, rgba) = thumbhash_to_rgba(hash)?;
+        encode(&from_rgba(rgba, width, height)?, image::ImageOutputFormat::Png)
+    }
+}
+
+/// Port of the reference ThumbHash encoder
+fn rgba_to_thumbhash(w: usize, h: usize, rgba: &[u8]) -> Vec<u8> {
+    // Average color, weighted by alpha
+    let (mut avg_r, mut avg_g, mut avg_b, mut avg_a) = (0f32, 0f32, 0f32, 0f32);
+    for px in rgba.chunks_exact(4) {
+        let alpha = px[3] as f32 / 255.0;
+        avg_r += alpha / 255.0 * px[0] as f32;
+        avg_g += alpha / 255.0 * px[1] as f32;
+        avg_b += alpha / 255.0 * px[2] as f32;
+        avg_a += alpha;
+    }
+    if avg_a > 0.0 {
+        avg_r /= avg_a;
+        avg_g /= avg_a;
+        avg_b /= avg_a;
+    }
+
+    let has_alpha = avg_a < (w * h) as f32;
+    let l_limit = if has_alpha { 5.0 } else { 7.0 };
+    let longest = w.max(h) as f32;
+    let lx = ((l_limit * w as f32 / longest).round() as usize).max(1);
+    let ly = ((l_limit * h as f32 / longest).round() as usize).max(1);
+
+    // Convert to LPQA, compositing transparent pixels over the average color
+    let mut l = Vec::with_capacity(w * h);
+    let mut p = Vec::with_capacity(w * h);
+    let mut q = Vec::with_capacity(w * h);
+    let mut a = Vec::with_capacity(w * h);
+    for px in rgba.chunks_exact(4) {
+        let alpha = px[3] as f32 / 255.0;
+        let r = avg_r * (1.0 - alpha) + alpha / 255.0 * px[0] as f32;
+        let g = avg_g * (1.0 - alpha) + alpha / 255.0 * px[1] as f32;
+        let b = avg_b * (1.0 - alpha) + alpha / 255.0 * px[2] as f32;
+        l.push((r + g + b) / 3.0);
+        p.push((r + g) / 2.0 - b);
+        q.push(r - g);
+        a.push(alpha);
+    }
+
+    let (l_dc, l_ac, l_scale) = encode_channel(&l, w, h, lx.max(3), ly.max(3));
+    let (p_dc, p_ac, p_scale) = encode_channel(&p, w, h, 3, 3);
+    let (q_dc, q_ac, q_scale) = encode_channel(&q, w, h, 3, 3);
+    let alpha_channel = if has_alpha { Some(encode_channel(&a, w, h, 5, 5)) } else { None };
+
+    // Constant terms
+    let is_landscape = w > h;
+    let header24 = (63.0 * l_dc).round() as u32
+        | ((31.5 + 31.5 * p_dc).round() as u32) << 6
+        | ((31.5 + 31.5 * q_dc).round() as u32) << 12
+        | ((31.0 * l_scale).round() as u32) << 18
+        | (has_alpha as u32) << 23;
+    let header16 = (if is_landscape { ly } else { lx }) as u32
+        | ((63.0 * p_scale).round() as u32) << 3
+        | ((63.0 * q_scale).round() as u32) << 9
+        | (is_landscape as u32) << 15;
+
+    let mut hash = vec![
+        (header24 & 255) as u8,
+        ((header24 >> 8) & 255) as u8,
+        (header24 >> 16) as u8,
+        (header16 & 255) as u8,
+        (header16 >> 8) as u8,
+    ];
+    if let Some((a_dc, _, a_scale)) = &alpha_channel {
+        hash.push((15.0 * a_dc).round() as u8 | ((15.0 * a_scale).round() as u8) << 4);
+    }
+
+    // Varying terms, packed as nibbles
+    let mut acs = vec![l_ac, p_ac, q_ac];
+    if let Some((_, a_ac, _)) = alpha_channel {
+        acs.push(a_ac);
+    }
+    let ac_start = hash.len();
+    for (ac_index, f) in acs.iter().flatten().enumerate() {
+        let slot = ac_start + (ac_index >> 1);
+        if slot >= hash.len() {
+            hash.push(0);
+        }
+        hash[slot] |= ((15.0 * f).round() as u8) << ((ac_index & 1) << 2);
+    }
+
+    hash
+}
+
+/// DCT of one channel into a DC term, normalized AC terms and their scale
+fn encode_channel(channel: &[f32], w: usize, h: usize, nx: usize, ny: usize) -> (f32, Vec<f32>, f32) {
+    let mut dc = 0.0;
+    let mut ac = Vec::new();
+    let mut scale = 0f32;
+    let mut fx = vec![0f32; w];
+
+    for cy in 0..ny {
+        let mut cx = 0;
+        while cx * ny < nx * (ny - cy) {
+            for (x, f) in fx.iter_mut().enumerate() {
+                *f = (PI / w as f32 * cx as f32 * (x as f32 + 0.5)).cos();
+            }
+
+            let mut f = 0.0;
+            for y in 0..h {
+                let fy = (PI / h as f32 * cy as f32 * (y as f32 + 0.5)).cos();
+                for x in 0..w {
+                    f += channel[x + y * w] * fx[x] * fy;
+                }
+            }
+            f /= (w * h) as f32;
+
+            if cx > 0 || cy > 0 {
+                ac.push(f);
+                scale = scale.max(f.abs());
+            } else {
+                dc = f;
+            }
+            cx += 1;
+        }
+    }
+
+    if scale > 0.0 {
+        for f in ac.iter_mut() {
+            *f = 0.5 + 0.5 / scale * *f;
+        }
+    }
+
+    (dc, ac, scale)
+}
+
+/// Port of the reference ThumbHash decoder
+fn thumbhash_to_rgba(hash: &[u8]) -> Result<(u32, u32, Vec<u8>), JsValue> {
+    if hash.len() < 5 {
+        return Err(JsValue::from_str("Invalid ThumbHash: too short"));
+    }
+
+    let header24 = hash[0] as u32 | (hash[1] as u32) << 8 | (hash[2] as u32) << 16;
+    let header16 = hash[3] as u32 | (hash[4] as u32) << 8;
+    let l_dc = (header24 & 63) as f32 / 63.0;
+    let p_dc = ((header24 >> 6) & 63) as f32 / 31.5 - 1.0;
+    let q_dc = ((header24 >> 12) & 63) as f32 / 31.5 - 1.0;
+    let l_scale = ((header24 >> 18) & 31) as f32 / 31.0;
+    let has_alpha = (header24 >> 23) != 0;
+    let p_scale = ((header16 >> 3) & 63) as f32 / 63.0;
+    let q_scale = ((header16 >> 9) & 63) as f32 / 63.0;
+    let is_landscape = (header16 >> 15) != 0;
+
+    let l_limit = if has_alpha { 5 } else { 7 };
+    let lx = (if is_landscape { l_limit } else { (header16 & 7) as usize }).max(3);
+    let ly = (if is_landscape { (header16 & 7) as usize } else { l_limit }).max(3);
+
+    if has_alpha && hash.len() < 6 {
+        return Err(JsValue::from_str("Invalid ThumbHash: missing alpha"));
+    }
+    let a_dc = if has_alpha { (hash[5] & 15) as f32 / 15.0 } else { 1.0 };
+    let a_scale = if has_alpha { (hash[5] >> 4) as f32 / 15.0 } else { 0.0 };
+
+    let ac_start = if has_alpha { 6 } else { 5 };
+    let mut ac_index = 0;
+    let mut decode_channel = |nx: usize, ny: usize, scale: f32| {
+        let mut ac = Vec::new();
+        for cy in 0..ny {
+            let mut cx = if cy > 0 { 0 } else { 1 };
+            while cx * ny < nx * (ny - cy) {
+                // Tolerate truncated hashes by treating missing terms as mid-range
+                let byte = hash.get(ac_start + (ac_index >> 1)).copied().unwrap_or(0x77);
+                let nibble = (byte >> ((ac_index & 1) << 2)) & 15;
+                ac.push((nibble as f32 / 7.5 - 1.0) * scale);
+                ac_index += 1;
+                cx += 1;
+            }
+        }
+        ac
+    };
+    let l_ac = decode_channel(lx, ly, l_scale);
+    let p_ac = decode_channel(3, 3, p_scale * 1.25);
+    let q_ac = decode_channel(3, 3, q_scale * 1.25);
+    let a_ac = if has_alpha { decode_channel(5, 5, a_scale) } else { Vec::new() };
+
+    // Approximate aspect ratio from the stored component counts
+    let ratio = {
+        let lx = if is_landscape { l_limit } else { (header16 & 7) as usize };
+        let ly = if is_landscape { (header16 & 7) as usize } else { l_limit };
+        lx as f32 / ly.max(1) as f32
+    };
+    let w = (if ratio > 1.0 { PREVIEW_SIZE } else { PREVIEW_SIZE * ratio }).round().max(1.0) as usize;
+    let h = (if ratio > 1.0 { PREVIEW_SIZE / ratio } else { PREVIEW_SIZE }).round().max(1.0) as usize;
+
+    let mut rgba = Vec::with_capacity(w * h * 4);
+    let n = lx.max(if has_alpha { 5 } else { 3 });
+    let m = ly.max(if has_alpha { 5 } else { 3 });
+    let mut fx = vec![0f32; n];
+    let mut fy = vec![0f32; m];
+
+    for y in 0..h {
+        for x in 0..w {
+            let (mut l, mut p, mut q, mut a) = (l_dc, p_dc, q_dc, a_dc);
+
+            for (cx, f) in fx.iter_mut().enumerate() {
+                *f = (PI / w as f32 * (x as f32 + 0.5) * cx as f32).cos();
+            }
+            for (cy, f) in fy.iter_mut().enumerate() {
+                *f = (PI / h as f32 * (y as f32 + 0.5) * cy as f32).cos();
+            }
+
+            let mut j = 0;
+            for cy in 0..ly {
+                let fy2 = fy[cy] * 2.0;
+                let mut cx = if cy > 0 { 0 } else { 1 };
+                while cx * ly < lx * (ly - cy) {
+                    l += l_ac[j] * fx[cx] * fy2;
+                    j += 1;
+                    cx += 1;
+                }
+            }
+
+            let mut j = 0;
+            for cy in 0..3 {
+                let fy2 = fy[cy] * 2.0;
+                for cx in (if cy > 0 { 0 } else { 1 })..3 - cy {
+                    let f = fx[cx] * fy2;
+                    p += p_ac[j] * f;
+                    q += q_ac[j] * f;
+                    j += 1;
+                }
+            }
+
+            if has_alpha {
+                let mut j = 0;
+                for cy in 0..5 {
+                    let fy2 = fy[cy] * 2.0;
+                    for cx in (if cy > 0 { 0 } else { 1 })..5 - cy {
+                        a += a_ac[j] * fx[cx] * fy2;
+                        j += 1;
+                    }
+                }
+            }
+
+            let b = l - 2.0 / 3.0 * p;
+            let r = (3.0 * l - b + q) / 2.0;
+            let g = r - q;
+            for v in [r, g, b, a] {
+                rgba.push((255.0 * v.min(1.0)).max(0.0) as u8);
+            }
+        }
+    }
+
+    Ok((w as u32, h as u32, rgba))
+}
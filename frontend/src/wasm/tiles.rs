@@ -0,0 +1,134 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Function, Uint8Array};
+use image::{DynamicImage, GenericImageView, imageops::FilterType};
+use crate::encoding::encode_as;
+use crate::image_processor::{ImageProcessor, decode};
+use crate::limits::check_dimensions;
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Slice an image into a Deep Zoom (DZI) tile pyramid
+    ///
+    /// Tiles are handed to `on_tile(level, column, row, bytes)` as soon as they
+    /// are encoded, so only the current level is ever held in memory. Levels
+    /// are produced from full resolution downwards, each one halved from the
+    /// previous. Returns the `.dzi` descriptor XML.
+    ///
+    /// The full-resolution level is decoded in one piece, so the source is
+    /// held to the `set_limits` width, height and pixel limits like any other
+    /// decode; a larger image is rejected before tiling starts.
+    #[wasm_bindgen]
+    pub fn generate_tile_pyramid(
+        &self,
+        image_data: &[u8],
+        tile_size: u32,
+        overlap: u32,
+        format: &str,
+        quality: u8,
+        on_tile: &Function,
+    ) -> Result<String, JsValue> {
+        if tile_size == 0 {
+            return Err(JsValue::from_str("Tile size must be non-zero"));
+        }
+        if overlap >= tile_size {
+            return Err(JsValue::from_str("Overlap must be smaller than the tile size"));
+        }
+        let extension = match format.to_lowercase().as_str() {
+            "jpeg" | "jpg" => "jpg",
+            "png" => "png",
+            "webp" => "webp",
+            _ => return Err(JsValue::from_str("Unsupported tile format")),
+        };
+
+        let mut level_image = decode(image_data)?;
+        let (width, height) = level_image.dimensions();
+        // Also covers formats whose header `decode` can't read ahead of time
+        check_dimensions(width, height)?;
+        let max_level = level_count(width, height) - 1;
+
+        for level in (0..=max_level).rev() {
+            let (level_width, level_height) = level_image.dimensions();
+            let columns = level_width.div_ceil(tile_size);
+            let rows = level_height.div_ceil(tile_size);
+
+            for row in 0..rows {
+                for column in 0..columns {
+                    let (x, tile_width) = tile_span(column, tile_size, overlap, level_width);
+                    let (y, tile_height) = tile_span(row, tile_size, overlap, level_height);
+
+                    let tile = level_image.crop_imm(x, y, tile_width, tile_height);
+                    let bytes = encode_as(&tile, format, quality)?;
+
+                    let args = js_sys::Array::of4(
+                        &level.into(),
+                        &column.into(),
+                        &row.into(),
+                        &Uint8Array::from(bytes.as_slice()),
+                    );
+                    on_tile.apply(&JsValue::NULL, &args)?;
+                }
+            }
+
+            if level > 0 {
+                level_image = half_size(&level_image);
+            }
+        }
+
+        Ok(format!(
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                r#"<Image xmlns="http://schemas.microsoft.com/deepzoom/2008" Format="{}" Overlap="{}" TileSize="{}">"#,
+                r#"<Size Width="{}" Height="{}"/></Image>"#
+            ),
+            extension, overlap, tile_size, width, height
+        ))
+    }
+}
+
+/// Number of DZI levels: level 0 is 1x1 and the last is full resolution
+fn level_count(width: u32, height: u32) -> u32 {
+    let longest = width.max(height).max(1);
+    32 - (longest - 1).leading_zeros() + 1
+}
+
+/// Offset and extent of tile `index` along one axis, including overlap on
+/// each interior edge
+fn tile_span(index: u32, tile_size: u32, overlap: u32, extent: u32) -> (u32, u32) {
+    // Saturating: `tile_size` comes from the caller and may be near u32::MAX
+    let start = index.saturating_mul(tile_size).saturating_sub(if index > 0 { overlap } else { 0 });
+    let end = (index + 1).saturating_mul(tile_size).saturating_add(overlap).min(extent);
+    (start, end - start)
+}
+
+/// Next pyramid level, rounding odd dimensions up as DZI expects
+fn half_size(img: &DynamicImage) -> DynamicImage {
+    let (width, height) = img.dimensions();
+    let width = width.div_ceil(2).max(1);
+    let height = height.div_ceil(2).max(1);
+    img.resize_exact(width, height, FilterType::Triangle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_count_reaches_one_pixel() {
+        assert_eq!(level_count(1, 1), 1);
+        assert_eq!(level_count(2, 1), 2);
+        assert_eq!(level_count(1000, 600), 11);
+        assert_eq!(level_count(1024, 1024), 11);
+    }
+
+    #[test]
+    fn tile_span_adds_interior_overlap() {
+        assert_eq!(tile_span(0, 256, 1, 600), (0, 257));
+        assert_eq!(tile_span(1, 256, 1, 600), (255, 258));
+        assert_eq!(tile_span(2, 256, 1, 600), (511, 89));
+    }
+
+    #[test]
+    fn tile_span_saturates_huge_tiles() {
+        assert_eq!(tile_span(0, u32::MAX, u32::MAX - 1, 600), (0, 600));
+    }
+}
@@ -0,0 +1,426 @@
+use wasm_bindgen::prelude::*;
+use gloo_utils::format::JsValueSerdeExt;
+use js_sys::{Object, Reflect, Uint8Array};
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage, imageops::{self, FilterType}};
+use crate::filters::sobel_gradients;
+use crate::image_processor::{ImageProcessor, decode, encode, parse_color};
+use crate::limits::{LimitExceeded, check_dimensions};
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Rotate clockwise by any angle with bilinear resampling
+    ///
+    /// The canvas grows to fit the rotated image; uncovered corners are
+    /// filled with `background_color` (hex or `transparent`).
+    #[wasm_bindgen]
+    pub fn rotate_arbitrary(&self, image_data: &[u8], degrees: f32, background_color: &str) -> Result<Vec<u8>, JsValue> {
+        let background = parse_color(background_color)?;
+        let rgba = decode(image_data)?.to_rgba8();
+        let (width, height) = rgba.dimensions();
+
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let out_width = (width as f32 * cos.abs() + height as f32 * sin.abs()).round().max(1.0) as u32;
+        let out_height = (width as f32 * sin.abs() + height as f32 * cos.abs()).round().max(1.0) as u32;
+        check_dimensions(out_width, out_height)?;
+
+        let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+        let (ocx, ocy) = (out_width as f32 / 2.0, out_height as f32 / 2.0);
+
+        let rotated = warp(&rgba, out_width, out_height, background, |x, y| {
+            // Inverse rotation maps each output pixel back into the source
+            let (dx, dy) = (x - ocx, y - ocy);
+            (dx * cos + dy * sin + cx, -dx * sin + dy * cos + cy)
+        });
+
+        encode(&DynamicImage::ImageRgba8(rotated), image::ImageOutputFormat::Png)
+    }
+
+    /// Shear by angles in degrees, like CSS `skew(shear_x, shear_y)`
+    ///
+    /// Positive `shear_x` shifts lower rows to the right and positive
+    /// `shear_y` shifts columns further right downward. The
+    /// canvas grows to fit; uncovered areas are filled with `background`
+    /// (hex or `transparent`). Small negative angles straighten scans that
+    /// were sheared by the scanner.
+    #[wasm_bindgen]
+    pub fn skew(&self, image_data: &[u8], shear_x: f32, shear_y: f32, background: &str) -> Result<Vec<u8>, JsValue> {
+        if !(shear_x.abs() < 89.0 && shear_y.abs() < 89.0) {
+            return Err(JsValue::from_str("Shear angles must be between -89 and 89 degrees"));
+        }
+        let (kx, ky) = (shear_x.to_radians().tan(), shear_y.to_radians().tan());
+        let det = 1.0 - kx * ky;
+        if det.abs() < 0.01 {
+            return Err(JsValue::from_str("Shear angles collapse the image to a line"));
+        }
+
+        let background = parse_color(background)?;
+        let rgba = decode(image_data)?.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+
+        // Sheared corners relative to the center give the canvas size
+        let corners = [(-cx, -cy), (cx, -cy), (-cx, cy), (cx, cy)].map(|(x, y)| (x + kx * y, ky * x + y));
+        let (min_x, max_x) = corners.iter().fold((f32::MAX, f32::MIN), |(lo, hi), &(x, _)| (lo.min(x), hi.max(x)));
+        let (min_y, max_y) = corners.iter().fold((f32::MAX, f32::MIN), |(lo, hi), &(_, y)| (lo.min(y), hi.max(y)));
+        let out_width = (max_x - min_x).round().max(1.0) as u32;
+        let out_height = (max_y - min_y).round().max(1.0) as u32;
+        check_dimensions(out_width, out_height)?;
+        let (ocx, ocy) = (out_width as f32 / 2.0, out_height as f32 / 2.0);
+
+        let skewed = warp(&rgba, out_width, out_height, background, |x, y| {
+            // Inverse of [[1, kx], [ky, 1]]
+            let (dx, dy) = (x - ocx, y - ocy);
+            ((dx - kx * dy) / det + cx, (dy - ky * dx) / det + cy)
+        });
+
+        encode(&DynamicImage::ImageRgba8(skewed), image::ImageOutputFormat::Png)
+    }
+
+    /// Remove radial lens distortion with the Brown-Conrady model
+    ///
+    /// Each output point at radius `r` samples the source at
+    /// `r * (1 + k1 * r^2 + k2 * r^4)`, with `r` normalized so the corners
+    /// are at 1. Barrel distortion (wide-angle lenses, webcams) has
+    /// negative coefficients, typically `k1` between -0.3 and -0.05;
+    /// pincushion distortion has positive ones. Pincushion correction
+    /// pulls the edges in and leaves transparent corners.
+    #[wasm_bindgen]
+    pub fn correct_distortion(&self, image_data: &[u8], k1: f32, k2: f32) -> Result<Vec<u8>, JsValue> {
+        if !k1.is_finite() || !k2.is_finite() {
+            return Err(JsValue::from_str("Distortion coefficients must be finite"));
+        }
+
+        let rgba = decode(image_data)?.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+        let radius = (cx * cx + cy * cy).sqrt().max(1.0);
+
+        let corrected = warp(&rgba, width, height, Rgba([0, 0, 0, 0]), |x, y| {
+            let (dx, dy) = ((x - cx) / radius, (y - cy) / radius);
+            let r2 = dx * dx + dy * dy;
+            let factor = 1.0 + k1 * r2 + k2 * r2 * r2;
+            (cx + dx * factor * radius, cy + dy * factor * radius)
+        });
+
+        encode(&DynamicImage::ImageRgba8(corrected), image::ImageOutputFormat::Png)
+    }
+
+    /// Crop to the target aspect ratio around the most salient region, then
+    /// resize to exactly `target_width` x `target_height`
+    #[wasm_bindgen]
+    pub fn smart_crop(&self, image_data: &[u8], target_width: u32, target_height: u32) -> Result<Vec<u8>, JsValue> {
+        let mut img = decode(image_data)?;
+        let (x, y, width, height) = smart_crop_window(&img, target_width, target_height)?;
+
+        let cropped = img.crop(x, y, width, height).resize_exact(target_width, target_height, FilterType::Lanczos3);
+        encode(&cropped, image::ImageOutputFormat::Png)
+    }
+
+    /// Compute the window `smart_crop` would use, as `{ x, y, width, height }`
+    #[wasm_bindgen]
+    pub fn smart_crop_box(&self, image_data: &[u8], target_width: u32, target_height: u32) -> Result<JsValue, JsValue> {
+        let img = decode(image_data)?;
+        let (x, y, width, height) = smart_crop_window(&img, target_width, target_height)?;
+
+        let result = serde_json::json!({
+            "x": x,
+            "y": y,
+            "width": width,
+            "height": height
+        });
+
+        JsValue::from_serde(&result)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Crop away uniform or fully transparent borders
+    ///
+    /// The border color is taken from the top-left pixel; pixels within
+    /// `tolerance` (0-255 per channel) of it, or fully transparent ones
+    /// when it is transparent, count as border. Returns `{ image, x, y,
+    /// width, height }` with the crop box in source pixels. An image that
+    /// is all border comes back unchanged.
+    #[wasm_bindgen]
+    pub fn auto_trim(&self, image_data: &[u8], tolerance: u8) -> Result<Object, JsValue> {
+        let rgba = decode(image_data)?.to_rgba8();
+        let (x, y, width, height) = trim_box(&rgba, tolerance);
+        let trimmed = imageops::crop_imm(&rgba, x, y, width, height).to_image();
+
+        let png = encode(&DynamicImage::ImageRgba8(trimmed), image::ImageOutputFormat::Png)?;
+        let result = Object::new();
+        Reflect::set(&result, &"image".into(), &Uint8Array::from(png.as_slice()))?;
+        Reflect::set(&result, &"x".into(), &x.into())?;
+        Reflect::set(&result, &"y".into(), &y.into())?;
+        Reflect::set(&result, &"width".into(), &width.into())?;
+        Reflect::set(&result, &"height".into(), &height.into())?;
+
+        Ok(result)
+    }
+
+    /// Grow the canvas by the given number of pixels on each side, filling
+    /// the new area with `color` (hex or `transparent`)
+    #[wasm_bindgen]
+    pub fn extend_canvas(&self, image_data: &[u8], top: u32, right: u32, bottom: u32, left: u32, color: &str) -> Result<Vec<u8>, JsValue> {
+        let color = parse_color(color)?;
+        let rgba = decode(image_data)?.to_rgba8();
+        let canvas = extend(&rgba, top, right, bottom, left, color)?;
+
+        encode(&DynamicImage::ImageRgba8(canvas), image::ImageOutputFormat::Png)
+    }
+
+    /// Frame the image with a solid border of `width` pixels
+    ///
+    /// A non-zero `radius` rounds the outer corners of the frame, leaving
+    /// anti-aliased transparency outside them.
+    #[wasm_bindgen]
+    pub fn add_border(&self, image_data: &[u8], width: u32, color: &str, radius: u32) -> Result<Vec<u8>, JsValue> {
+        let color = parse_color(color)?;
+        let rgba = decode(image_data)?.to_rgba8();
+        let mut canvas = extend(&rgba, width, width, width, width, color)?;
+
+        if radius > 0 {
+            round_alpha(&mut canvas, radius as f32);
+        }
+
+        encode(&DynamicImage::ImageRgba8(canvas), image::ImageOutputFormat::Png)
+    }
+
+    /// Round the corners to anti-aliased transparency
+    #[wasm_bindgen]
+    pub fn round_corners(&self, image_data: &[u8], radius: u32) -> Result<Vec<u8>, JsValue> {
+        let mut rgba = decode(image_data)?.to_rgba8();
+        round_alpha(&mut rgba, radius as f32);
+
+        encode(&DynamicImage::ImageRgba8(rgba), image::ImageOutputFormat::Png)
+    }
+
+    /// Crop the largest centered circle, e.g. for avatars
+    #[wasm_bindgen]
+    pub fn circle_crop(&self, image_data: &[u8]) -> Result<Vec<u8>, JsValue> {
+        let img = decode(image_data)?;
+        let (width, height) = img.dimensions();
+        let side = width.min(height);
+
+        let mut square = img.crop_imm((width - side) / 2, (height - side) / 2, side, side).to_rgba8();
+        round_alpha(&mut square, side as f32 / 2.0);
+
+        encode(&DynamicImage::ImageRgba8(square), image::ImageOutputFormat::Png)
+    }
+}
+
+/// Smallest box holding every pixel that differs from the border color
+fn trim_box(rgba: &RgbaImage, tolerance: u8) -> (u32, u32, u32, u32) {
+    let (width, height) = rgba.dimensions();
+    let border = *rgba.get_pixel(0, 0);
+    let is_border = |px: &Rgba<u8>| {
+        if border[3] == 0 {
+            px[3] == 0
+        } else {
+            px.0.iter().zip(border.0).all(|(&a, b)| a.abs_diff(b) <= tolerance)
+        }
+    };
+
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (width, height, 0, 0);
+    for (x, y, px) in rgba.enumerate_pixels() {
+        if !is_border(px) {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+    }
+
+    if min_x > max_x {
+        return (0, 0, width, height);
+    }
+    (min_x, min_y, max_x - min_x + 1, max_y - min_y + 1)
+}
+
+/// Copy `src` onto a larger canvas filled with `color`
+fn extend(src: &RgbaImage, top: u32, right: u32, bottom: u32, left: u32, color: Rgba<u8>) -> Result<RgbaImage, LimitExceeded> {
+    let (width, height) = src.dimensions();
+    // Saturating, so padding past u32 fails the size limits instead of wrapping
+    let out_width = width.saturating_add(left).saturating_add(right);
+    let out_height = height.saturating_add(top).saturating_add(bottom);
+    check_dimensions(out_width, out_height)?;
+
+    let mut canvas = RgbaImage::from_pixel(out_width, out_height, color);
+    imageops::replace(&mut canvas, src, left as i64, top as i64);
+    Ok(canvas)
+}
+
+/// Fade alpha outside a rounded rectangle covering the whole image
+fn round_alpha(rgba: &mut RgbaImage, radius: f32) {
+    let (width, height) = rgba.dimensions();
+    let radius = radius.min(width as f32 / 2.0).min(height as f32 / 2.0);
+
+    for (x, y, px) in rgba.enumerate_pixels_mut() {
+        let coverage = rounded_rect_coverage(x as f32 + 0.5, y as f32 + 0.5, width as f32, height as f32, radius);
+        if coverage < 1.0 {
+            px[3] = (px[3] as f32 * coverage).round() as u8;
+        }
+    }
+}
+
+/// Approximate fraction of the pixel centered at (`x`, `y`) that lies inside
+/// a `width` x `height` rectangle with corners of `radius`
+fn rounded_rect_coverage(x: f32, y: f32, width: f32, height: f32, radius: f32) -> f32 {
+    // Nearest point on the inner rectangle the corner arcs are centered on
+    let cx = x.max(radius).min(width - radius);
+    let cy = y.max(radius).min(height - radius);
+    let distance = ((x - cx).powi(2) + (y - cy).powi(2)).sqrt();
+
+    (radius - distance + 0.5).max(0.0).min(1.0)
+}
+
+/// Longest side of the downscaled copy used to score crop windows
+const SALIENCY_SIZE: u32 = 256;
+
+/// Pick the largest window with the target aspect ratio that maximizes
+/// saliency (edge energy plus skin-tone and saturation boosts), with a mild
+/// preference for the center
+fn smart_crop_window(img: &DynamicImage, target_width: u32, target_height: u32) -> Result<(u32, u32, u32, u32), JsValue> {
+    if target_width == 0 || target_height == 0 {
+        return Err(JsValue::from_str("Target dimensions must be non-zero"));
+    }
+    check_dimensions(target_width, target_height)?;
+
+    let (width, height) = img.dimensions();
+    let target_aspect = target_width as f64 / target_height as f64;
+    let (crop_width, crop_height) = if width as f64 / height as f64 > target_aspect {
+        (((height as f64 * target_aspect).round() as u32).max(1).min(width), height)
+    } else {
+        (width, ((width as f64 / target_aspect).round() as u32).max(1).min(height))
+    };
+
+    if crop_width == width && crop_height == height {
+        return Ok((0, 0, width, height));
+    }
+
+    // Score on a small copy; `scale` maps small coordinates back to full size
+    let small = img.thumbnail(SALIENCY_SIZE, SALIENCY_SIZE).to_rgba8();
+    let (sw, sh) = small.dimensions();
+    let scale = width as f64 / sw as f64;
+
+    let gray = DynamicImage::ImageRgba8(small.clone()).to_luma8();
+    let (gx, gy) = sobel_gradients(&gray);
+
+    let mut saliency = vec![0f64; (sw * sh) as usize];
+    for (i, px) in small.pixels().enumerate() {
+        let (r, g, b) = (px[0] as f64, px[1] as f64, px[2] as f64);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+        let skin = r > 95.0 && g > 40.0 && b > 20.0 && r > g && r > b && (r - g) > 15.0;
+
+        let edge = ((gx[i] * gx[i] + gy[i] * gy[i]) as f64).sqrt() / 4.0;
+        saliency[i] = (edge + saturation * 40.0 + if skin { 60.0 } else { 0.0 }) * px[3] as f64 / 255.0;
+    }
+
+    // Summed-area table for constant-time window sums
+    let stride = (sw + 1) as usize;
+    let mut integral = vec![0f64; stride * (sh + 1) as usize];
+    for y in 0..sh as usize {
+        let mut row = 0.0;
+        for x in 0..sw as usize {
+            row += saliency[y * sw as usize + x];
+            integral[(y + 1) * stride + x + 1] = integral[y * stride + x + 1] + row;
+        }
+    }
+    let window_sum = |x: usize, y: usize, w: usize, h: usize| {
+        integral[(y + h) * stride + x + w] - integral[y * stride + x + w] - integral[(y + h) * stride + x] + integral[y * stride + x]
+    };
+
+    let ww = ((crop_width as f64 / scale).round() as usize).max(1).min(sw as usize);
+    let wh = ((crop_height as f64 / scale).round() as usize).max(1).min(sh as usize);
+    let (max_x, max_y) = (sw as usize - ww, sh as usize - wh);
+
+    let mut best = (0usize, 0usize);
+    let mut best_score = f64::MIN;
+    for y in 0..=max_y {
+        for x in 0..=max_x {
+            // Up to 10% penalty for drifting from center
+            let dx = if max_x > 0 { (x as f64 / max_x as f64 - 0.5).abs() } else { 0.0 };
+            let dy = if max_y > 0 { (y as f64 / max_y as f64 - 0.5).abs() } else { 0.0 };
+            let score = window_sum(x, y, ww, wh) * (1.0 - 0.1 * (dx + dy));
+            if score > best_score {
+                best_score = score;
+                best = (x, y);
+            }
+        }
+    }
+
+    let x = ((best.0 as f64 * scale).round() as u32).min(width - crop_width);
+    let y = ((best.1 as f64 * scale).round() as u32).min(height - crop_height);
+
+    Ok((x, y, crop_width, crop_height))
+}
+
+/// Build an image by inverse mapping: `map` takes the center of an output
+/// pixel and returns the corresponding point in source coordinates
+pub(crate) fn warp<F: Fn(f32, f32) -> (f32, f32)>(src: &RgbaImage, width: u32, height: u32, background: Rgba<u8>, map: F) -> RgbaImage {
+    let mut output = RgbaImage::new(width, height);
+
+    for (x, y, out) in output.enumerate_pixels_mut() {
+        let (sx, sy) = map(x as f32 + 0.5, y as f32 + 0.5);
+        *out = sample_bilinear(src, sx - 0.5, sy - 0.5, background);
+    }
+
+    output
+}
+
+/// Bilinear sample at continuous pixel coordinates; samples outside the
+/// image take the background color so edges blend smoothly into it
+pub(crate) fn sample_bilinear(src: &RgbaImage, x: f32, y: f32, background: Rgba<u8>) -> Rgba<u8> {
+    let (width, height) = (src.width() as i64, src.height() as i64);
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let (x0, y0) = (x0 as i64, y0 as i64);
+
+    if x0 < -1 || y0 < -1 || x0 >= width || y0 >= height {
+        return background;
+    }
+
+    let at = |px: i64, py: i64| -> Rgba<u8> {
+        if px < 0 || py < 0 || px >= width || py >= height {
+            background
+        } else {
+            *src.get_pixel(px as u32, py as u32)
+        }
+    };
+
+    let (p00, p10, p01, p11) = (at(x0, y0), at(x0 + 1, y0), at(x0, y0 + 1), at(x0 + 1, y0 + 1));
+    let mut out = [0u8; 4];
+    for c in 0..4 {
+        let top = p00[c] as f32 * (1.0 - fx) + p10[c] as f32 * fx;
+        let bottom = p01[c] as f32 * (1.0 - fx) + p11[c] as f32 * fx;
+        out[c] = (top * (1.0 - fy) + bottom * fy).round() as u8;
+    }
+
+    Rgba(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extend_pads_every_side() {
+        let src = RgbaImage::from_pixel(2, 1, Rgba([255, 0, 0, 255]));
+        let canvas = extend(&src, 1, 2, 3, 4, Rgba([0, 0, 0, 0])).unwrap();
+
+        assert_eq!(canvas.dimensions(), (8, 5));
+        assert_eq!(canvas.get_pixel(4, 1), &Rgba([255, 0, 0, 255]));
+        assert_eq!(canvas.get_pixel(0, 0), &Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn extend_checks_canvas_size_before_allocating() {
+        let src = RgbaImage::new(1, 1);
+        let result = extend(&src, 0, 0, 20_000, 20_000, Rgba([0, 0, 0, 0]));
+        assert!(matches!(result, Err(LimitExceeded { limit: "maxPixels", .. })));
+
+        let result = extend(&src, 0, u32::MAX, 0, u32::MAX, Rgba([0, 0, 0, 0]));
+        assert!(matches!(result, Err(LimitExceeded { limit: "maxWidth", .. })));
+    }
+}
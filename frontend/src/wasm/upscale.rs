@@ -0,0 +1,199 @@
+use wasm_bindgen::prelude::*;
+use image::{DynamicImage, Rgba, RgbaImage, imageops::FilterType};
+use crate::image_processor::{ImageProcessor, decode, encode};
+use crate::limits::{LimitExceeded, check_dimensions};
+
+/// Largest enlargement we allow, to keep output allocations sane
+const MAX_UPSCALE_FACTOR: f32 = 8.0;
+
+#[wasm_bindgen]
+impl ImageProcessor {
+    /// Enlarge an image by `factor`
+    ///
+    /// `algorithm` is `lanczos` (photos, any factor), `nearest` (hard pixel
+    /// edges, any factor) or `scalex`, an edge-directed Scale2x/Scale3x
+    /// filter for pixel art and flat logos that only supports 2x, 3x and 4x.
+    #[wasm_bindgen]
+    pub fn upscale(&self, image_data: &[u8], factor: f32, algorithm: &str) -> Result<Vec<u8>, JsValue> {
+        if !(1.0..=MAX_UPSCALE_FACTOR).contains(&factor) {
+            return Err(JsValue::from_str("Upscale factor must be between 1 and 8"));
+        }
+        let img = decode(image_data)?;
+
+        let resized = match algorithm.to_lowercase().as_str() {
+            "lanczos" | "nearest" => {
+                let filter = if algorithm.eq_ignore_ascii_case("nearest") { FilterType::Nearest } else { FilterType::Lanczos3 };
+                // `as` saturates, so a huge result fails the limits below
+                let width = (img.width() as f64 * factor as f64).round() as u32;
+                let height = (img.height() as f64 * factor as f64).round() as u32;
+                check_dimensions(width, height)?;
+                img.resize_exact(width, height, filter).to_rgba8()
+            }
+            "scalex" => {
+                let scale = match factor {
+                    1.0 => 1,
+                    2.0 => 2,
+                    3.0 => 3,
+                    4.0 => 4,
+                    _ => return Err(JsValue::from_str("scalex supports factors 2, 3 and 4")),
+                };
+                check_scaled_size(img.width(), img.height(), scale)?;
+                let rgba = img.to_rgba8();
+                match scale {
+                    2 => scale2x(&rgba),
+                    3 => scale3x(&rgba),
+                    4 => scale2x(&scale2x(&rgba)),
+                    _ => rgba,
+                }
+            }
+            _ => return Err(JsValue::from_str("Upscale algorithm must be lanczos, nearest or scalex")),
+        };
+
+        encode(&DynamicImage::ImageRgba8(resized), image::ImageOutputFormat::Png)
+    }
+}
+
+/// Check an integer enlargement against the limits before allocating it
+fn check_scaled_size(width: u32, height: u32, scale: u32) -> Result<(), LimitExceeded> {
+    // Saturating, so an overflowing side fails the width or height limit
+    let width = width.saturating_mul(scale);
+    let height = height.saturating_mul(scale);
+    check_dimensions(width, height)
+}
+
+/// Neighborhood of a pixel with edges clamped, in reading order:
+/// `[a, b, c, d, e, f, g, h, i]` with `e` the center
+fn neighborhood(src: &RgbaImage, x: u32, y: u32) -> [Rgba<u8>; 9] {
+    let (width, height) = src.dimensions();
+    let xs = [x.saturating_sub(1), x, (x + 1).min(width - 1)];
+    let ys = [y.saturating_sub(1), y, (y + 1).min(height - 1)];
+
+    let mut out = [Rgba([0, 0, 0, 0]); 9];
+    for (j, &sy) in ys.iter().enumerate() {
+        for (i, &sx) in xs.iter().enumerate() {
+            out[j * 3 + i] = *src.get_pixel(sx, sy);
+        }
+    }
+    out
+}
+
+/// Whether two colors count as the same for edge detection. The YUV
+/// thresholds are those of hqx, so anti-aliased logos still find their edges.
+fn same(p: Rgba<u8>, q: Rgba<u8>) -> bool {
+    let yuv = |px: Rgba<u8>| {
+        let (r, g, b) = (px[0] as f32, px[1] as f32, px[2] as f32);
+        (
+            0.299 * r + 0.587 * g + 0.114 * b,
+            -0.169 * r - 0.331 * g + 0.5 * b,
+            0.5 * r - 0.419 * g - 0.081 * b,
+        )
+    };
+    let (y1, u1, v1) = yuv(p);
+    let (y2, u2, v2) = yuv(q);
+
+    (y1 - y2).abs() <= 48.0 && (u1 - u2).abs() <= 7.0 && (v1 - v2).abs() <= 6.0 && (p[3] as i16 - q[3] as i16).abs() <= 32
+}
+
+/// AdvMAME2x / Scale2x
+fn scale2x(src: &RgbaImage) -> RgbaImage {
+    let (width, height) = src.dimensions();
+    let mut out = RgbaImage::new(width * 2, height * 2);
+
+    for y in 0..height {
+        for x in 0..width {
+            let [_, b, _, d, e, f, _, h, _] = neighborhood(src, x, y);
+            let (e0, e1, e2, e3) = if !same(b, h) && !same(d, f) {
+                (
+                    if same(d, b) { d } else { e },
+                    if same(b, f) { f } else { e },
+                    if same(d, h) { d } else { e },
+                    if same(h, f) { f } else { e },
+                )
+            } else {
+                (e, e, e, e)
+            };
+
+            out.put_pixel(2 * x, 2 * y, e0);
+            out.put_pixel(2 * x + 1, 2 * y, e1);
+            out.put_pixel(2 * x, 2 * y + 1, e2);
+            out.put_pixel(2 * x + 1, 2 * y + 1, e3);
+        }
+    }
+
+    out
+}
+
+/// AdvMAME3x / Scale3x
+fn scale3x(src: &RgbaImage) -> RgbaImage {
+    let (width, height) = src.dimensions();
+    let mut out = RgbaImage::new(width * 3, height * 3);
+
+    for y in 0..height {
+        for x in 0..width {
+            let [a, b, c, d, e, f, g, h, i] = neighborhood(src, x, y);
+            let mut block = [e; 9];
+
+            if !same(b, h) && !same(d, f) {
+                let db = same(d, b);
+                let bf = same(b, f);
+                let dh = same(d, h);
+                let hf = same(h, f);
+
+                if db {
+                    block[0] = d;
+                }
+                if (db && !same(e, c)) || (bf && !same(e, a)) {
+                    block[1] = b;
+                }
+                if bf {
+                    block[2] = f;
+                }
+                if (db && !same(e, g)) || (dh && !same(e, a)) {
+                    block[3] = d;
+                }
+                if (bf && !same(e, i)) || (hf && !same(e, c)) {
+                    block[5] = f;
+                }
+                if dh {
+                    block[6] = d;
+                }
+                if (dh && !same(e, i)) || (hf && !same(e, g)) {
+                    block[7] = h;
+                }
+                if hf {
+                    block[8] = f;
+                }
+            }
+
+            for (k, px) in block.iter().enumerate() {
+                out.put_pixel(3 * x + k as u32 % 3, 3 * y + k as u32 / 3, *px);
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaled_size_is_checked_before_allocating() {
+        assert_eq!(check_scaled_size(1000, 1000, 4), Ok(()));
+        assert!(matches!(check_scaled_size(5000, 5000, 3), Err(LimitExceeded { limit: "maxPixels", .. })));
+        assert!(matches!(check_scaled_size(u32::MAX / 2, 1, 3), Err(LimitExceeded { limit: "maxWidth", .. })));
+    }
+
+    #[test]
+    fn scale2x_rounds_diagonal_edges() {
+        let (black, white) = (Rgba([0, 0, 0, 255]), Rgba([255, 255, 255, 255]));
+        let src = RgbaImage::from_fn(2, 2, |x, y| if x == 0 && y == 0 { white } else if x + y == 1 { black } else { white });
+        let out = scale2x(&src);
+
+        assert_eq!(out.dimensions(), (4, 4));
+        // The white corner pixel next to two black neighbors takes their color on the shared corner
+        assert_eq!(out.get_pixel(1, 1), &black);
+        assert_eq!(out.get_pixel(0, 0), &white);
+    }
+}